@@ -1,9 +1,6 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use lzzzz::{lz4, lz4_hc, lz4f};
-use std::{
-    i32,
-    io::{Read, Write},
-};
+use std::io::{Read, Write};
 
 fn lz4_compress(level: i32, data: &[u8]) {
     let mut buf = [0u8; 4096];
@@ -112,6 +109,7 @@ fn lz4f_write_compressor(n: usize, prefs: lz4f::Preferences, data: &[u8]) {
     for _ in 0..n {
         w.write_all(data).unwrap();
     }
+    w.finish().unwrap();
 }
 
 fn lz4f_bufread_compressor(n: usize, prefs: lz4f::Preferences, data: &[u8]) {
@@ -122,6 +120,46 @@ fn lz4f_bufread_compressor(n: usize, prefs: lz4f::Preferences, data: &[u8]) {
     }
 }
 
+fn lz4f_write_compressor_small_writes(prefs: lz4f::Preferences, data: &[u8]) {
+    let mut buf = Vec::new();
+    let mut w = lz4f::WriteCompressor::new(&mut buf, prefs).unwrap();
+    for chunk in data.chunks(64) {
+        w.write_all(chunk).unwrap();
+    }
+    w.finish().unwrap();
+}
+
+fn lz4f_read_compressor(prefs: lz4f::Preferences, data: &[u8]) {
+    let mut buf = Vec::new();
+    let mut r = lz4f::ReadCompressor::new(data, prefs).unwrap();
+    r.read_to_end(&mut buf).unwrap();
+}
+
+fn lz4f_read_decompressor(buf: &mut [u8], data: &[u8]) {
+    let mut r = lz4f::ReadDecompressor::new(data).unwrap();
+    while r.read(buf).unwrap() > 0 {}
+}
+
+fn lz4f_bufread_decompressor(buf: &mut [u8], data: &[u8]) {
+    let mut r = lz4f::BufReadDecompressor::new(data).unwrap();
+    while r.read(buf).unwrap() > 0 {}
+}
+
+fn lz4f_compress_to_vec(prefs: &lz4f::Preferences, data: &[u8]) {
+    let mut buf = Vec::new();
+    lz4f::compress_to_vec(data, &mut buf, prefs).unwrap();
+}
+
+fn lz4f_pool_compress_to_vec(pool: &lz4f::ContextPool, prefs: &lz4f::Preferences, data: &[u8]) {
+    let mut buf = Vec::new();
+    pool.compress_to_vec(data, &mut buf, prefs).unwrap();
+}
+
+fn lz4f_pool_decompress(pool: &lz4f::ContextPool, data: &[u8]) {
+    let mut buf = Vec::new();
+    pool.decompress_to_vec(data, &mut buf).unwrap();
+}
+
 fn lz4f_benchmark(c: &mut Criterion) {
     let data = include_bytes!("lorem-ipsum.txt");
 
@@ -160,6 +198,75 @@ fn lz4f_benchmark(c: &mut Criterion) {
     c.bench_function("lz4f::decompress", |b| {
         b.iter(|| lz4f_decompress(black_box(&compressed)))
     });
+
+    let large = data.repeat(256);
+    let mut compressed = Vec::new();
+    lz4f::compress_to_vec(&large, &mut compressed, &Default::default()).unwrap();
+
+    c.bench_function("lz4f::decompress (large)", |b| {
+        b.iter(|| lz4f_decompress(black_box(&compressed)))
+    });
+
+    c.bench_function("lz4f::WriteCompressor (64-byte writes)", |b| {
+        let prefs = lz4f::PreferencesBuilder::new().build();
+        b.iter(|| lz4f_write_compressor_small_writes(prefs, black_box(&large)))
+    });
+
+    c.bench_function("lz4f::ReadCompressor (Default)", |b| {
+        let prefs = lz4f::PreferencesBuilder::new().build();
+        b.iter(|| lz4f_read_compressor(prefs, black_box(&large)))
+    });
+
+    c.bench_function("lz4f::ReadDecompressor (1 MiB reads)", |b| {
+        let mut buf = vec![0; 1 << 20];
+        b.iter(|| lz4f_read_decompressor(&mut buf, black_box(&compressed)))
+    });
+
+    c.bench_function("lz4f::BufReadDecompressor (1 MiB reads)", |b| {
+        let mut buf = vec![0; 1 << 20];
+        b.iter(|| lz4f_bufread_decompressor(&mut buf, black_box(&compressed)))
+    });
+
+    let small = &data[..256];
+    let pool = lz4f::ContextPool::new(1);
+
+    c.bench_function("lz4f::compress_to_vec (256 bytes)", |b| {
+        let prefs = lz4f::PreferencesBuilder::new().build();
+        b.iter(|| lz4f_compress_to_vec(&prefs, black_box(small)))
+    });
+
+    c.bench_function("lz4f::ContextPool::compress_to_vec (256 bytes)", |b| {
+        let prefs = lz4f::PreferencesBuilder::new().build();
+        b.iter(|| lz4f_pool_compress_to_vec(&pool, &prefs, black_box(small)))
+    });
+
+    c.bench_function("lz4f::compress_to_vec (256 bytes, CLEVEL_DEFAULT)", |b| {
+        let prefs = lz4f::PreferencesBuilder::new()
+            .compression_level(lz4_hc::CLEVEL_DEFAULT)
+            .build();
+        b.iter(|| lz4f_compress_to_vec(&prefs, black_box(small)))
+    });
+
+    c.bench_function(
+        "lz4f::ContextPool::compress_to_vec (256 bytes, CLEVEL_DEFAULT)",
+        |b| {
+            let prefs = lz4f::PreferencesBuilder::new()
+                .compression_level(lz4_hc::CLEVEL_DEFAULT)
+                .build();
+            b.iter(|| lz4f_pool_compress_to_vec(&pool, &prefs, black_box(small)))
+        },
+    );
+
+    let mut compressed = Vec::new();
+    lz4f::compress_to_vec(small, &mut compressed, &Default::default()).unwrap();
+
+    c.bench_function("lz4f::decompress (256 bytes)", |b| {
+        b.iter(|| lz4f_decompress(black_box(&compressed)))
+    });
+
+    c.bench_function("lz4f::ContextPool::decompress_to_vec (256 bytes)", |b| {
+        b.iter(|| lz4f_pool_decompress(&pool, black_box(&compressed)))
+    });
 }
 
 criterion_group!(lz4f_benches, lz4f_benchmark);
@@ -1,6 +1,11 @@
 fn main() -> Result<(), cc::Error> {
+    // Set by `RUSTFLAGS="--cfg lzzzz_read_buf"` on nightly to implement `Read::read_buf`.
+    println!("cargo:rustc-check-cfg=cfg(lzzzz_read_buf)");
+
     let sources = &["lz4.c", "lz4hc.c", "lz4frame.c", "xxhash.c"][..];
     let dir = std::path::Path::new("vendor/liblz4");
+    // `cc` emits `rerun-if-env-changed`, which disables the default change detection.
+    println!("cargo:rerun-if-changed={}", dir.display());
     cc::Build::new()
         .files(sources.iter().map(|file| dir.join(file)))
         .try_compile("lz4")
@@ -1,3 +1,4 @@
+use crate::lz4f;
 use std::{convert, error, fmt, io, result};
 
 /// A list specifying general categories of LZ4 error.
@@ -16,40 +17,247 @@ pub enum ErrorKind {
     CompressedDataIncomplete,
     /// Dictionary data was not consistent during the streaming decompression.
     DictionaryChangedDuringDecompression,
+    /// The frame required a dictionary that was not available.
+    DictionaryNotFound,
+    /// The decompression exceeded the configured limits.
+    DecompressionLimitExceeded,
+    /// liblz4 reported an LZ4F error.
+    Lz4f(lz4f::ErrorKind),
+}
+
+impl ErrorKind {
+    /// Returns the `io::ErrorKind` which best describes this kind.
+    fn io_kind(self) -> io::ErrorKind {
+        use lz4f::ErrorKind as F;
+        match self {
+            Self::InitializationFailed | Self::Lz4f(F::AllocationFailed) => {
+                io::ErrorKind::OutOfMemory
+            }
+            Self::CompressionFailed | Self::Lz4f(F::DstMaxSizeTooSmall) => io::ErrorKind::WriteZero,
+            Self::CompressedDataIncomplete | Self::Lz4f(F::FrameHeaderIncomplete) => {
+                io::ErrorKind::UnexpectedEof
+            }
+            Self::DecompressionFailed
+            | Self::FrameHeaderInvalid
+            | Self::DecompressionLimitExceeded
+            | Self::Lz4f(
+                F::MaxBlockSizeInvalid
+                | F::HeaderVersionWrong
+                | F::BlockChecksumInvalid
+                | F::ReservedFlagSet
+                | F::FrameTypeUnknown
+                | F::FrameSizeWrong
+                | F::DecompressionFailed
+                | F::HeaderChecksumInvalid
+                | F::ContentChecksumInvalid,
+            ) => io::ErrorKind::InvalidData,
+            Self::DictionaryChangedDuringDecompression
+            | Self::DictionaryNotFound
+            | Self::Lz4f(
+                F::BlockModeInvalid
+                | F::ContentChecksumFlagInvalid
+                | F::CompressionLevelInvalid
+                | F::SrcSizeTooLarge
+                | F::ParameterNull,
+            ) => io::ErrorKind::InvalidInput,
+            Self::Lz4f(
+                F::Generic
+                | F::SrcPtrWrong
+                | F::FrameDecodingAlreadyStarted
+                | F::CompressionStateUninitialized
+                | F::IoWrite
+                | F::IoRead,
+            ) => io::ErrorKind::Other,
+        }
+    }
+}
+
+impl convert::From<lz4f::ErrorKind> for ErrorKind {
+    fn from(kind: lz4f::ErrorKind) -> Self {
+        Self::Lz4f(kind)
+    }
 }
 
 impl fmt::Display for ErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> result::Result<(), fmt::Error> {
-        <Self as fmt::Debug>::fmt(self, f)
+        match self {
+            Self::Lz4f(kind) => <lz4f::ErrorKind as fmt::Display>::fmt(kind, f),
+            _ => <Self as fmt::Debug>::fmt(self, f),
+        }
     }
 }
 
-/// The error type for LZ4 operations.
+/// The error type for LZ4, LZ4_HC and LZ4F operations.
+///
+/// Besides the [`ErrorKind`], decompressors record where the error occurred
+/// when they know it. Converting into `io::Error` picks a matching `io::ErrorKind`,
+/// and the original error is available through `io::Error::get_ref`.
+///
+/// [`ErrorKind`]: enum.ErrorKind.html
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::{lz4f, ErrorKind};
+/// use std::io::prelude::*;
+///
+/// let mut r = lz4f::ReadDecompressor::new(&b"garbage"[..])?;
+/// let err = r.read_to_end(&mut Vec::new()).unwrap_err();
+/// assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+///
+/// let err = err
+///     .get_ref()
+///     .unwrap()
+///     .downcast_ref::<lzzzz::Error>()
+///     .unwrap();
+/// assert_eq!(err.kind(), ErrorKind::FrameHeaderInvalid);
+/// assert_eq!(err.input_offset(), Some(0));
+/// # Ok::<(), std::io::Error>(())
+/// ```
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Error {
     kind: ErrorKind,
+    input_offset: Option<u64>,
+    output_offset: Option<u64>,
+    frame_index: Option<u64>,
+    block_index: Option<u64>,
+    checksums: Option<(u32, u32)>,
 }
 
 impl Error {
     pub(crate) const fn new(kind: ErrorKind) -> Self {
-        Self { kind }
+        Self {
+            kind,
+            input_offset: None,
+            output_offset: None,
+            frame_index: None,
+            block_index: None,
+            checksums: None,
+        }
+    }
+
+    pub(crate) const fn lz4f(kind: lz4f::ErrorKind) -> Self {
+        Self::new(ErrorKind::Lz4f(kind))
+    }
+
+    pub(crate) const fn with_offsets(mut self, input: u64, output: u64) -> Self {
+        self.input_offset = Some(input);
+        self.output_offset = Some(output);
+        self
+    }
+
+    pub(crate) const fn with_frame_index(mut self, index: u64) -> Self {
+        self.frame_index = Some(index);
+        self
+    }
+
+    pub(crate) const fn with_block_index(mut self, index: u64) -> Self {
+        self.block_index = Some(index);
+        self
+    }
+
+    pub(crate) const fn with_checksums(mut self, expected: u32, actual: u32) -> Self {
+        self.checksums = Some((expected, actual));
+        self
     }
 
     /// Returns the corresponding `ErrorKind` for this error.
     pub const fn kind(self) -> ErrorKind {
         self.kind
     }
+
+    /// Returns the offset into the compressed input where the failing data starts.
+    ///
+    /// Decompressors driven by liblz4 report the start of the data passed
+    /// to the failing call, so the error lies at or after this offset.
+    pub const fn input_offset(self) -> Option<u64> {
+        self.input_offset
+    }
+
+    /// Returns the number of bytes decompressed before the error.
+    pub const fn output_offset(self) -> Option<u64> {
+        self.output_offset
+    }
+
+    /// Returns the zero-based index of the frame where decoding failed.
+    pub const fn frame_index(self) -> Option<u64> {
+        self.frame_index
+    }
+
+    /// Returns the zero-based index of the block where decoding failed.
+    ///
+    /// Checksum errors name the failing block itself. Other errors from the
+    /// decompressors driven by liblz4 name the block in progress at the start
+    /// of the failing call, like [`input_offset`](Self::input_offset).
+    /// `lz4f::FrameDecoder` and the block-format APIs leave this empty.
+    pub const fn block_index(self) -> Option<u64> {
+        self.block_index
+    }
+
+    /// Returns the checksum recorded in the data, for checksum errors.
+    ///
+    /// Header checksums always carry it. Block and content checksums carry it
+    /// from `lz4f::decompress_to_vec`, the LZ4F stream decompressors and
+    /// `lz4f::SalvageDecompressor`, but not from `lz4f::FrameDecoder`,
+    /// which leaves the check to liblz4.
+    pub const fn expected_checksum(self) -> Option<u32> {
+        match self.checksums {
+            Some((expected, _)) => Some(expected),
+            None => None,
+        }
+    }
+
+    /// Returns the checksum computed from the data, for checksum errors.
+    ///
+    /// Filled whenever [`expected_checksum`](Self::expected_checksum) is.
+    pub const fn actual_checksum(self) -> Option<u32> {
+        match self.checksums {
+            Some((_, actual)) => Some(actual),
+            None => None,
+        }
+    }
+}
+
+impl convert::From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Self::new(kind)
+    }
 }
 
 impl convert::From<Error> for io::Error {
     fn from(err: Error) -> Self {
-        Self::new(io::ErrorKind::Other, err)
+        Self::new(err.kind.io_kind(), err)
     }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> result::Result<(), fmt::Error> {
-        <ErrorKind as fmt::Display>::fmt(&self.kind, f)
+        <ErrorKind as fmt::Display>::fmt(&self.kind, f)?;
+        let context = [
+            ("input offset", self.input_offset),
+            ("output offset", self.output_offset),
+            ("frame", self.frame_index),
+            ("block", self.block_index),
+        ];
+        let mut sep = " (";
+        for (name, value) in context {
+            if let Some(value) = value {
+                write!(f, "{}{} {}", sep, name, value)?;
+                sep = ", ";
+            }
+        }
+        if let Some((expected, actual)) = self.checksums {
+            write!(
+                f,
+                "{}expected checksum {:#010x}, actual {:#010x}",
+                sep, expected, actual
+            )?;
+            sep = ", ";
+        }
+        if sep == ", " {
+            f.write_str(")")?;
+        }
+        Ok(())
     }
 }
 
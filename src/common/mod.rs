@@ -1,9 +1,33 @@
 mod api;
 mod binding;
 mod error;
+mod ring;
 
 pub use api::{version_number, version_string};
 pub use error::{Error, ErrorKind, Result};
+pub(crate) use ring::RingBuffer;
 
 pub(crate) const DEFAULT_BUF_SIZE: usize = 8 * 1024;
 pub(crate) const DICTIONARY_SIZE: usize = 64 * 1024;
+
+/// Frees the compression and decompression states cached by the current thread.
+///
+/// [`lz4::compress`], [`lz4_hc::compress`], [`lz4f::decompress_to_vec`] and
+/// similar functions lazily allocate a state per thread and keep it for reuse.
+/// Call this before a short-lived thread exits, or when it goes idle,
+/// to release that memory early. The states are recreated on the next call.
+///
+/// To manage the memory yourself, use [`lz4::State`], [`lz4_hc::State`] or
+/// [`lz4f::DecompressionState`] directly.
+///
+/// [`lz4::compress`]: lz4/fn.compress.html
+/// [`lz4_hc::compress`]: lz4_hc/fn.compress.html
+/// [`lz4f::decompress_to_vec`]: lz4f/fn.decompress_to_vec.html
+/// [`lz4::State`]: lz4/struct.State.html
+/// [`lz4_hc::State`]: lz4_hc/struct.State.html
+/// [`lz4f::DecompressionState`]: lz4f/struct.DecompressionState.html
+pub fn free_thread_local_states() {
+    crate::lz4::State::free_thread_local();
+    crate::lz4_hc::State::free_thread_local();
+    crate::lz4f::DecompressionState::free_thread_local();
+}
@@ -0,0 +1,98 @@
+use super::{Error, ErrorKind, Result};
+use std::cmp;
+
+enum Storage<'a> {
+    Owned(Vec<u8>),
+    Borrowed(&'a mut [u8]),
+}
+
+/// A contiguous buffer that holds the messages of a block stream back to back,
+/// together with the history the next message may refer to.
+///
+/// The first `history_size` bytes of the buffer are reserved for the history,
+/// and the rest bounds the length of a message.
+///
+/// The buffer is never exposed except for the slot of the next message and
+/// the last message, so the history stays unmodified at the same address between calls.
+pub(crate) struct RingBuffer<'a> {
+    buf: Storage<'a>,
+    history_size: usize,
+    pos: usize,
+    last_pos: usize,
+    last_start: usize,
+}
+
+impl<'a> RingBuffer<'a> {
+    pub fn new(history_size: usize, len: usize) -> Self {
+        Self::from_storage(Storage::Owned(vec![0; len]), history_size)
+    }
+
+    pub fn with_buffer(history_size: usize, buf: &'a mut [u8]) -> Result<Self> {
+        if buf.len() <= history_size {
+            return Err(Error::new(ErrorKind::InitializationFailed));
+        }
+        Ok(Self::from_storage(Storage::Borrowed(buf), history_size))
+    }
+
+    fn from_storage(buf: Storage<'a>, history_size: usize) -> Self {
+        Self {
+            buf,
+            history_size,
+            pos: 0,
+            last_pos: 0,
+            last_start: 0,
+        }
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        match &self.buf {
+            Storage::Owned(buf) => buf,
+            Storage::Borrowed(buf) => buf,
+        }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        match &mut self.buf {
+            Storage::Owned(buf) => buf,
+            Storage::Borrowed(buf) => buf,
+        }
+    }
+
+    pub fn max_message_size(&self) -> usize {
+        self.as_slice().len() - self.history_size
+    }
+
+    /// Returns the slot for the next message of `len` bytes, wrapping around
+    /// to the start of the buffer if the rest of it is too short.
+    ///
+    /// Returns `None` if `len` exceeds the maximum message size.
+    pub fn next_slot(&mut self, len: usize) -> Option<&mut [u8]> {
+        if len > self.max_message_size() {
+            return None;
+        }
+        let start = if self.pos + len > self.as_slice().len() {
+            0
+        } else {
+            self.pos
+        };
+        self.last_pos = self.pos;
+        self.last_start = start;
+        self.pos = start + len;
+        Some(&mut self.as_mut_slice()[start..][..len])
+    }
+
+    /// Shortens the last message to `len` bytes, so that the next one follows it directly.
+    pub fn truncate_last(&mut self, len: usize) {
+        self.pos = cmp::min(self.pos, self.last_start + len);
+    }
+
+    /// Drops the last message, as if `next_slot` had not been called.
+    pub fn discard_last(&mut self) {
+        self.pos = self.last_pos;
+    }
+
+    /// Returns the last message.
+    pub fn last(&self) -> &[u8] {
+        &self.as_slice()[self.last_start..self.pos]
+    }
+}
@@ -0,0 +1,8 @@
+//! File formats built on LZ4.
+//!
+//! - [`mozlz4`]: Mozilla's `mozlz4` / `jsonlz4` files, used by Firefox for
+//!   session and bookmark backups.
+//!
+//! [`mozlz4`]: mozlz4/index.html
+
+pub mod mozlz4;
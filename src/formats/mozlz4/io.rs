@@ -0,0 +1,289 @@
+use super::{check_size, compress_to_vec, decode_header, decompress_body, HEADER_SIZE};
+use crate::{lz4, lz4f::DecompressionLimits, Error, ErrorKind};
+use std::{
+    cmp, fmt,
+    io::{self, BufRead, Read, Write},
+};
+
+/// The [`Write`]-based `mozlz4` compressor.
+///
+/// The data is buffered until [`finish`] or [`try_finish`] compresses it
+/// and writes out the file. Dropping the compressor also writes out the file,
+/// but ignores errors.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::formats::mozlz4;
+/// use std::io::prelude::*;
+///
+/// let mut w = mozlz4::WriteCompressor::new(Vec::new());
+/// w.write_all(br#"{"version":["sessionrestore",1]}"#)?;
+/// let file = w.finish()?;
+///
+/// assert!(file.starts_with(mozlz4::MAGIC));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
+/// [`finish`]: #method.finish
+/// [`try_finish`]: #method.try_finish
+pub struct WriteCompressor<W: Write> {
+    inner: Option<W>,
+    buf: Vec<u8>,
+    comp: Option<Vec<u8>>,
+    consumed: usize,
+    acc: i32,
+}
+
+impl<W: Write> WriteCompressor<W> {
+    /// Creates a new `WriteCompressor<W>`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            inner: Some(writer),
+            buf: Vec::new(),
+            comp: None,
+            consumed: 0,
+            acc: lz4::ACC_LEVEL_DEFAULT,
+        }
+    }
+
+    /// Sets the acceleration factor.
+    ///
+    /// See [`lz4`] for details.
+    ///
+    /// [`lz4`]: ../../lz4/index.html#acceleration-factor
+    pub fn set_acceleration(&mut self, acc: i32) {
+        self.acc = acc;
+    }
+
+    /// Returns a mutable reference to the writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner.as_mut().unwrap()
+    }
+
+    /// Returns a shared reference to the writer.
+    pub fn get_ref(&self) -> &W {
+        self.inner.as_ref().unwrap()
+    }
+
+    /// Compresses the data and writes out the file.
+    ///
+    /// On error, the unwritten part of the file stays in the buffer, and calling this again
+    /// resumes from there. Once the data is compressed, further writes fail with
+    /// [`ErrorKind::CompressionFailed`].
+    ///
+    /// [`ErrorKind::CompressionFailed`]: ../../enum.ErrorKind.html#variant.CompressionFailed
+    pub fn try_finish(&mut self) -> io::Result<()> {
+        if self.comp.is_none() {
+            let mut comp = Vec::new();
+            compress_to_vec(&self.buf, &mut comp, self.acc)?;
+            self.buf = Vec::new();
+            self.comp = Some(comp);
+        }
+
+        let comp = self.comp.as_ref().unwrap();
+        let device = self.inner.as_mut().unwrap();
+        while self.consumed < comp.len() {
+            match device.write(&comp[self.consumed..])? {
+                0 => return Err(io::ErrorKind::WriteZero.into()),
+                len => self.consumed += len,
+            }
+        }
+        device.flush()
+    }
+
+    /// Compresses the data, writes out the file and returns the ownership of the writer.
+    ///
+    /// See [`try_finish`] for the errors. Use it instead to keep the compressor on error.
+    ///
+    /// [`try_finish`]: #method.try_finish
+    pub fn finish(mut self) -> io::Result<W> {
+        self.try_finish()?;
+        Ok(self.inner.take().unwrap())
+    }
+}
+
+impl<W> fmt::Debug for WriteCompressor<W>
+where
+    W: Write + fmt::Debug,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("WriteCompressor")
+            .field("writer", &self.inner)
+            .finish()
+    }
+}
+
+impl<W: Write> Write for WriteCompressor<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Data over the size of an LZ4 block is rejected before it is buffered.
+        if self.comp.is_some() || lz4::max_compressed_size(self.buf.len() + buf.len()) == 0 {
+            return Err(Error::new(ErrorKind::CompressionFailed).into());
+        }
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // The data cannot be written out until the compressor is finished.
+        self.inner.as_mut().unwrap().flush()
+    }
+}
+
+impl<W: Write> Drop for WriteCompressor<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.try_finish();
+        }
+    }
+}
+
+/// The [`Read`]-based `mozlz4` decompressor.
+///
+/// The whole file is read and decompressed on the first read.
+/// It fails with [`ErrorKind::DecompressionFailed`] if the reader has more data
+/// than the compressed block may take.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::{formats::mozlz4, lz4, lz4f::DecompressionLimitsBuilder};
+/// use std::io::prelude::*;
+///
+/// let mut file = Vec::new();
+/// mozlz4::compress_to_vec(b"Hello world!", &mut file, lz4::ACC_LEVEL_DEFAULT)?;
+///
+/// let mut r = mozlz4::ReadDecompressor::new(file.as_slice());
+/// r.set_limits(
+///     DecompressionLimitsBuilder::new()
+///         .max_output_size(16 << 20)
+///         .build(),
+/// );
+///
+/// let mut buf = String::new();
+/// r.read_to_string(&mut buf)?;
+/// assert_eq!(buf, "Hello world!");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// [`Read`]: https://doc.rust-lang.org/std/io/trait.Read.html
+/// [`ErrorKind::DecompressionFailed`]: ../../enum.ErrorKind.html#variant.DecompressionFailed
+pub struct ReadDecompressor<R: Read> {
+    inner: R,
+    src: Vec<u8>,
+    buf: Vec<u8>,
+    consumed: usize,
+    limits: DecompressionLimits,
+    done: bool,
+}
+
+impl<R: Read> ReadDecompressor<R> {
+    /// Creates a new `ReadDecompressor<R>`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            inner: reader,
+            src: Vec::new(),
+            buf: Vec::new(),
+            consumed: 0,
+            limits: DecompressionLimits::default(),
+            done: false,
+        }
+    }
+
+    /// Sets the decompression limits.
+    ///
+    /// A file whose header announces more than the maximum output size of `limits`
+    /// fails with [`ErrorKind::DecompressionLimitExceeded`] before its body is read.
+    /// The other limits do not apply.
+    ///
+    /// [`ErrorKind::DecompressionLimitExceeded`]: ../../enum.ErrorKind.html#variant.DecompressionLimitExceeded
+    pub fn set_limits(&mut self, limits: DecompressionLimits) {
+        self.limits = limits;
+    }
+
+    /// Returns ownership of the reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns a mutable reference to the reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Returns a shared reference to the reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Reads and decompresses the whole file.
+    ///
+    /// On error, the data read so far is kept, and calling this again resumes from there.
+    fn load(&mut self) -> io::Result<()> {
+        if self.done {
+            return Ok(());
+        }
+
+        if self.src.len() < HEADER_SIZE {
+            let len = HEADER_SIZE - self.src.len();
+            (&mut self.inner)
+                .take(len as u64)
+                .read_to_end(&mut self.src)?;
+        }
+        let (size, _) = decode_header(&self.src)?;
+        if size > self.limits.max_output_size() {
+            return Err(Error::new(ErrorKind::DecompressionLimitExceeded).into());
+        }
+
+        // One byte over the maximum block size tells an overlong body.
+        let max_len = HEADER_SIZE + lz4::max_compressed_size(size) + 1;
+        let len = max_len.saturating_sub(self.src.len());
+        (&mut self.inner)
+            .take(len as u64)
+            .read_to_end(&mut self.src)?;
+        if self.src.len() >= max_len {
+            return Err(Error::new(ErrorKind::DecompressionFailed).into());
+        }
+
+        let (_, body) = decode_header(&self.src)?;
+        check_size(size, body.len(), &self.limits)?;
+        self.buf.resize(size, 0);
+        decompress_body(body, &mut self.buf)?;
+        self.src = Vec::new();
+        self.done = true;
+        Ok(())
+    }
+}
+
+impl<R> fmt::Debug for ReadDecompressor<R>
+where
+    R: Read + fmt::Debug,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("ReadDecompressor")
+            .field("reader", &self.inner)
+            .finish()
+    }
+}
+
+impl<R: Read> Read for ReadDecompressor<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let src = self.fill_buf()?;
+        let len = cmp::min(buf.len(), src.len());
+        buf[..len].copy_from_slice(&src[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<R: Read> BufRead for ReadDecompressor<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.load()?;
+        Ok(&self.buf[self.consumed..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.consumed = cmp::min(self.consumed + amt, self.buf.len());
+    }
+}
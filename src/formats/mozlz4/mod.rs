@@ -0,0 +1,242 @@
+//! Mozilla `mozlz4` format.
+//!
+//! Firefox stores session and bookmark backups (`.jsonlz4`, `.mozlz4`, `.baklz4`)
+//! in this format: the 8-byte magic number `mozLz40\0`, the size of the original data
+//! as a 32-bit little-endian integer, and the data compressed as a single LZ4 block.
+//!
+//! Since the whole file is a single block, [`WriteCompressor`] and [`ReadDecompressor`]
+//! hold the whole original data in memory.
+//!
+//! # Size limits
+//!
+//! Compression fails with [`ErrorKind::CompressionFailed`] if the data is larger than
+//! an LZ4 block can hold (about 2 GiB).
+//!
+//! On decompression, the size in the header is checked before the output is allocated.
+//! A size which the compressed block cannot expand to fails with
+//! [`ErrorKind::DecompressionFailed`], and a size over the maximum output size of the
+//! [`DecompressionLimits`] given to [`decompress_to_vec_with_limits`] or
+//! [`ReadDecompressor::set_limits`] fails with [`ErrorKind::DecompressionLimitExceeded`].
+//! The other limits describe LZ4F frames and do not apply.
+//!
+//! # Example
+//!
+//! ```
+//! use lzzzz::{formats::mozlz4, lz4};
+//!
+//! let data = br#"{"version":["sessionrestore",1],"windows":[]}"#;
+//!
+//! let mut comp = Vec::new();
+//! mozlz4::compress_to_vec(data, &mut comp, lz4::ACC_LEVEL_DEFAULT)?;
+//! assert!(comp.starts_with(mozlz4::MAGIC));
+//!
+//! let mut decomp = Vec::new();
+//! mozlz4::decompress_to_vec(&comp, &mut decomp)?;
+//! assert_eq!(decomp, &data[..]);
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! [`WriteCompressor`]: struct.WriteCompressor.html
+//! [`ReadDecompressor`]: struct.ReadDecompressor.html
+//! [`DecompressionLimits`]: ../../lz4f/struct.DecompressionLimits.html
+//! [`ReadDecompressor::set_limits`]: struct.ReadDecompressor.html#method.set_limits
+//! [`decompress_to_vec_with_limits`]: fn.decompress_to_vec_with_limits.html
+//! [`ErrorKind::CompressionFailed`]: ../../enum.ErrorKind.html#variant.CompressionFailed
+//! [`ErrorKind::DecompressionFailed`]: ../../enum.ErrorKind.html#variant.DecompressionFailed
+//! [`ErrorKind::DecompressionLimitExceeded`]: ../../enum.ErrorKind.html#variant.DecompressionLimitExceeded
+
+mod io;
+
+pub use io::{ReadDecompressor, WriteCompressor};
+
+use crate::{lz4, lz4f::DecompressionLimits, Error, ErrorKind, Result};
+use std::cmp;
+
+/// The magic number at the start of a `mozlz4` file.
+pub const MAGIC: &[u8; 8] = b"mozLz40\0";
+
+/// The length of the header, which consists of the magic number and the original size.
+pub const HEADER_SIZE: usize = 12;
+
+/// A byte of an LZ4 block expands to 255 bytes at most.
+const MAX_RATIO: usize = 255;
+
+/// Calculates the maximum size of a `mozlz4` file.
+///
+/// If `original_size` is too large to compress, this returns `0`.
+#[must_use]
+pub const fn max_compressed_size(original_size: usize) -> usize {
+    match lz4::max_compressed_size(original_size) {
+        0 => 0,
+        len => HEADER_SIZE + len,
+    }
+}
+
+/// Compresses data into a `mozlz4` file.
+///
+/// Returns the number of bytes written into the destination buffer.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::{formats::mozlz4, lz4};
+///
+/// let data = b"The quick brown fox jumps over the lazy dog.";
+/// let mut buf = [0u8; 256];
+///
+/// let len = mozlz4::compress(data, &mut buf, lz4::ACC_LEVEL_DEFAULT)?;
+/// assert_eq!(mozlz4::decompressed_size(&buf[..len])?, data.len());
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn compress(src: &[u8], dst: &mut [u8], acc: i32) -> Result<usize> {
+    if dst.len() < HEADER_SIZE {
+        return Err(Error::new(ErrorKind::CompressionFailed));
+    }
+    let (header, body) = dst.split_at_mut(HEADER_SIZE);
+    let len = if src.is_empty() {
+        // liblz4 encodes empty data as a single token.
+        *body
+            .first_mut()
+            .ok_or_else(|| Error::new(ErrorKind::CompressionFailed))? = 0;
+        1
+    } else {
+        lz4::compress(src, body, acc)?
+    };
+    header.copy_from_slice(&encode_header(src.len()));
+    Ok(HEADER_SIZE + len)
+}
+
+/// Appends a `mozlz4` file to `Vec<u8>`.
+///
+/// Returns the number of bytes appended to the given `Vec<u8>`.
+pub fn compress_to_vec(src: &[u8], dst: &mut Vec<u8>, acc: i32) -> Result<usize> {
+    let orig_len = dst.len();
+    dst.extend_from_slice(&encode_header(src.len()));
+    let result = if src.is_empty() {
+        dst.push(0);
+        Ok(1)
+    } else {
+        lz4::compress_to_vec(src, dst, acc)
+    };
+    match result {
+        Ok(len) => Ok(HEADER_SIZE + len),
+        Err(err) => {
+            dst.truncate(orig_len);
+            Err(err)
+        }
+    }
+}
+
+/// Returns the size of the original data stored in the header of a `mozlz4` file.
+///
+/// Fails with [`ErrorKind::FrameHeaderInvalid`] if `src` does not start with [`MAGIC`],
+/// or with [`ErrorKind::CompressedDataIncomplete`] if `src` is shorter than the header.
+///
+/// [`MAGIC`]: constant.MAGIC.html
+/// [`ErrorKind::FrameHeaderInvalid`]: ../../enum.ErrorKind.html#variant.FrameHeaderInvalid
+/// [`ErrorKind::CompressedDataIncomplete`]: ../../enum.ErrorKind.html#variant.CompressedDataIncomplete
+pub fn decompressed_size(src: &[u8]) -> Result<usize> {
+    decode_header(src).map(|(size, _)| size)
+}
+
+/// Decompresses a `mozlz4` file.
+///
+/// The destination slice must be at least as long as [`decompressed_size`].
+///
+/// Returns the number of bytes written into the destination buffer.
+///
+/// [`decompressed_size`]: fn.decompressed_size.html
+pub fn decompress(src: &[u8], dst: &mut [u8]) -> Result<usize> {
+    let (size, body) = decode_header(src)?;
+    if dst.len() < size {
+        return Err(Error::new(ErrorKind::DecompressionFailed));
+    }
+    decompress_body(body, &mut dst[..size])
+}
+
+/// Decompresses a `mozlz4` file and appends the data to `Vec<u8>`.
+///
+/// Returns the number of bytes appended to the given `Vec<u8>`.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::formats::mozlz4;
+///
+/// let file = b"mozLz40\0\x05\0\0\0\x50hello";
+///
+/// let mut buf = Vec::new();
+/// mozlz4::decompress_to_vec(file, &mut buf)?;
+/// assert_eq!(buf, b"hello");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn decompress_to_vec(src: &[u8], dst: &mut Vec<u8>) -> Result<usize> {
+    decompress_to_vec_with_limits(src, dst, &DecompressionLimits::default())
+}
+
+/// Decompresses a `mozlz4` file within `limits`, and appends the data to `Vec<u8>`.
+///
+/// Fails with [`ErrorKind::DecompressionLimitExceeded`] before allocating the output
+/// if the header announces more than the maximum output size of `limits`.
+/// The other limits do not apply.
+///
+/// Returns the number of bytes appended to the given `Vec<u8>`.
+///
+/// [`ErrorKind::DecompressionLimitExceeded`]: ../../enum.ErrorKind.html#variant.DecompressionLimitExceeded
+pub fn decompress_to_vec_with_limits(
+    src: &[u8],
+    dst: &mut Vec<u8>,
+    limits: &DecompressionLimits,
+) -> Result<usize> {
+    let (size, body) = decode_header(src)?;
+    check_size(size, body.len(), limits)?;
+    let orig_len = dst.len();
+    dst.resize(orig_len + size, 0);
+    let result = decompress_body(body, &mut dst[orig_len..]);
+    if result.is_err() {
+        dst.truncate(orig_len);
+    }
+    result
+}
+
+fn encode_header(size: usize) -> [u8; HEADER_SIZE] {
+    let mut header = [0; HEADER_SIZE];
+    header[..MAGIC.len()].copy_from_slice(MAGIC);
+    // Data which does not fit in u32 fails to compress anyway.
+    header[MAGIC.len()..].copy_from_slice(&(size as u32).to_le_bytes());
+    header
+}
+
+/// Returns the original size and the compressed block.
+fn decode_header(src: &[u8]) -> Result<(usize, &[u8])> {
+    let magic = &src[..cmp::min(src.len(), MAGIC.len())];
+    if !MAGIC.starts_with(magic) {
+        return Err(Error::new(ErrorKind::FrameHeaderInvalid));
+    }
+    if src.len() < HEADER_SIZE {
+        return Err(Error::new(ErrorKind::CompressedDataIncomplete));
+    }
+    let mut size = [0; 4];
+    size.copy_from_slice(&src[MAGIC.len()..HEADER_SIZE]);
+    Ok((u32::from_le_bytes(size) as usize, &src[HEADER_SIZE..]))
+}
+
+/// Checks the original size in the header before allocating the output.
+fn check_size(size: usize, body_len: usize, limits: &DecompressionLimits) -> Result<()> {
+    if size > limits.max_output_size() {
+        Err(Error::new(ErrorKind::DecompressionLimitExceeded))
+    } else if size > body_len.saturating_mul(MAX_RATIO) {
+        Err(Error::new(ErrorKind::DecompressionFailed))
+    } else {
+        Ok(())
+    }
+}
+
+/// Decompresses the block into `dst`, which is as long as the original size.
+fn decompress_body(body: &[u8], dst: &mut [u8]) -> Result<usize> {
+    let len = lz4::decompress(body, dst)?;
+    if len != dst.len() {
+        return Err(Error::new(ErrorKind::DecompressionFailed));
+    }
+    Ok(len)
+}
@@ -3,11 +3,15 @@
 #![deny(unsafe_code)]
 #![deny(clippy::all)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
+#![cfg_attr(lzzzz_read_buf, feature(read_buf, core_io_borrowed_buf))]
 
 mod common;
 
+pub mod formats;
 pub mod lz4;
 pub mod lz4_hc;
 pub mod lz4f;
+pub mod tune;
+pub mod xxhash;
 
 pub use common::*;
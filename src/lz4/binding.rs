@@ -59,6 +59,7 @@ extern "C" {
     pub fn LZ4_createStream() -> *mut LZ4Stream;
     pub fn LZ4_freeStream(ptr: *mut LZ4Stream) -> c_int;
     pub fn LZ4_initStream(buffer: *mut c_void, size: usize) -> *mut LZ4Stream;
+    pub fn LZ4_resetStream_fast(ptr: *mut LZ4Stream);
     pub fn LZ4_loadDict(ptr: *mut LZ4Stream, dictionary: *const c_char, dict_size: c_int) -> c_int;
     pub fn LZ4_saveDict(
         ptr: *mut LZ4Stream,
@@ -87,4 +88,5 @@ extern "C" {
         src_size: c_int,
         dst_capacity: c_int,
     ) -> c_int;
+    pub fn LZ4_decoderRingBufferSize(max_block_size: c_int) -> c_int;
 }
@@ -3,11 +3,7 @@
 use super::super::binding;
 use crate::{Error, ErrorKind, Result};
 
-use std::{
-    cell::RefCell,
-    ops::Deref,
-    os::raw::{c_char, c_int, c_void},
-};
+use std::os::raw::{c_char, c_int, c_void};
 
 const LZ4_MAX_INPUT_SIZE: usize = 0x7E00_0000;
 
@@ -19,44 +15,6 @@ pub const fn size_of_state() -> usize {
     binding::LZ4_STREAMSIZE
 }
 
-pub fn compress_fast_ext_state(
-    state: &mut [u8],
-    src: &[u8],
-    dst: *mut u8,
-    dst_len: usize,
-    acceleration: i32,
-) -> usize {
-    unsafe {
-        binding::LZ4_compress_fast_extState(
-            state.as_mut_ptr() as *mut c_void,
-            src.as_ptr() as *const c_char,
-            dst as *mut i8,
-            src.len() as c_int,
-            dst_len as c_int,
-            acceleration as c_int,
-        ) as usize
-    }
-}
-
-pub fn compress_fast_ext_state_fast_reset(
-    state: &mut [u8],
-    src: &[u8],
-    dst: *mut u8,
-    dst_len: usize,
-    acceleration: i32,
-) -> usize {
-    unsafe {
-        binding::LZ4_compress_fast_extState_fastReset(
-            state.as_mut_ptr() as *mut c_void,
-            src.as_ptr() as *const c_char,
-            dst as *mut i8,
-            src.len() as c_int,
-            dst_len as c_int,
-            acceleration as c_int,
-        ) as usize
-    }
-}
-
 pub fn decompress_safe(src: &[u8], dst: &mut [u8]) -> Result<usize> {
     let result = unsafe {
         binding::LZ4_decompress_safe(
@@ -108,42 +66,54 @@ pub fn decompress_safe_using_dict(src: &[u8], dst: &mut [u8], dict: &[u8]) -> Re
     }
 }
 
-#[derive(Clone)]
-pub struct ExtState(RefCell<Box<[u8]>>);
+pub struct ExtState {
+    buf: Box<[u64]>,
+    initialized: bool,
+}
 
 impl ExtState {
-    fn new() -> Self {
-        let size = size_of_state() + 1;
-        Self(RefCell::new(vec![0; size].into_boxed_slice()))
-    }
-
-    pub fn with<F, R>(f: F) -> R
-    where
-        F: FnOnce(&Self, bool) -> R,
-    {
-        EXT_STATE.with(|state| {
-            let reset = {
-                let mut state = state.borrow_mut();
-                let last = state.len() - 1;
-                if state[last] == 0 {
-                    state[last] = 1;
-                    false
-                } else {
-                    true
-                }
-            };
-
-            (f)(state, reset)
+    pub fn new() -> Result<Self> {
+        let len = size_of_state().div_ceil(8);
+        let mut buf = Vec::new();
+        buf.try_reserve_exact(len)
+            .map_err(|_| Error::new(ErrorKind::InitializationFailed))?;
+        buf.resize(len, 0);
+        Ok(Self {
+            buf: buf.into_boxed_slice(),
+            initialized: false,
         })
     }
-}
 
-impl Deref for ExtState {
-    type Target = RefCell<Box<[u8]>>;
-
-    fn deref(&self) -> &Self::Target {
-        &self.0
+    pub fn compress_fast(
+        &mut self,
+        src: &[u8],
+        dst: *mut u8,
+        dst_len: usize,
+        acceleration: i32,
+    ) -> usize {
+        let state = self.buf.as_mut_ptr() as *mut c_void;
+        let len = unsafe {
+            if self.initialized {
+                binding::LZ4_compress_fast_extState_fastReset(
+                    state,
+                    src.as_ptr() as *const c_char,
+                    dst as *mut c_char,
+                    src.len() as c_int,
+                    dst_len as c_int,
+                    acceleration as c_int,
+                )
+            } else {
+                binding::LZ4_compress_fast_extState(
+                    state,
+                    src.as_ptr() as *const c_char,
+                    dst as *mut c_char,
+                    src.len() as c_int,
+                    dst_len as c_int,
+                    acceleration as c_int,
+                )
+            }
+        };
+        self.initialized = true;
+        len as usize
     }
 }
-
-thread_local!(static EXT_STATE: ExtState = ExtState::new());
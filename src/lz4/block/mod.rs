@@ -1,8 +1,10 @@
 mod api;
+mod store;
+
+pub use store::*;
 
 use crate::{Error, ErrorKind, Result};
-use api::ExtState;
-use std::cmp;
+use std::{cell::RefCell, cmp};
 
 /// Calculates the maximum size of the compressed output.
 ///
@@ -40,29 +42,7 @@ pub const fn max_compressed_size(original_size: usize) -> usize {
 /// # Ok::<(), std::io::Error>(())
 /// ```
 pub fn compress(src: &[u8], dst: &mut [u8], acc: i32) -> Result<usize> {
-    compress_to_ptr(src, dst.as_mut_ptr(), dst.len(), acc)
-}
-
-fn compress_to_ptr(src: &[u8], dst: *mut u8, dst_len: usize, acc: i32) -> Result<usize> {
-    if src.is_empty() {
-        return Ok(0);
-    }
-
-    let acc = cmp::min(acc, 33_554_431);
-
-    let len = ExtState::with(|state, reset| {
-        let mut state = state.borrow_mut();
-        if reset {
-            api::compress_fast_ext_state_fast_reset(&mut state, src, dst, dst_len, acc)
-        } else {
-            api::compress_fast_ext_state(&mut state, src, dst, dst_len, acc)
-        }
-    });
-    if len > 0 {
-        Ok(len)
-    } else {
-        Err(Error::new(ErrorKind::CompressionFailed))
-    }
+    State::with_thread_local(|state| state.compress(src, dst, acc))
 }
 
 /// Appends compressed data to `Vec<u8>`.
@@ -85,19 +65,7 @@ fn compress_to_ptr(src: &[u8], dst: *mut u8, dst_len: usize, acc: i32) -> Result
 /// # Ok::<(), std::io::Error>(())
 /// ```
 pub fn compress_to_vec(src: &[u8], dst: &mut Vec<u8>, acc: i32) -> Result<usize> {
-    let orig_len = dst.len();
-    dst.reserve(max_compressed_size(src.len()));
-    #[allow(unsafe_code)]
-    unsafe {
-        let result = compress_to_ptr(
-            src,
-            dst.as_mut_ptr().add(orig_len),
-            dst.capacity() - orig_len,
-            acc,
-        );
-        dst.set_len(orig_len + result.as_ref().unwrap_or(&0));
-        result
-    }
+    State::with_thread_local(|state| state.compress_to_vec(src, dst, acc))
 }
 
 /// Decompresses an LZ4 block.
@@ -182,3 +150,104 @@ pub fn decompress_partial(src: &[u8], dst: &mut [u8], original_size: usize) -> R
 pub fn decompress_with_dict(src: &[u8], dst: &mut [u8], dict: &[u8]) -> Result<usize> {
     api::decompress_safe_using_dict(src, dst, dict)
 }
+
+/// A reusable LZ4 block compression state.
+///
+/// [`compress`] and [`compress_to_vec`] keep a `State` per thread.
+/// Owning one explicitly lets you decide when it is allocated and freed,
+/// e.g. by pooling states across short-lived worker threads.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4;
+///
+/// let mut state = lz4::State::new()?;
+/// let mut buf = Vec::new();
+///
+/// for data in [
+///     &b"The quick brown fox"[..],
+///     &b"jumps over the lazy dog."[..],
+/// ] {
+///     buf.clear();
+///     state.compress_to_vec(data, &mut buf, lz4::ACC_LEVEL_DEFAULT)?;
+/// #   let mut out = vec![0; data.len()];
+/// #   lz4::decompress(&buf, &mut out)?;
+/// #   assert_eq!(out, data);
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct State(api::ExtState);
+
+impl State {
+    /// Creates a new `State`.
+    ///
+    /// Returns [`ErrorKind::InitializationFailed`] if the state cannot be allocated.
+    ///
+    /// [`ErrorKind::InitializationFailed`]: ../enum.ErrorKind.html#variant.InitializationFailed
+    pub fn new() -> Result<Self> {
+        api::ExtState::new().map(Self)
+    }
+
+    /// Performs LZ4 block compression with this state.
+    ///
+    /// See [`compress`] for details.
+    pub fn compress(&mut self, src: &[u8], dst: &mut [u8], acc: i32) -> Result<usize> {
+        self.compress_to_ptr(src, dst.as_mut_ptr(), dst.len(), acc)
+    }
+
+    /// Appends compressed data to `Vec<u8>` with this state.
+    ///
+    /// See [`compress_to_vec`] for details.
+    pub fn compress_to_vec(&mut self, src: &[u8], dst: &mut Vec<u8>, acc: i32) -> Result<usize> {
+        let orig_len = dst.len();
+        dst.reserve(max_compressed_size(src.len()));
+        #[allow(unsafe_code)]
+        unsafe {
+            let result = self.compress_to_ptr(
+                src,
+                dst.as_mut_ptr().add(orig_len),
+                dst.capacity() - orig_len,
+                acc,
+            );
+            dst.set_len(orig_len + result.as_ref().unwrap_or(&0));
+            result
+        }
+    }
+
+    fn compress_to_ptr(
+        &mut self,
+        src: &[u8],
+        dst: *mut u8,
+        dst_len: usize,
+        acc: i32,
+    ) -> Result<usize> {
+        if src.is_empty() {
+            return Ok(0);
+        }
+
+        let acc = cmp::min(acc, 33_554_431);
+        let len = self.0.compress_fast(src, dst, dst_len, acc);
+        if len > 0 {
+            Ok(len)
+        } else {
+            Err(Error::new(ErrorKind::CompressionFailed))
+        }
+    }
+
+    fn with_thread_local<F, R>(f: F) -> Result<R>
+    where
+        F: FnOnce(&mut Self) -> Result<R>,
+    {
+        STATE.with(|cell| match &mut *cell.borrow_mut() {
+            Some(state) => f(state),
+            slot @ None => f(slot.insert(Self::new()?)),
+        })
+    }
+
+    pub(crate) fn free_thread_local() {
+        let _ = STATE.try_with(|state| state.borrow_mut().take());
+    }
+}
+
+thread_local!(static STATE: RefCell<Option<State>> = const { RefCell::new(None) });
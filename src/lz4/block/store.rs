@@ -0,0 +1,157 @@
+use super::compress;
+use crate::{Error, ErrorKind, Result};
+
+/// The number of bytes sampled by the probe. Shorter inputs are compressed without probing.
+const PROBE_SAMPLE_LEN: usize = 4096;
+
+/// Order-0 entropy in bits per byte above which a sample is considered random.
+///
+/// A uniformly random sample of `PROBE_SAMPLE_LEN` bytes measures about 7.95.
+const PROBE_ENTROPY_THRESHOLD: f64 = 7.9;
+
+/// The encoding of a block produced by [`compress_or_store`].
+///
+/// [`compress_or_store`]: fn.compress_or_store.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BlockKind {
+    /// The block holds LZ4 sequences.
+    Compressed,
+    /// The block holds the original data as is.
+    Stored,
+}
+
+/// Performs LZ4 block compression, or stores the data as is if it does not shrink.
+///
+/// Random or already compressed data expands slightly when compressed.
+/// This function first takes a quick look at the byte statistics of `src`,
+/// and stores data that looks random without compressing it.
+/// Otherwise, it compresses `src`, and falls back to storing it unless
+/// the compressed block is smaller.
+///
+/// The probe looks only at byte statistics, so repeats of random-looking data
+/// may be stored even though LZ4 could compress them.
+///
+/// The destination slice must be at least as long as `src`.
+/// Returns the number of bytes written into the destination buffer and how
+/// the block is encoded. Pass both to [`decompress_or_copy`] to restore the data.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4::{self, BlockKind};
+///
+/// let data = b"The quick brown fox jumps over the lazy dog.".repeat(10);
+/// let mut buf = vec![0; data.len()];
+///
+/// let (len, kind) = lz4::compress_or_store(&data, &mut buf, lz4::ACC_LEVEL_DEFAULT)?;
+/// assert_eq!(kind, BlockKind::Compressed);
+///
+/// let mut decomp = vec![0; data.len()];
+/// lz4::decompress_or_copy(&buf[..len], &mut decomp, kind)?;
+/// assert_eq!(decomp, data);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// [`decompress_or_copy`]: fn.decompress_or_copy.html
+pub fn compress_or_store(src: &[u8], dst: &mut [u8], acc: i32) -> Result<(usize, BlockKind)> {
+    compress_or_store_with(src, dst, |src, dst| compress(src, dst, acc))
+}
+
+/// Appends the result of [`compress_or_store`] to `Vec<u8>`.
+///
+/// Returns the number of bytes appended to the given `Vec<u8>` and how
+/// the block is encoded.
+///
+/// [`compress_or_store`]: fn.compress_or_store.html
+pub fn compress_or_store_to_vec(
+    src: &[u8],
+    dst: &mut Vec<u8>,
+    acc: i32,
+) -> Result<(usize, BlockKind)> {
+    compress_or_store_to_vec_with(src, dst, |src, dst| compress_or_store(src, dst, acc))
+}
+
+/// Decompresses a block produced by [`compress_or_store`].
+///
+/// Compressed blocks are decompressed as in [`decompress`]. Stored blocks are
+/// copied into the destination slice, which must be at least as long as `src`.
+///
+/// Returns the number of bytes written into the destination buffer.
+///
+/// [`compress_or_store`]: fn.compress_or_store.html
+/// [`decompress`]: fn.decompress.html
+pub fn decompress_or_copy(src: &[u8], dst: &mut [u8], kind: BlockKind) -> Result<usize> {
+    match kind {
+        BlockKind::Compressed => super::decompress(src, dst),
+        BlockKind::Stored => {
+            let dst = dst
+                .get_mut(..src.len())
+                .ok_or_else(|| Error::new(ErrorKind::DecompressionFailed))?;
+            dst.copy_from_slice(src);
+            Ok(src.len())
+        }
+    }
+}
+
+/// Compresses `src` with `compress` unless it looks random, and stores it
+/// if the compressed block is not smaller.
+pub(crate) fn compress_or_store_with<F>(
+    src: &[u8],
+    dst: &mut [u8],
+    compress: F,
+) -> Result<(usize, BlockKind)>
+where
+    F: FnOnce(&[u8], &mut [u8]) -> Result<usize>,
+{
+    if dst.len() < src.len() {
+        return Err(Error::new(ErrorKind::CompressionFailed));
+    }
+    if !src.is_empty() && !looks_random(src) {
+        // liblz4 gives up as soon as the output would not be smaller than the input.
+        match compress(src, &mut dst[..src.len() - 1]) {
+            Ok(len) => return Ok((len, BlockKind::Compressed)),
+            Err(err) if err.kind() == ErrorKind::CompressionFailed => {}
+            Err(err) => return Err(err),
+        }
+    }
+    dst[..src.len()].copy_from_slice(src);
+    Ok((src.len(), BlockKind::Stored))
+}
+
+/// Runs `compress_or_store` on the end of `dst`.
+pub(crate) fn compress_or_store_to_vec_with<F>(
+    src: &[u8],
+    dst: &mut Vec<u8>,
+    compress_or_store: F,
+) -> Result<(usize, BlockKind)>
+where
+    F: FnOnce(&[u8], &mut [u8]) -> Result<(usize, BlockKind)>,
+{
+    let orig_len = dst.len();
+    dst.resize(orig_len + src.len(), 0);
+    let result = compress_or_store(src, &mut dst[orig_len..]);
+    dst.truncate(orig_len + result.as_ref().map_or(0, |(len, _)| *len));
+    result
+}
+
+/// Returns `true` if the byte statistics of `src` are close to uniform,
+/// as in random or already compressed data.
+fn looks_random(src: &[u8]) -> bool {
+    if src.len() < PROBE_SAMPLE_LEN {
+        return false;
+    }
+    let mut counts = [0u32; 256];
+    let step = src.len() / PROBE_SAMPLE_LEN;
+    for &byte in src.iter().step_by(step).take(PROBE_SAMPLE_LEN) {
+        counts[byte as usize] += 1;
+    }
+    let entropy = counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = f64::from(count) / PROBE_SAMPLE_LEN as f64;
+            -p * p.log2()
+        })
+        .sum::<f64>();
+    entropy > PROBE_ENTROPY_THRESHOLD
+}
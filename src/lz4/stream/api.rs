@@ -65,6 +65,12 @@ impl CompressionContext {
         }
     }
 
+    pub fn reset(&mut self) {
+        unsafe {
+            binding::LZ4_resetStream_fast(self.get_ptr());
+        }
+    }
+
     pub fn load_dict(&mut self, dict: &[u8]) {
         unsafe {
             binding::LZ4_loadDict(
@@ -151,3 +157,10 @@ impl Drop for DecompressionContext {
         }
     }
 }
+
+/// Returns `0` if `max_block_size` is too large.
+pub fn decoder_ring_buffer_size(max_block_size: usize) -> usize {
+    c_int::try_from(max_block_size)
+        .map(|size| unsafe { binding::LZ4_decoderRingBufferSize(size) })
+        .unwrap_or(0) as usize
+}
@@ -0,0 +1,424 @@
+use super::{Compressor, Decompressor};
+use crate::{lz4, Error, ErrorKind, Result};
+use std::{
+    borrow::Cow,
+    cmp, fmt,
+    io::{self, BufRead, Read, Write},
+};
+
+/// The maximum number of bytes in a block of the length-prefixed stream.
+pub(crate) const BLOCK_SIZE_MAX: usize = 64 * 1024;
+
+/// The length of the block header: the original and compressed sizes.
+const HEADER_LEN: usize = 8;
+
+/// Collects written data into blocks and writes them out with their headers.
+pub(crate) struct BlockWriter<W: Write> {
+    inner: Option<W>,
+    src: Vec<u8>,
+    out: Vec<u8>,
+    consumed: usize,
+}
+
+impl<W: Write> BlockWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            inner: Some(writer),
+            src: Vec::new(),
+            out: Vec::new(),
+            consumed: 0,
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        self.inner.as_ref().unwrap()
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner.as_mut().unwrap()
+    }
+
+    /// Takes the writer out, after which the other methods must not be called.
+    pub fn take(&mut self) -> Option<W> {
+        self.inner.take()
+    }
+
+    pub fn is_taken(&self) -> bool {
+        self.inner.is_none()
+    }
+
+    /// Takes up to the rest of the current block from `buf`,
+    /// and compresses the block with `compress` once it is full.
+    pub fn write<F>(&mut self, buf: &[u8], compress: F) -> io::Result<usize>
+    where
+        F: FnOnce(&[u8], &mut Vec<u8>) -> Result<usize>,
+    {
+        self.write_pending()?;
+        let len = cmp::min(buf.len(), BLOCK_SIZE_MAX - self.src.len());
+        self.src.extend_from_slice(&buf[..len]);
+        if self.src.len() == BLOCK_SIZE_MAX {
+            if let Err(err) = self.end_block(compress) {
+                // `buf` is reported as not consumed, so it must not stay in the block.
+                self.src.truncate(BLOCK_SIZE_MAX - len);
+                return Err(err.into());
+            }
+            // `buf` has been consumed, so a failure here must not be reported.
+            // The output stays pending and is written out on the next call.
+            let _ = self.write_pending();
+        }
+        Ok(len)
+    }
+
+    /// Ends the current block and writes out all the compressed data.
+    pub fn flush<F>(&mut self, compress: F) -> io::Result<()>
+    where
+        F: FnOnce(&[u8], &mut Vec<u8>) -> Result<usize>,
+    {
+        self.write_pending()?;
+        self.end_block(compress)?;
+        self.write_pending()?;
+        self.get_mut().flush()
+    }
+
+    fn end_block<F>(&mut self, compress: F) -> Result<()>
+    where
+        F: FnOnce(&[u8], &mut Vec<u8>) -> Result<usize>,
+    {
+        if self.src.is_empty() {
+            return Ok(());
+        }
+        self.out.clear();
+        self.out
+            .extend_from_slice(&(self.src.len() as u32).to_le_bytes());
+        self.out.extend_from_slice(&[0; 4]);
+        let len = match compress(&self.src, &mut self.out) {
+            Ok(len) => len,
+            Err(err) => {
+                self.out.clear();
+                return Err(err);
+            }
+        };
+        self.out[4..HEADER_LEN].copy_from_slice(&(len as u32).to_le_bytes());
+        self.src.clear();
+        Ok(())
+    }
+
+    /// Writes out the compressed data left in the buffer.
+    ///
+    /// On error, the unwritten part stays in the buffer for the next call.
+    fn write_pending(&mut self) -> io::Result<()> {
+        let device = self.inner.as_mut().unwrap();
+        while self.consumed < self.out.len() {
+            match device.write(&self.out[self.consumed..])? {
+                0 => return Err(io::ErrorKind::WriteZero.into()),
+                len => self.consumed += len,
+            }
+        }
+        self.out.clear();
+        self.consumed = 0;
+        Ok(())
+    }
+}
+
+/// The [`Write`]-based streaming LZ4 compressor with length-prefixed blocks.
+///
+/// The written data is compressed into blocks of up to 64 KiB,
+/// which refer to the previous blocks as in [`Compressor`].
+/// Each block is written after a header holding its original size and
+/// its compressed size, both as 32-bit little-endian integers,
+/// so [`StreamReader`] can read the stream back without any other framing.
+///
+/// Calling [`flush`] ends the current block and writes it out, which makes
+/// this a light-weight alternative to LZ4F for low-latency links.
+/// Dropping the writer also writes out the current block, but ignores errors.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4;
+/// use std::io::prelude::*;
+///
+/// let mut w = lz4::StreamWriter::new(Vec::new())?;
+/// w.write_all(b"Hello world!")?;
+/// w.flush()?;
+///
+/// let mut r = lz4::StreamReader::new(w.get_ref().as_slice())?;
+/// let mut buf = String::new();
+/// r.read_to_string(&mut buf)?;
+/// assert_eq!(buf, "Hello world!");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
+/// [`Compressor`]: struct.Compressor.html
+/// [`StreamReader`]: struct.StreamReader.html
+/// [`flush`]: #method.flush
+pub struct StreamWriter<'a, W: Write> {
+    inner: BlockWriter<W>,
+    comp: Compressor<'a>,
+    acc: i32,
+}
+
+impl<'a, W: Write> StreamWriter<'a, W> {
+    /// Creates a new `StreamWriter<W>`.
+    pub fn new(writer: W) -> Result<Self> {
+        Ok(Self {
+            inner: BlockWriter::new(writer),
+            comp: Compressor::new()?,
+            acc: lz4::ACC_LEVEL_DEFAULT,
+        })
+    }
+
+    /// Creates a new `StreamWriter<W>` with a dictionary.
+    ///
+    /// The reader needs the same dictionary.
+    pub fn with_dict<D>(writer: W, dict: D) -> Result<Self>
+    where
+        D: Into<Cow<'a, [u8]>>,
+    {
+        Ok(Self {
+            inner: BlockWriter::new(writer),
+            comp: Compressor::with_dict(dict)?,
+            acc: lz4::ACC_LEVEL_DEFAULT,
+        })
+    }
+
+    /// Sets the acceleration factor of the blocks compressed from now on.
+    ///
+    /// The default is [`ACC_LEVEL_DEFAULT`]. Call [`flush`] first to apply
+    /// the new factor to the data written after it.
+    ///
+    /// [`ACC_LEVEL_DEFAULT`]: constant.ACC_LEVEL_DEFAULT.html
+    /// [`flush`]: #method.flush
+    pub fn set_acceleration(&mut self, acc: i32) {
+        self.acc = acc;
+    }
+
+    /// Returns a mutable reference to the writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner.get_mut()
+    }
+
+    /// Returns a shared reference to the writer.
+    pub fn get_ref(&self) -> &W {
+        self.inner.get_ref()
+    }
+
+    /// Returns the ownership of the writer, writing out the current block in the process.
+    ///
+    /// Errors on writing the block are ignored. Call [`flush`] first to catch them.
+    ///
+    /// [`flush`]: #method.flush
+    pub fn into_inner(mut self) -> W {
+        let _ = self.flush();
+        self.inner.take().unwrap()
+    }
+}
+
+impl<W> fmt::Debug for StreamWriter<'_, W>
+where
+    W: Write + fmt::Debug,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("StreamWriter")
+            .field("writer", self.inner.get_ref())
+            .field("acc", &self.acc)
+            .finish()
+    }
+}
+
+impl<W: Write> Write for StreamWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let (comp, acc) = (&mut self.comp, self.acc);
+        self.inner
+            .write(buf, |src, dst| comp.next_to_vec(src, dst, acc))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let (comp, acc) = (&mut self.comp, self.acc);
+        self.inner.flush(|src, dst| comp.next_to_vec(src, dst, acc))
+    }
+}
+
+impl<W: Write> Drop for StreamWriter<'_, W> {
+    fn drop(&mut self) {
+        if !self.inner.is_taken() {
+            let _ = self.flush();
+        }
+    }
+}
+
+/// The [`Read`]-based streaming LZ4 decompressor for length-prefixed blocks.
+///
+/// This reads the stream written by [`StreamWriter`] or [`lz4_hc::StreamWriter`].
+/// If the reader reaches EOF in the middle of a block, reading fails with
+/// [`ErrorKind::CompressedDataIncomplete`]. Once a block fails to decompress,
+/// every later read fails with [`ErrorKind::DecompressionFailed`].
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4;
+/// use std::io::prelude::*;
+///
+/// let mut w = lz4::StreamWriter::new(Vec::new())?;
+/// writeln!(w, "Hello world!")?;
+/// w.flush()?;
+/// writeln!(w, "Hello again!")?;
+/// let buf = w.into_inner();
+///
+/// let r = lz4::StreamReader::new(buf.as_slice())?;
+/// let lines = r.lines().collect::<Result<Vec<_>, _>>()?;
+/// assert_eq!(lines, ["Hello world!", "Hello again!"]);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// [`Read`]: https://doc.rust-lang.org/std/io/trait.Read.html
+/// [`StreamWriter`]: struct.StreamWriter.html
+/// [`lz4_hc::StreamWriter`]: ../lz4_hc/struct.StreamWriter.html
+/// [`ErrorKind::CompressedDataIncomplete`]: ../enum.ErrorKind.html#variant.CompressedDataIncomplete
+/// [`ErrorKind::DecompressionFailed`]: ../enum.ErrorKind.html#variant.DecompressionFailed
+pub struct StreamReader<'a, R: Read> {
+    inner: R,
+    decomp: Decompressor<'a>,
+    buf: Vec<u8>,
+    filled: usize,
+    consumed: usize,
+    failed: bool,
+}
+
+impl<'a, R: Read> StreamReader<'a, R> {
+    /// Creates a new `StreamReader<R>`.
+    pub fn new(reader: R) -> Result<Self> {
+        Ok(Self::with_decompressor(reader, Decompressor::new()?))
+    }
+
+    /// Creates a new `StreamReader<R>` with a dictionary.
+    pub fn with_dict<D>(reader: R, dict: D) -> Result<Self>
+    where
+        D: Into<Cow<'a, [u8]>>,
+    {
+        Ok(Self::with_decompressor(
+            reader,
+            Decompressor::with_dict(dict)?,
+        ))
+    }
+
+    fn with_decompressor(reader: R, decomp: Decompressor<'a>) -> Self {
+        Self {
+            inner: reader,
+            decomp,
+            buf: Vec::new(),
+            filled: 0,
+            consumed: 0,
+            failed: false,
+        }
+    }
+
+    /// Returns the ownership of the reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns a mutable reference to the reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Returns a shared reference to the reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Reads and decompresses the next block.
+    ///
+    /// Returns `false` at EOF on a block boundary. On error, the data read so far
+    /// stays in the buffer, and calling this again resumes from there.
+    fn read_block(&mut self) -> io::Result<bool> {
+        let mut len = HEADER_LEN;
+        loop {
+            if self.filled >= HEADER_LEN {
+                let original_size = self.header(0);
+                let compressed_size = self.header(4);
+                if original_size == 0
+                    || original_size > BLOCK_SIZE_MAX
+                    || compressed_size > lz4::max_compressed_size(original_size)
+                {
+                    return Err(Error::new(ErrorKind::DecompressionFailed).into());
+                }
+                len = HEADER_LEN + compressed_size;
+            }
+            if self.filled == len && len > HEADER_LEN {
+                break;
+            }
+            self.buf.resize(len, 0);
+            match self.inner.read(&mut self.buf[self.filled..len]) {
+                Ok(0) if self.filled == 0 => return Ok(false),
+                Ok(0) => return Err(Error::new(ErrorKind::CompressedDataIncomplete).into()),
+                Ok(read) => self.filled += read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        // The decompressor has already moved past the previous block,
+        // so the stream cannot be read any further after a failure.
+        let original_size = self.header(0);
+        match self.decomp.next(&self.buf[HEADER_LEN..], original_size) {
+            Ok(data) if data.len() == original_size => {}
+            _ => {
+                self.failed = true;
+                return Err(Error::new(ErrorKind::DecompressionFailed).into());
+            }
+        }
+        self.filled = 0;
+        self.consumed = 0;
+        Ok(true)
+    }
+
+    fn header(&self, offset: usize) -> usize {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(&self.buf[offset..][..4]);
+        u32::from_le_bytes(bytes) as usize
+    }
+}
+
+impl<R> fmt::Debug for StreamReader<'_, R>
+where
+    R: Read + fmt::Debug,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("StreamReader")
+            .field("reader", &self.inner)
+            .finish()
+    }
+}
+
+impl<R: Read> Read for StreamReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = {
+            let data = self.fill_buf()?;
+            let len = cmp::min(buf.len(), data.len());
+            buf[..len].copy_from_slice(&data[..len]);
+            len
+        };
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<R: Read> BufRead for StreamReader<'_, R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.failed {
+            return Err(Error::new(ErrorKind::DecompressionFailed).into());
+        }
+        if self.consumed == self.decomp.data().len() && !self.read_block()? {
+            return Ok(&[]);
+        }
+        Ok(&self.decomp.data()[self.consumed..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.consumed = cmp::min(self.consumed + amt, self.decomp.data().len());
+    }
+}
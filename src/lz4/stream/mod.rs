@@ -1,11 +1,13 @@
 mod api;
+mod io;
+mod ring;
 
-use crate::{
-    common::{DEFAULT_BUF_SIZE, DICTIONARY_SIZE},
-    lz4, Error, ErrorKind, Result,
-};
+use crate::{common::DICTIONARY_SIZE, lz4, Error, ErrorKind, Result};
 use api::{CompressionContext, DecompressionContext};
-use std::{borrow::Cow, cmp, collections::LinkedList, pin::Pin};
+pub(crate) use io::BlockWriter;
+pub use io::{StreamReader, StreamWriter};
+pub use ring::{RingCompressor, RingDecompressor};
+use std::{borrow::Cow, cmp, marker::PhantomData, pin::Pin};
 
 /// Streaming LZ4 compressor.
 ///
@@ -58,6 +60,48 @@ impl<'a> Compressor<'a> {
         Ok(comp)
     }
 
+    /// Starts a new stream without a dictionary.
+    ///
+    /// The compression state is reused, which is much cheaper than creating
+    /// a new `Compressor`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lzzzz::lz4;
+    ///
+    /// let data = b"The quick brown fox jumps over the lazy dog.";
+    /// let mut comp = lz4::Compressor::new()?;
+    ///
+    /// let mut first = Vec::new();
+    /// comp.next_to_vec(data, &mut first, lz4::ACC_LEVEL_DEFAULT)?;
+    ///
+    /// // The second stream does not refer to the first one.
+    /// comp.reset();
+    /// let mut second = Vec::new();
+    /// comp.next_to_vec(data, &mut second, lz4::ACC_LEVEL_DEFAULT)?;
+    /// assert_eq!(first, second);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn reset(&mut self) {
+        self.ctx.reset();
+        self.dict = Pin::new(Cow::Borrowed(&[]));
+    }
+
+    /// Starts a new stream with a dictionary.
+    ///
+    /// The compression state is reused as in [`reset`].
+    ///
+    /// [`reset`]: #method.reset
+    pub fn reset_with_dict<D>(&mut self, dict: D)
+    where
+        D: Into<Cow<'a, [u8]>>,
+    {
+        self.ctx.reset();
+        self.dict = Pin::new(dict.into());
+        self.ctx.load_dict(&self.dict);
+    }
+
     /// Performs LZ4 streaming compression.
     ///
     /// Returns the number of bytes written into the destination buffer.
@@ -66,10 +110,63 @@ impl<'a> Compressor<'a> {
     }
 
     fn next_to_ptr(&mut self, src: &[u8], dst: *mut u8, dst_len: usize, acc: i32) -> Result<usize> {
-        let is_empty = src.is_empty() && dst_len == 0;
-        let dst_len = self.ctx.next(src, dst, dst_len, acc);
+        let result = self.compress_to_ptr(src, dst, dst_len, acc);
+        self.save_dict();
+        result
+    }
 
+    /// Performs LZ4 streaming compression on scattered input.
+    ///
+    /// Each slice of `src` is compressed into a separate block and the blocks are written
+    /// back to back into the destination buffer. Every block can refer to the previous
+    /// slice without copying it, so this is cheaper than calling [`next`] for each slice.
+    ///
+    /// Returns the compressed size of each block.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lzzzz::lz4;
+    ///
+    /// let header = b"The quick brown fox ";
+    /// let body = b"jumps over the lazy dog.";
+    /// let mut buf = [0u8; 256];
+    ///
+    /// let mut comp = lz4::Compressor::new()?;
+    /// let lens = comp.next_slices(&[header, body], &mut buf, lz4::ACC_LEVEL_DEFAULT)?;
+    ///
+    /// let mut decomp = lz4::Decompressor::new()?;
+    /// assert_eq!(decomp.next(&buf[..lens[0]], header.len())?, header);
+    /// assert_eq!(decomp.next(&buf[lens[0]..][..lens[1]], body.len())?, body);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    ///
+    /// [`next`]: #method.next
+    pub fn next_slices(&mut self, src: &[&[u8]], dst: &mut [u8], acc: i32) -> Result<Vec<usize>> {
+        let mut lens = Vec::with_capacity(src.len());
+        let mut offset = 0;
+        let result = src.iter().try_for_each(|src| {
+            let dst = &mut dst[offset..];
+            let len = self.compress_to_ptr(src, dst.as_mut_ptr(), dst.len(), acc)?;
+            lens.push(len);
+            offset += len;
+            Ok(())
+        });
+
+        // The slices are alive until here, so the history is saved only once.
         self.save_dict();
+        result.map(|_| lens)
+    }
+
+    fn compress_to_ptr(
+        &mut self,
+        src: &[u8],
+        dst: *mut u8,
+        dst_len: usize,
+        acc: i32,
+    ) -> Result<usize> {
+        let is_empty = src.is_empty() && dst_len == 0;
+        let dst_len = self.ctx.next(src, dst, dst_len, acc);
 
         if dst_len > 0 {
             Ok(dst_len)
@@ -126,10 +223,9 @@ impl<'a> Compressor<'a> {
 /// ```
 pub struct Decompressor<'a> {
     ctx: DecompressionContext,
-    cache: LinkedList<Vec<u8>>,
-    cache_len: usize,
+    buf: Vec<u8>,
     last_len: usize,
-    dict: Pin<Cow<'a, [u8]>>,
+    _dict: PhantomData<Cow<'a, [u8]>>,
 }
 
 impl<'a> Decompressor<'a> {
@@ -137,10 +233,9 @@ impl<'a> Decompressor<'a> {
     pub fn new() -> Result<Self> {
         Ok(Self {
             ctx: DecompressionContext::new()?,
-            cache: LinkedList::new(),
-            cache_len: 0,
+            buf: Vec::new(),
             last_len: 0,
-            dict: Pin::new(Cow::Borrowed(&[])),
+            _dict: PhantomData,
         })
     }
 
@@ -149,62 +244,69 @@ impl<'a> Decompressor<'a> {
     where
         D: Into<Cow<'a, [u8]>>,
     {
-        let mut decomp = Self {
-            dict: Pin::new(dict.into()),
-            ..Self::new()?
-        };
-        decomp.ctx.reset(&decomp.dict)?;
+        let dict = dict.into();
+        let mut decomp = Self::new()?;
+        decomp
+            .buf
+            .extend_from_slice(&dict[dict.len().saturating_sub(DICTIONARY_SIZE)..]);
+        decomp.ctx.reset(&decomp.buf)?;
         Ok(decomp)
     }
 
+    /// Starts a new stream without a dictionary.
+    ///
+    /// The decompression state and the buffer are reused, which is much cheaper
+    /// than creating a new `Decompressor`.
+    pub fn reset(&mut self) -> Result<()> {
+        self.buf.clear();
+        self.last_len = 0;
+        self.ctx.reset(&self.buf)
+    }
+
+    /// Starts a new stream with a dictionary.
+    ///
+    /// The decompression state and the buffer are reused as in [`reset`].
+    ///
+    /// [`reset`]: #method.reset
+    pub fn reset_with_dict<D>(&mut self, dict: D) -> Result<()>
+    where
+        D: Into<Cow<'a, [u8]>>,
+    {
+        let dict = dict.into();
+        self.buf.clear();
+        self.buf
+            .extend_from_slice(&dict[dict.len().saturating_sub(DICTIONARY_SIZE)..]);
+        self.last_len = 0;
+        self.ctx.reset(&self.buf)
+    }
+
     /// Decompresses an LZ4 block.
     pub fn next(&mut self, src: &[u8], original_size: usize) -> Result<&[u8]> {
-        if self
-            .cache
-            .back()
-            .map(|v| v.capacity() - v.len())
-            .filter(|n| *n >= original_size)
-            .is_none()
-        {
-            self.cache.push_back(Vec::with_capacity(cmp::max(
-                original_size,
-                DEFAULT_BUF_SIZE,
-            )));
+        if self.buf.capacity() - self.buf.len() < original_size {
+            // Keep the last 64 KiB at the front of the buffer, which liblz4 sees as
+            // a single contiguous prefix. Reserving at least 64 KiB more limits
+            // the copy to once per 64 KiB of output.
+            let history = self.buf.len().saturating_sub(DICTIONARY_SIZE);
+            self.buf.drain(..history);
+            self.buf.reserve(cmp::max(original_size, DICTIONARY_SIZE));
+            self.ctx.reset(&self.buf)?;
         }
 
-        let back = self.cache.back_mut().unwrap();
-        let orig_len = back.len();
-
+        let orig_len = self.buf.len();
         #[allow(unsafe_code)]
         unsafe {
             let dst_len = self.ctx.decompress(
                 src,
-                back.as_mut_ptr().add(orig_len),
-                back.capacity() - orig_len,
+                self.buf.as_mut_ptr().add(orig_len),
+                self.buf.capacity() - orig_len,
             )?;
-            back.set_len(orig_len + dst_len);
-            self.cache_len += dst_len;
-        }
-        self.last_len = original_size;
-
-        while let Some(len) = self
-            .cache
-            .front()
-            .map(Vec::len)
-            .filter(|n| self.cache_len - n >= DICTIONARY_SIZE)
-        {
-            self.cache.pop_front();
-            self.cache_len -= len;
+            self.buf.set_len(orig_len + dst_len);
+            self.last_len = dst_len;
         }
         Ok(self.data())
     }
 
     fn data(&self) -> &[u8] {
-        if let Some(back) = self.cache.back() {
-            let offset = back.len() - self.last_len;
-            &back[offset..]
-        } else {
-            &[]
-        }
+        &self.buf[self.buf.len() - self.last_len..]
     }
 }
@@ -0,0 +1,246 @@
+use super::api::{decoder_ring_buffer_size, CompressionContext, DecompressionContext};
+use crate::{common::DICTIONARY_SIZE, lz4, Error, ErrorKind, Result, RingBuffer};
+
+/// Streaming LZ4 compressor over a ring buffer.
+///
+/// [`Compressor`] copies the last 64 KiB of the input into its own buffer
+/// after every call, so that the next block can refer to it.
+/// `RingCompressor` avoids that copy by keeping every message in a contiguous
+/// ring buffer of at least 64 KiB plus the maximum message size,
+/// and compressing it in place.
+///
+/// The ring buffer is either allocated by the compressor or borrowed
+/// exclusively from the caller, so the history cannot be modified
+/// while the compressor refers to it.
+///
+/// The output is an ordinary LZ4 block stream, which [`Decompressor`] can decompress.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4;
+///
+/// let mut comp = lz4::RingCompressor::new(1024)?;
+/// let mut decomp = lz4::Decompressor::new()?;
+///
+/// for msg in &[&b"Hello world!"[..], b"Hello again!"] {
+///     let mut buf = [0u8; 256];
+///     let len = comp.next(msg, &mut buf, lz4::ACC_LEVEL_DEFAULT)?;
+///     assert_eq!(decomp.next(&buf[..len], msg.len())?, &msg[..]);
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// [`Compressor`]: struct.Compressor.html
+/// [`Decompressor`]: struct.Decompressor.html
+pub struct RingCompressor<'a> {
+    ctx: CompressionContext,
+    ring: RingBuffer<'a>,
+}
+
+impl<'a> RingCompressor<'a> {
+    /// Creates a new `RingCompressor` for messages up to `max_message_size` bytes.
+    ///
+    /// The ring buffer holds 64 KiB plus `max_message_size` bytes.
+    pub fn new(max_message_size: usize) -> Result<Self> {
+        Ok(Self {
+            ctx: CompressionContext::new()?,
+            ring: RingBuffer::new(DICTIONARY_SIZE, DICTIONARY_SIZE + max_message_size),
+        })
+    }
+
+    /// Creates a new `RingCompressor` over a ring buffer provided by the caller.
+    ///
+    /// Messages can be as long as `buf.len()` minus 64 KiB.
+    /// Fails with [`ErrorKind::InitializationFailed`] if `buf` is not longer than 64 KiB.
+    ///
+    /// [`ErrorKind::InitializationFailed`]: ../enum.ErrorKind.html#variant.InitializationFailed
+    pub fn with_buffer(buf: &'a mut [u8]) -> Result<Self> {
+        Ok(Self {
+            ctx: CompressionContext::new()?,
+            ring: RingBuffer::with_buffer(DICTIONARY_SIZE, buf)?,
+        })
+    }
+
+    /// Returns the maximum length of a message.
+    pub fn max_message_size(&self) -> usize {
+        self.ring.max_message_size()
+    }
+
+    /// Performs LZ4 streaming compression.
+    ///
+    /// `src` is copied into the ring buffer and compressed there.
+    /// Fails with [`ErrorKind::CompressionFailed`] if `src` is longer than
+    /// [`max_message_size`].
+    ///
+    /// Returns the number of bytes written into the destination buffer.
+    ///
+    /// [`ErrorKind::CompressionFailed`]: ../enum.ErrorKind.html#variant.CompressionFailed
+    /// [`max_message_size`]: #method.max_message_size
+    pub fn next(&mut self, src: &[u8], dst: &mut [u8], acc: i32) -> Result<usize> {
+        self.next_with(src.len(), |slot| slot.copy_from_slice(src), dst, acc)
+    }
+
+    /// Performs LZ4 streaming compression on a message written directly into the ring buffer.
+    ///
+    /// `fill` receives the `len` bytes of the ring buffer that hold the next message,
+    /// and writes the message into them.
+    ///
+    /// Returns the number of bytes written into the destination buffer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lzzzz::lz4;
+    ///
+    /// let header = b"MSG1";
+    /// let body = b"The quick brown fox jumps over the lazy dog.";
+    /// let mut buf = [0u8; 256];
+    ///
+    /// let mut comp = lz4::RingCompressor::new(1024)?;
+    /// let len = comp.next_with(
+    ///     header.len() + body.len(),
+    ///     |slot| {
+    ///         slot[..header.len()].copy_from_slice(header);
+    ///         slot[header.len()..].copy_from_slice(body);
+    ///     },
+    ///     &mut buf,
+    ///     lz4::ACC_LEVEL_DEFAULT,
+    /// )?;
+    ///
+    /// let mut decomp = [0u8; 256];
+    /// let decomp_len = lz4::decompress(&buf[..len], &mut decomp)?;
+    /// assert_eq!(&decomp[header.len()..decomp_len], &body[..]);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn next_with<F>(&mut self, len: usize, fill: F, dst: &mut [u8], acc: i32) -> Result<usize>
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        let slot = self
+            .ring
+            .next_slot(len)
+            .ok_or_else(|| Error::new(ErrorKind::CompressionFailed))?;
+        fill(slot);
+        let dst_len = self.ctx.next(slot, dst.as_mut_ptr(), dst.len(), acc);
+        if dst_len > 0 || (len == 0 && dst.is_empty()) {
+            Ok(dst_len)
+        } else {
+            Err(Error::new(ErrorKind::CompressionFailed))
+        }
+    }
+
+    /// Appends compressed data to Vec<u8>.
+    ///
+    /// Returns the number of bytes appended to the given `Vec<u8>`.
+    pub fn next_to_vec(&mut self, src: &[u8], dst: &mut Vec<u8>, acc: i32) -> Result<usize> {
+        let orig_len = dst.len();
+        dst.resize(orig_len + lz4::max_compressed_size(src.len()), 0);
+        let result = self.next(src, &mut dst[orig_len..], acc);
+        dst.truncate(orig_len + result.as_ref().unwrap_or(&0));
+        result
+    }
+}
+
+/// liblz4 copies a match from the previous pass of the ring buffer in chunks,
+/// which may overwrite the source of the match unless it is at least 14 bytes
+/// ahead of the output. See `LZ4_DECODER_RING_BUFFER_SIZE` in `lz4.h`.
+const DECODER_HISTORY_SIZE: usize = DICTIONARY_SIZE + 14;
+
+/// Streaming LZ4 decompressor over a ring buffer.
+///
+/// [`Decompressor`] keeps the last 64 KiB of its output in an internal buffer.
+/// `RingDecompressor` instead decompresses every block directly into
+/// a contiguous ring buffer of at least 64 KiB and 14 bytes plus the maximum message size,
+/// which it allocates or borrows from the caller, and never copies the history.
+///
+/// Like [`RingCompressor`], the ring buffer is borrowed exclusively,
+/// so the history cannot be modified while the decompressor refers to it.
+/// Any LZ4 block stream can be decompressed as long as no block is longer than
+/// the maximum message size.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4;
+///
+/// let mut comp = lz4::Compressor::new()?;
+/// let mut ring = vec![0; 64 * 1024 + 1024];
+/// let mut decomp = lz4::RingDecompressor::with_buffer(&mut ring)?;
+///
+/// for msg in &[&b"Hello world!"[..], b"Hello again!"] {
+///     let mut buf = Vec::new();
+///     comp.next_to_vec(msg, &mut buf, lz4::ACC_LEVEL_DEFAULT)?;
+///     assert_eq!(decomp.next(&buf, msg.len())?, &msg[..]);
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// [`Decompressor`]: struct.Decompressor.html
+/// [`RingCompressor`]: struct.RingCompressor.html
+pub struct RingDecompressor<'a> {
+    ctx: DecompressionContext,
+    ring: RingBuffer<'a>,
+}
+
+impl<'a> RingDecompressor<'a> {
+    /// Creates a new `RingDecompressor` for messages up to `max_message_size` bytes.
+    ///
+    /// The ring buffer holds 64 KiB and 14 bytes plus `max_message_size` bytes,
+    /// as `LZ4_decoderRingBufferSize` of liblz4 requires.
+    pub fn new(max_message_size: usize) -> Result<Self> {
+        let len = decoder_ring_buffer_size(max_message_size);
+        if len == 0 {
+            return Err(Error::new(ErrorKind::InitializationFailed));
+        }
+        Ok(Self {
+            ctx: DecompressionContext::new()?,
+            ring: RingBuffer::new(DECODER_HISTORY_SIZE, len),
+        })
+    }
+
+    /// Creates a new `RingDecompressor` over a ring buffer provided by the caller.
+    ///
+    /// Messages can be as long as `buf.len()` minus 64 KiB and 14 bytes.
+    /// Fails with [`ErrorKind::InitializationFailed`] if `buf` is not longer than that.
+    ///
+    /// [`ErrorKind::InitializationFailed`]: ../enum.ErrorKind.html#variant.InitializationFailed
+    pub fn with_buffer(buf: &'a mut [u8]) -> Result<Self> {
+        Ok(Self {
+            ctx: DecompressionContext::new()?,
+            ring: RingBuffer::with_buffer(DECODER_HISTORY_SIZE, buf)?,
+        })
+    }
+
+    /// Returns the maximum length of a message.
+    pub fn max_message_size(&self) -> usize {
+        self.ring.max_message_size()
+    }
+
+    /// Decompresses an LZ4 block into the ring buffer.
+    ///
+    /// Fails with [`ErrorKind::DecompressionFailed`] if `original_size` is longer than
+    /// [`max_message_size`].
+    ///
+    /// Returns the decompressed data, which stays in the ring buffer until it is overwritten
+    /// by later blocks.
+    ///
+    /// [`ErrorKind::DecompressionFailed`]: ../enum.ErrorKind.html#variant.DecompressionFailed
+    /// [`max_message_size`]: #method.max_message_size
+    pub fn next(&mut self, src: &[u8], original_size: usize) -> Result<&[u8]> {
+        let slot = self
+            .ring
+            .next_slot(original_size)
+            .ok_or_else(|| Error::new(ErrorKind::DecompressionFailed))?;
+        match self.ctx.decompress(src, slot.as_mut_ptr(), slot.len()) {
+            Ok(len) => {
+                self.ring.truncate_last(len);
+                Ok(self.ring.last())
+            }
+            Err(err) => {
+                self.ring.discard_last();
+                Err(err)
+            }
+        }
+    }
+}
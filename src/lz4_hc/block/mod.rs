@@ -1,8 +1,7 @@
 mod api;
 
 use crate::{lz4, Error, ErrorKind, Result};
-use api::ExtState;
-use std::{cmp, io::Cursor};
+use std::{cell::RefCell, cmp, io::Cursor};
 
 /// Performs LZ4_HC block compression.
 ///
@@ -35,25 +34,7 @@ use std::{cmp, io::Cursor};
 /// # Ok::<(), std::io::Error>(())
 /// ```
 pub fn compress(src: &[u8], dst: &mut [u8], level: i32) -> Result<usize> {
-    compress_to_ptr(src, dst.as_mut_ptr(), dst.len(), level)
-}
-
-fn compress_to_ptr(src: &[u8], dst: *mut u8, dst_len: usize, level: i32) -> Result<usize> {
-    if src.is_empty() {
-        return Ok(0);
-    }
-    let len = ExtState::with(|state, reset| {
-        if reset {
-            api::compress_ext_state_fast_reset(&mut state.borrow_mut(), src, dst, dst_len, level)
-        } else {
-            api::compress_ext_state(&mut state.borrow_mut(), src, dst, dst_len, level)
-        }
-    });
-    if len > 0 {
-        Ok(len)
-    } else {
-        Err(Error::new(ErrorKind::CompressionFailed))
-    }
+    State::with_thread_local(|state| state.compress(src, dst, level))
 }
 
 /// Compresses data until the destination slice fills up.
@@ -85,17 +66,7 @@ pub fn compress_partial<T>(src: &mut Cursor<T>, dst: &mut [u8], level: i32) -> R
 where
     T: AsRef<[u8]>,
 {
-    let src_ref = src.get_ref().as_ref();
-    let pos = cmp::min(src_ref.len(), src.position() as usize);
-    let src_ref = &src_ref[pos..];
-    if src_ref.is_empty() || dst.is_empty() {
-        return Ok(0);
-    }
-    let (src_len, dst_len) = ExtState::with(|state, _| {
-        api::compress_dest_size(&mut state.borrow_mut(), src_ref, dst, level)
-    });
-    src.set_position(src.position() + src_len as u64);
-    Ok(dst_len)
+    State::with_thread_local(|state| state.compress_partial(src, dst, level))
 }
 
 /// Appends compressed data to `Vec<u8>`.
@@ -122,17 +93,172 @@ where
 /// # Ok::<(), std::io::Error>(())
 /// ```
 pub fn compress_to_vec(src: &[u8], dst: &mut Vec<u8>, level: i32) -> Result<usize> {
-    let orig_len = dst.len();
-    dst.reserve(lz4::max_compressed_size(src.len()));
-    #[allow(unsafe_code)]
-    unsafe {
-        let result = compress_to_ptr(
-            src,
-            dst.as_mut_ptr().add(orig_len),
-            dst.capacity() - orig_len,
-            level,
-        );
-        dst.set_len(orig_len + result.as_ref().unwrap_or(&0));
-        result
+    State::with_thread_local(|state| state.compress_to_vec(src, dst, level))
+}
+
+/// Performs LZ4_HC block compression, or stores the data as is if it does not shrink.
+///
+/// This works like [`lz4::compress_or_store`] with LZ4_HC compression.
+/// Use [`lz4::decompress_or_copy`] to restore the data.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::{lz4, lz4_hc};
+///
+/// let data = b"The quick brown fox jumps over the lazy dog.".repeat(10);
+/// let mut buf = vec![0; data.len()];
+///
+/// let (len, kind) = lz4_hc::compress_or_store(&data, &mut buf, lz4_hc::CLEVEL_DEFAULT)?;
+///
+/// let mut decomp = vec![0; data.len()];
+/// lz4::decompress_or_copy(&buf[..len], &mut decomp, kind)?;
+/// assert_eq!(decomp, data);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// [`lz4::compress_or_store`]: ../lz4/fn.compress_or_store.html
+/// [`lz4::decompress_or_copy`]: ../lz4/fn.decompress_or_copy.html
+pub fn compress_or_store(
+    src: &[u8],
+    dst: &mut [u8],
+    level: i32,
+) -> Result<(usize, lz4::BlockKind)> {
+    lz4::compress_or_store_with(src, dst, |src, dst| compress(src, dst, level))
+}
+
+/// Appends the result of [`compress_or_store`] to `Vec<u8>`.
+///
+/// Returns the number of bytes appended to the given `Vec<u8>` and how
+/// the block is encoded.
+///
+/// [`compress_or_store`]: fn.compress_or_store.html
+pub fn compress_or_store_to_vec(
+    src: &[u8],
+    dst: &mut Vec<u8>,
+    level: i32,
+) -> Result<(usize, lz4::BlockKind)> {
+    lz4::compress_or_store_to_vec_with(src, dst, |src, dst| compress_or_store(src, dst, level))
+}
+
+/// A reusable LZ4_HC block compression state.
+///
+/// [`compress`], [`compress_partial`] and [`compress_to_vec`] keep a `State` per thread.
+/// Owning one explicitly lets you decide when it is allocated and freed,
+/// e.g. by pooling states across short-lived worker threads.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::{lz4, lz4_hc};
+///
+/// let mut state = lz4_hc::State::new()?;
+/// let mut buf = Vec::new();
+///
+/// for data in [
+///     &b"The quick brown fox"[..],
+///     &b"jumps over the lazy dog."[..],
+/// ] {
+///     buf.clear();
+///     state.compress_to_vec(data, &mut buf, lz4_hc::CLEVEL_DEFAULT)?;
+/// #   let mut out = vec![0; data.len()];
+/// #   lz4::decompress(&buf, &mut out)?;
+/// #   assert_eq!(out, data);
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct State(api::ExtState);
+
+impl State {
+    /// Creates a new `State`.
+    ///
+    /// Returns [`ErrorKind::InitializationFailed`] if the state cannot be allocated.
+    ///
+    /// [`ErrorKind::InitializationFailed`]: ../enum.ErrorKind.html#variant.InitializationFailed
+    pub fn new() -> Result<Self> {
+        api::ExtState::new().map(Self)
+    }
+
+    /// Performs LZ4_HC block compression with this state.
+    ///
+    /// See [`compress`] for details.
+    pub fn compress(&mut self, src: &[u8], dst: &mut [u8], level: i32) -> Result<usize> {
+        self.compress_to_ptr(src, dst.as_mut_ptr(), dst.len(), level)
+    }
+
+    /// Compresses data until the destination slice fills up, with this state.
+    ///
+    /// See [`compress_partial`] for details.
+    pub fn compress_partial<T>(
+        &mut self,
+        src: &mut Cursor<T>,
+        dst: &mut [u8],
+        level: i32,
+    ) -> Result<usize>
+    where
+        T: AsRef<[u8]>,
+    {
+        let src_ref = src.get_ref().as_ref();
+        let pos = cmp::min(src_ref.len(), src.position() as usize);
+        let src_ref = &src_ref[pos..];
+        if src_ref.is_empty() || dst.is_empty() {
+            return Ok(0);
+        }
+        let (src_len, dst_len) = self.0.compress_dest_size(src_ref, dst, level);
+        src.set_position(src.position() + src_len as u64);
+        Ok(dst_len)
+    }
+
+    /// Appends compressed data to `Vec<u8>` with this state.
+    ///
+    /// See [`compress_to_vec`] for details.
+    pub fn compress_to_vec(&mut self, src: &[u8], dst: &mut Vec<u8>, level: i32) -> Result<usize> {
+        let orig_len = dst.len();
+        dst.reserve(lz4::max_compressed_size(src.len()));
+        #[allow(unsafe_code)]
+        unsafe {
+            let result = self.compress_to_ptr(
+                src,
+                dst.as_mut_ptr().add(orig_len),
+                dst.capacity() - orig_len,
+                level,
+            );
+            dst.set_len(orig_len + result.as_ref().unwrap_or(&0));
+            result
+        }
+    }
+
+    fn compress_to_ptr(
+        &mut self,
+        src: &[u8],
+        dst: *mut u8,
+        dst_len: usize,
+        level: i32,
+    ) -> Result<usize> {
+        if src.is_empty() {
+            return Ok(0);
+        }
+        let len = self.0.compress(src, dst, dst_len, level);
+        if len > 0 {
+            Ok(len)
+        } else {
+            Err(Error::new(ErrorKind::CompressionFailed))
+        }
+    }
+
+    fn with_thread_local<F, R>(f: F) -> Result<R>
+    where
+        F: FnOnce(&mut Self) -> Result<R>,
+    {
+        STATE.with(|cell| match &mut *cell.borrow_mut() {
+            Some(state) => f(state),
+            slot @ None => f(slot.insert(Self::new()?)),
+        })
+    }
+
+    pub(crate) fn free_thread_local() {
+        let _ = STATE.try_with(|state| state.borrow_mut().take());
     }
 }
+
+thread_local!(static STATE: RefCell<Option<State>> = const { RefCell::new(None) });
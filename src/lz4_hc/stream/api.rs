@@ -1,7 +1,7 @@
 #![allow(unsafe_code)]
 
 use super::super::{binding, binding::LZ4StreamHC};
-use crate::{Error, ErrorKind, Result};
+use crate::{lz4_hc::CLEVEL_DEFAULT, Error, ErrorKind, Result};
 
 use std::{
     os::raw::{c_char, c_int},
@@ -10,6 +10,8 @@ use std::{
 
 pub struct CompressionContext {
     stream: NonNull<LZ4StreamHC>,
+    compression_level: i32,
+    favor_dec_speed: bool,
 }
 
 unsafe impl Send for CompressionContext {}
@@ -18,16 +20,31 @@ impl CompressionContext {
     pub fn new() -> Result<Self> {
         let ptr = unsafe { NonNull::new(binding::LZ4_createStreamHC()) };
         ptr.ok_or_else(|| Error::new(ErrorKind::InitializationFailed))
-            .map(|stream| Self { stream })
+            .map(|stream| Self {
+                stream,
+                compression_level: CLEVEL_DEFAULT,
+                favor_dec_speed: false,
+            })
+    }
+
+    /// Starts a new stream, keeping the compression level and the decompression speed flag.
+    pub fn reset(&mut self) {
+        unsafe {
+            binding::LZ4_resetStreamHC_fast(self.stream.as_ptr(), self.compression_level as c_int);
+        }
+        // A full re-initialization inside the reset clears the flag.
+        self.set_favor_dec_speed(self.favor_dec_speed);
     }
 
     pub fn set_compression_level(&mut self, compression_level: i32) {
+        self.compression_level = compression_level;
         unsafe {
             binding::LZ4_setCompressionLevel(self.stream.as_ptr(), compression_level as c_int)
         }
     }
 
     pub fn set_favor_dec_speed(&mut self, flag: bool) {
+        self.favor_dec_speed = flag;
         unsafe {
             binding::LZ4_favorDecompressionSpeed(
                 self.stream.as_ptr(),
@@ -44,6 +61,8 @@ impl CompressionContext {
                 dict.len() as c_int,
             );
         }
+        // Loading a dictionary re-initializes the stream, which clears the flag.
+        self.set_favor_dec_speed(self.favor_dec_speed);
     }
 
     pub fn save_dict(&mut self, dict: &mut [u8]) {
@@ -0,0 +1,122 @@
+use super::Compressor;
+use crate::{lz4::BlockWriter, lz4_hc::FavorDecSpeed, Result};
+use std::{
+    borrow::Cow,
+    fmt,
+    io::{self, Write},
+};
+
+/// The [`Write`]-based streaming LZ4_HC compressor with length-prefixed blocks.
+///
+/// This writes the same stream as [`lz4::StreamWriter`] with LZ4_HC compression,
+/// which [`lz4::StreamReader`] reads back.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::{lz4, lz4_hc};
+/// use std::io::prelude::*;
+///
+/// let mut w = lz4_hc::StreamWriter::new(Vec::new())?;
+/// w.set_compression_level(lz4_hc::CLEVEL_MAX);
+/// w.write_all(b"Hello world!")?;
+/// w.flush()?;
+///
+/// let mut r = lz4::StreamReader::new(w.get_ref().as_slice())?;
+/// let mut buf = String::new();
+/// r.read_to_string(&mut buf)?;
+/// assert_eq!(buf, "Hello world!");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
+/// [`lz4::StreamWriter`]: ../lz4/struct.StreamWriter.html
+/// [`lz4::StreamReader`]: ../lz4/struct.StreamReader.html
+pub struct StreamWriter<'a, W: Write> {
+    inner: BlockWriter<W>,
+    comp: Compressor<'a>,
+}
+
+impl<'a, W: Write> StreamWriter<'a, W> {
+    /// Creates a new `StreamWriter<W>`.
+    pub fn new(writer: W) -> Result<Self> {
+        Ok(Self {
+            inner: BlockWriter::new(writer),
+            comp: Compressor::new()?,
+        })
+    }
+
+    /// Creates a new `StreamWriter<W>` with a dictionary.
+    ///
+    /// The reader needs the same dictionary.
+    pub fn with_dict<D>(writer: W, dict: D) -> Result<Self>
+    where
+        D: Into<Cow<'a, [u8]>>,
+    {
+        Ok(Self {
+            inner: BlockWriter::new(writer),
+            comp: Compressor::with_dict(dict)?,
+        })
+    }
+
+    /// Sets the compression level of the blocks compressed from now on.
+    pub fn set_compression_level(&mut self, level: i32) {
+        self.comp.set_compression_level(level);
+    }
+
+    /// Sets the decompression speed mode flag of the blocks compressed from now on.
+    pub fn set_favor_dec_speed(&mut self, dec_speed: FavorDecSpeed) {
+        self.comp.set_favor_dec_speed(dec_speed);
+    }
+
+    /// Returns a mutable reference to the writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner.get_mut()
+    }
+
+    /// Returns a shared reference to the writer.
+    pub fn get_ref(&self) -> &W {
+        self.inner.get_ref()
+    }
+
+    /// Returns the ownership of the writer, writing out the current block in the process.
+    ///
+    /// Errors on writing the block are ignored. Call [`flush`] first to catch them.
+    ///
+    /// [`flush`]: #method.flush
+    pub fn into_inner(mut self) -> W {
+        let _ = self.flush();
+        self.inner.take().unwrap()
+    }
+}
+
+impl<W> fmt::Debug for StreamWriter<'_, W>
+where
+    W: Write + fmt::Debug,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("StreamWriter")
+            .field("writer", self.inner.get_ref())
+            .finish()
+    }
+}
+
+impl<W: Write> Write for StreamWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let comp = &mut self.comp;
+        self.inner.write(buf, |src, dst| comp.next_to_vec(src, dst))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let comp = &mut self.comp;
+        self.inner.flush(|src, dst| comp.next_to_vec(src, dst))
+    }
+}
+
+impl<W: Write> Drop for StreamWriter<'_, W> {
+    fn drop(&mut self) {
+        if !self.inner.is_taken() {
+            let _ = self.flush();
+        }
+    }
+}
@@ -1,7 +1,11 @@
 mod api;
+mod io;
+mod ring;
 
 use crate::{common::DICTIONARY_SIZE, lz4, lz4_hc::FavorDecSpeed, Result};
 use api::CompressionContext;
+pub use io::StreamWriter;
+pub use ring::RingCompressor;
 use std::{borrow::Cow, cmp, io::Cursor, pin::Pin};
 
 /// Streaming LZ4_HC compressor.
@@ -55,6 +59,30 @@ impl<'a> Compressor<'a> {
         Ok(comp)
     }
 
+    /// Starts a new stream without a dictionary.
+    ///
+    /// The compression state, which takes about 256 KiB, is reused instead of
+    /// allocating a new one. The compression level and the decompression speed flag
+    /// are kept.
+    pub fn reset(&mut self) {
+        self.ctx.reset();
+        self.dict = Pin::new(Cow::Borrowed(&[]));
+    }
+
+    /// Starts a new stream with a dictionary.
+    ///
+    /// The compression state is reused as in [`reset`].
+    ///
+    /// [`reset`]: #method.reset
+    pub fn reset_with_dict<D>(&mut self, dict: D)
+    where
+        D: Into<Cow<'a, [u8]>>,
+    {
+        self.ctx.reset();
+        self.dict = Pin::new(dict.into());
+        self.ctx.load_dict(&self.dict);
+    }
+
     /// Sets the compression level.
     pub fn set_compression_level(&mut self, level: i32) {
         self.ctx.set_compression_level(level);
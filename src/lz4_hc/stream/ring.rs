@@ -0,0 +1,118 @@
+use super::api::CompressionContext;
+use crate::{
+    common::DICTIONARY_SIZE, lz4, lz4_hc::FavorDecSpeed, Error, ErrorKind, Result, RingBuffer,
+};
+
+/// Streaming LZ4_HC compressor over a ring buffer.
+///
+/// This works like [`lz4::RingCompressor`] with LZ4_HC compression:
+/// every message is kept in a contiguous ring buffer of at least 64 KiB plus
+/// the maximum message size, so [`Compressor`]'s copy of the history
+/// after every call is not needed.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::{lz4, lz4_hc};
+///
+/// let mut comp = lz4_hc::RingCompressor::new(1024)?;
+/// let mut decomp = lz4::Decompressor::new()?;
+///
+/// for msg in &[&b"Hello world!"[..], b"Hello again!"] {
+///     let mut buf = [0u8; 256];
+///     let len = comp.next(msg, &mut buf)?;
+///     assert_eq!(decomp.next(&buf[..len], msg.len())?, &msg[..]);
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// [`lz4::RingCompressor`]: ../lz4/struct.RingCompressor.html
+/// [`Compressor`]: struct.Compressor.html
+pub struct RingCompressor<'a> {
+    ctx: CompressionContext,
+    ring: RingBuffer<'a>,
+}
+
+impl<'a> RingCompressor<'a> {
+    /// Creates a new `RingCompressor` for messages up to `max_message_size` bytes.
+    ///
+    /// The ring buffer holds 64 KiB plus `max_message_size` bytes.
+    pub fn new(max_message_size: usize) -> Result<Self> {
+        Ok(Self {
+            ctx: CompressionContext::new()?,
+            ring: RingBuffer::new(DICTIONARY_SIZE, DICTIONARY_SIZE + max_message_size),
+        })
+    }
+
+    /// Creates a new `RingCompressor` over a ring buffer provided by the caller.
+    ///
+    /// Messages can be as long as `buf.len()` minus 64 KiB.
+    /// Fails with [`ErrorKind::InitializationFailed`] if `buf` is not longer than 64 KiB.
+    ///
+    /// [`ErrorKind::InitializationFailed`]: ../enum.ErrorKind.html#variant.InitializationFailed
+    pub fn with_buffer(buf: &'a mut [u8]) -> Result<Self> {
+        Ok(Self {
+            ctx: CompressionContext::new()?,
+            ring: RingBuffer::with_buffer(DICTIONARY_SIZE, buf)?,
+        })
+    }
+
+    /// Returns the maximum length of a message.
+    pub fn max_message_size(&self) -> usize {
+        self.ring.max_message_size()
+    }
+
+    /// Sets the compression level.
+    pub fn set_compression_level(&mut self, level: i32) {
+        self.ctx.set_compression_level(level);
+    }
+
+    /// Sets the decompression speed mode flag.
+    pub fn set_favor_dec_speed(&mut self, dec_speed: FavorDecSpeed) {
+        self.ctx
+            .set_favor_dec_speed(dec_speed == FavorDecSpeed::Enabled);
+    }
+
+    /// Performs LZ4_HC streaming compression.
+    ///
+    /// `src` is copied into the ring buffer and compressed there.
+    /// Fails with [`ErrorKind::CompressionFailed`] if `src` is longer than
+    /// [`max_message_size`].
+    ///
+    /// Returns the number of bytes written into the destination buffer.
+    ///
+    /// [`ErrorKind::CompressionFailed`]: ../enum.ErrorKind.html#variant.CompressionFailed
+    /// [`max_message_size`]: #method.max_message_size
+    pub fn next(&mut self, src: &[u8], dst: &mut [u8]) -> Result<usize> {
+        self.next_with(src.len(), |slot| slot.copy_from_slice(src), dst)
+    }
+
+    /// Performs LZ4_HC streaming compression on a message written directly into the ring buffer.
+    ///
+    /// `fill` receives the `len` bytes of the ring buffer that hold the next message,
+    /// and writes the message into them.
+    ///
+    /// Returns the number of bytes written into the destination buffer.
+    pub fn next_with<F>(&mut self, len: usize, fill: F, dst: &mut [u8]) -> Result<usize>
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        let slot = self
+            .ring
+            .next_slot(len)
+            .ok_or_else(|| Error::new(ErrorKind::CompressionFailed))?;
+        fill(slot);
+        self.ctx.next(slot, dst.as_mut_ptr(), dst.len())
+    }
+
+    /// Appends compressed data to Vec<u8>.
+    ///
+    /// Returns the number of bytes appended to the given `Vec<u8>`.
+    pub fn next_to_vec(&mut self, src: &[u8], dst: &mut Vec<u8>) -> Result<usize> {
+        let orig_len = dst.len();
+        dst.resize(orig_len + lz4::max_compressed_size(src.len()), 0);
+        let result = self.next(src, &mut dst[orig_len..]);
+        dst.truncate(orig_len + result.as_ref().unwrap_or(&0));
+        result
+    }
+}
@@ -10,10 +10,18 @@ use super::{
 };
 use crate::lz4f::{Error, ErrorKind, FrameInfo, Preferences, Result};
 
-use std::{mem::MaybeUninit, os::raw::c_void, ptr::NonNull};
+use std::{
+    mem::MaybeUninit,
+    os::raw::c_void,
+    ptr::{self, NonNull},
+};
 
 pub const LZ4F_MIN_SIZE_TO_KNOW_HEADER_LENGTH: usize = 5;
 pub const LZ4F_HEADER_SIZE_MAX: usize = 19;
+pub const LZ4F_MAGIC_NUMBER: u32 = 0x184D_2204;
+pub const LZ4F_MAGIC_SKIPPABLE_START: u32 = 0x184D_2A50;
+pub const LZ4F_MAGIC_SKIPPABLE_MASK: u32 = 0xFFFF_FFF0;
+pub const LZ4F_BLOCK_UNCOMPRESSED_FLAG: u32 = 0x8000_0000;
 
 pub struct CompressionContext {
     ctx: NonNull<LZ4FCompressionCtx>,
@@ -47,7 +55,7 @@ impl CompressionContext {
                     self.ctx.as_ptr(),
                     dst as *mut c_void,
                     dst_len,
-                    (*dict.handle()).0.as_ptr(),
+                    dict.handle().0.as_ptr(),
                     prefs as *const Preferences,
                 )
             } else {
@@ -104,8 +112,31 @@ impl CompressionContext {
         result_from_code(code).map(|_| code)
     }
 
+    pub fn compress_frame(
+        &mut self,
+        dst: *mut u8,
+        dst_len: usize,
+        src: &[u8],
+        dict: Option<&Dictionary>,
+        prefs: &Preferences,
+    ) -> Result<usize> {
+        let dict = dict.map_or(ptr::null(), |dict| dict.handle().0.as_ptr() as *const _);
+        let code = unsafe {
+            binding::LZ4F_compressFrame_usingCDict(
+                self.ctx.as_ptr(),
+                dst as *mut c_void,
+                dst_len,
+                src.as_ptr() as *const c_void,
+                src.len(),
+                dict,
+                prefs as *const Preferences,
+            )
+        } as usize;
+        result_from_code(code).map(|_| code)
+    }
+
     pub fn compress_bound(src_size: usize, prefs: &Preferences) -> usize {
-        unsafe { binding::LZ4F_compressBound(src_size as usize, prefs as *const Preferences) }
+        unsafe { binding::LZ4F_compressBound(src_size, prefs as *const Preferences) }
     }
 }
 
@@ -119,6 +150,7 @@ impl Drop for CompressionContext {
 
 pub struct DecompressionContext {
     ctx: NonNull<LZ4FDecompressionCtx>,
+    skip_checksums: bool,
 }
 
 unsafe impl Send for DecompressionContext {}
@@ -135,23 +167,33 @@ impl DecompressionContext {
                 Ok(Self {
                     ctx: NonNull::new(ctx.assume_init())
                         .ok_or_else(|| crate::Error::new(crate::ErrorKind::InitializationFailed))?,
+                    skip_checksums: false,
                 })
             })
         }
     }
 
+    /// Once enabled, checksums are skipped until the end of the current frame.
+    pub fn set_skip_checksums(&mut self, flag: bool) {
+        self.skip_checksums = flag;
+    }
+
+    pub fn skip_checksums(&self) -> bool {
+        self.skip_checksums
+    }
+
     pub fn get_frame_info(&self, src: &[u8]) -> Result<(FrameInfo, usize)> {
         let mut info = MaybeUninit::<FrameInfo>::uninit();
         let mut src_len = src.len();
         let code = unsafe {
             binding::LZ4F_getFrameInfo(
                 self.ctx.as_ptr(),
-                info.as_mut_ptr() as *mut FrameInfo,
+                info.as_mut_ptr(),
                 src.as_ptr() as *const c_void,
                 &mut src_len as *mut usize,
             )
         };
-        result_from_code(code).map(|_| (unsafe { info.assume_init() }, src_len as usize))
+        result_from_code(code).map(|_| (unsafe { info.assume_init() }, src_len))
     }
 
     pub fn decompress_dict(
@@ -160,10 +202,24 @@ impl DecompressionContext {
         dst: &mut [u8],
         dict: &[u8],
         stable_dst: bool,
+    ) -> Result<(usize, usize, usize)> {
+        // liblz4 only writes initialized bytes, so `dst` never ends up uninitialized.
+        let dst = unsafe {
+            std::slice::from_raw_parts_mut(dst.as_mut_ptr() as *mut MaybeUninit<u8>, dst.len())
+        };
+        self.decompress_dict_uninit(src, dst, dict, stable_dst)
+    }
+
+    pub fn decompress_dict_uninit(
+        &mut self,
+        src: &[u8],
+        dst: &mut [MaybeUninit<u8>],
+        dict: &[u8],
+        stable_dst: bool,
     ) -> Result<(usize, usize, usize)> {
         let mut dst_len = dst.len();
         let mut src_len = src.len();
-        let opt = LZ4FDecompressionOptions::stable(stable_dst);
+        let opt = LZ4FDecompressionOptions::new(stable_dst, self.skip_checksums);
         let code = unsafe {
             binding::LZ4F_decompress_usingDict(
                 self.ctx.as_ptr(),
@@ -176,7 +232,7 @@ impl DecompressionContext {
                 &opt as *const LZ4FDecompressionOptions,
             )
         };
-        result_from_code(code).map(|_| (src_len as usize, dst_len as usize, code as usize))
+        result_from_code(code).map(|_| (src_len, dst_len, code))
     }
 
     pub fn reset(&mut self) {
@@ -195,7 +251,7 @@ impl Drop for DecompressionContext {
 }
 
 pub fn compress_frame_bound(src_size: usize, prefs: &Preferences) -> usize {
-    unsafe { binding::LZ4F_compressFrameBound(src_size as usize, prefs as *const Preferences) }
+    unsafe { binding::LZ4F_compressFrameBound(src_size, prefs as *const Preferences) }
 }
 
 pub fn header_size(src: &[u8]) -> usize {
@@ -216,7 +272,7 @@ pub fn compress(src: &[u8], dst: *mut u8, dst_len: usize, prefs: &Preferences) -
 }
 
 fn result_from_code(code: usize) -> Result<()> {
-    Err(Error::new(match code.wrapping_neg() {
+    Err(Error::lz4f(match code.wrapping_neg() {
         1 => ErrorKind::Generic,
         2 => ErrorKind::MaxBlockSizeInvalid,
         3 => ErrorKind::BlockModeInvalid,
@@ -236,6 +292,10 @@ fn result_from_code(code: usize) -> Result<()> {
         17 => ErrorKind::HeaderChecksumInvalid,
         18 => ErrorKind::ContentChecksumInvalid,
         19 => ErrorKind::FrameDecodingAlreadyStarted,
+        20 => ErrorKind::CompressionStateUninitialized,
+        21 => ErrorKind::ParameterNull,
+        22 => ErrorKind::IoWrite,
+        23 => ErrorKind::IoRead,
         _ => return Ok(()),
     }))
 }
@@ -249,7 +309,7 @@ impl DictionaryHandle {
     pub fn new(data: &[u8]) -> Result<Self> {
         let dict = unsafe { binding::LZ4F_createCDict(data.as_ptr() as *const c_void, data.len()) };
         NonNull::new(dict)
-            .ok_or_else(|| crate::Error::new(crate::ErrorKind::InitializationFailed).into())
+            .ok_or_else(|| crate::Error::new(crate::ErrorKind::InitializationFailed))
             .map(Self)
     }
 }
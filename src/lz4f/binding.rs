@@ -39,13 +39,15 @@ impl LZ4FCompressionOptions {
 #[repr(C)]
 pub struct LZ4FDecompressionOptions {
     pub stable_dst: c_uint,
-    pub _reserved: [c_uint; 3],
+    pub skip_checksums: c_uint,
+    pub _reserved: [c_uint; 2],
 }
 
 impl LZ4FDecompressionOptions {
-    pub fn stable(stable: bool) -> Self {
+    pub fn new(stable: bool, skip_checksums: bool) -> Self {
         Self {
-            stable_dst: if stable { 1 } else { 0 },
+            stable_dst: stable.into(),
+            skip_checksums: skip_checksums.into(),
             ..Default::default()
         }
     }
@@ -62,6 +64,15 @@ extern "C" {
         src_size: size_t,
         prefs: *const Preferences,
     ) -> size_t;
+    pub fn LZ4F_compressFrame_usingCDict(
+        ctx: *mut LZ4FCompressionCtx,
+        dst_buffer: *mut c_void,
+        dst_capacity: size_t,
+        src_buffer: *const c_void,
+        src_size: size_t,
+        dict: *const LZ4FCompressionDict,
+        prefs: *const Preferences,
+    ) -> size_t;
     pub fn LZ4F_decompress_usingDict(
         ctx: *mut LZ4FDecompressionCtx,
         dst_buffer: *mut c_void,
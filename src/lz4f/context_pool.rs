@@ -0,0 +1,125 @@
+use super::{
+    api::{CompressionContext, DecompressionContext},
+    frame, DecompressionLimits, Dictionary, Preferences, Result,
+};
+use std::sync::{Mutex, PoisonError};
+
+/// A thread-safe pool of reusable LZ4F compression and decompression contexts.
+///
+/// [`compress_to_vec`] and [`decompress_to_vec`] set up a context for every call,
+/// which dominates the cost for small messages. `ContextPool` keeps up to
+/// `capacity` idle contexts of each kind and hands them out to concurrent callers.
+/// When the pool runs dry a new context is created, and contexts returned to
+/// a full pool are freed.
+///
+/// [`compress_to_vec`]: fn.compress_to_vec.html
+/// [`decompress_to_vec`]: fn.decompress_to_vec.html
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f::{ContextPool, Preferences};
+///
+/// let pool = ContextPool::new(16);
+/// let data = b"The quick brown fox jumps over the lazy dog.";
+///
+/// let mut buf = Vec::new();
+/// pool.compress_to_vec(data, &mut buf, &Preferences::default())?;
+///
+/// let mut decomp = Vec::new();
+/// pool.decompress_to_vec(&buf, &mut decomp)?;
+/// assert_eq!(decomp.as_slice(), &data[..]);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct ContextPool {
+    comp: Mutex<Vec<CompressionContext>>,
+    decomp: Mutex<Vec<DecompressionContext>>,
+    capacity: usize,
+    dict: Option<Dictionary>,
+}
+
+impl ContextPool {
+    /// Creates a new `ContextPool` that keeps up to `capacity` idle contexts of each kind.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            comp: Mutex::new(Vec::new()),
+            decomp: Mutex::new(Vec::new()),
+            capacity,
+            dict: None,
+        }
+    }
+
+    /// Creates a new `ContextPool` whose frames are compressed and decompressed
+    /// with `dict`.
+    pub fn with_dict(capacity: usize, dict: Dictionary) -> Self {
+        Self {
+            dict: Some(dict),
+            ..Self::new(capacity)
+        }
+    }
+
+    /// Returns the maximum number of idle contexts kept for each kind.
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Appends a compressed frame to `Vec<u8>` using a pooled context.
+    ///
+    /// Returns the number of bytes appended to the given `Vec<u8>`.
+    pub fn compress_to_vec(
+        &self,
+        src: &[u8],
+        dst: &mut Vec<u8>,
+        prefs: &Preferences,
+    ) -> Result<usize> {
+        let dict = self.dict.as_ref();
+        self.with_ctx(
+            &self.comp,
+            || CompressionContext::new(None),
+            |ctx| frame::compress_to_vec_with_ctx(ctx, src, dst, dict, prefs),
+        )
+    }
+
+    /// Decompresses an LZ4 frame using a pooled context.
+    ///
+    /// Returns the number of bytes appended to the given `Vec<u8>`.
+    pub fn decompress_to_vec(&self, src: &[u8], dst: &mut Vec<u8>) -> Result<usize> {
+        self.decompress_to_vec_with_limits(src, dst, &Default::default())
+    }
+
+    /// Decompresses an LZ4 frame within the given limits using a pooled context.
+    ///
+    /// See [`decompress_to_vec_with_limits`] for details.
+    ///
+    /// [`decompress_to_vec_with_limits`]: fn.decompress_to_vec_with_limits.html
+    pub fn decompress_to_vec_with_limits(
+        &self,
+        src: &[u8],
+        dst: &mut Vec<u8>,
+        limits: &DecompressionLimits,
+    ) -> Result<usize> {
+        let dict = self.dict.as_ref().map_or(&[][..], Dictionary::data);
+        self.with_ctx(&self.decomp, DecompressionContext::new, |ctx| {
+            ctx.reset();
+            frame::decompress_to_vec_with_ctx(ctx, src, dst, dict, limits)
+        })
+    }
+
+    fn with_ctx<T, C, F, R>(&self, pool: &Mutex<Vec<T>>, create: C, f: F) -> Result<R>
+    where
+        C: FnOnce() -> Result<T>,
+        F: FnOnce(&mut T) -> Result<R>,
+    {
+        let ctx = pool.lock().unwrap_or_else(PoisonError::into_inner).pop();
+        let mut ctx = match ctx {
+            Some(ctx) => ctx,
+            None => create()?,
+        };
+        let result = f(&mut ctx);
+        let mut pool = pool.lock().unwrap_or_else(PoisonError::into_inner);
+        if pool.len() < self.capacity {
+            pool.push(ctx);
+        }
+        result
+    }
+}
@@ -1,18 +1,32 @@
 use super::{api::DictionaryHandle, Result};
+use crate::common::DICTIONARY_SIZE;
 use std::sync::Arc;
 
 /// A pre-compiled dictionary for the efficient compression.
 #[derive(Clone)]
-pub struct Dictionary(Arc<DictionaryHandle>);
+pub struct Dictionary {
+    handle: Arc<DictionaryHandle>,
+    data: Arc<[u8]>,
+}
 
 impl Dictionary {
     /// Builds a new `Dictionary`.
+    ///
+    /// Only the last 64 KiB of `data` are used.
     pub fn new(data: &[u8]) -> Result<Self> {
-        Ok(Self(Arc::new(DictionaryHandle::new(data)?)))
+        let data = &data[data.len().saturating_sub(DICTIONARY_SIZE)..];
+        Ok(Self {
+            handle: Arc::new(DictionaryHandle::new(data)?),
+            data: data.into(),
+        })
     }
 
     pub(crate) fn handle(&self) -> &DictionaryHandle {
-        &self.0
+        &self.handle
+    }
+
+    pub(crate) fn data(&self) -> &[u8] {
+        &self.data
     }
 }
 
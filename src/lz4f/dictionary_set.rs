@@ -0,0 +1,181 @@
+use super::{
+    api::{CompressionContext, DecompressionContext},
+    frame, DecompressionLimits, Dictionary, Preferences, Result,
+};
+use crate::{Error, ErrorKind};
+use std::cmp;
+
+/// A set of dictionaries identified by their dictionary ids.
+///
+/// `DictionarySet` compresses each message with the dictionary that gives the smallest
+/// output and records its id in the frame header, so that the matching dictionary
+/// can be resolved again on decompression.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f::{Dictionary, DictionarySet, Preferences};
+///
+/// let mut set = DictionarySet::new();
+/// set.insert(
+///     1,
+///     Dictionary::new(b"The quick brown fox jumps over the lazy dog.")?,
+/// );
+/// set.insert(
+///     2,
+///     Dictionary::new(b"Lorem ipsum dolor sit amet, consectetur adipiscing elit.")?,
+/// );
+///
+/// let data = b"Lorem ipsum dolor sit amet.";
+/// let mut buf = Vec::new();
+/// set.compress_to_vec(data, &mut buf, &Preferences::default())?;
+///
+/// let mut decomp = Vec::new();
+/// set.decompress_to_vec(&buf, &mut decomp)?;
+/// assert_eq!(decomp.as_slice(), &data[..]);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct DictionarySet {
+    entries: Vec<(u32, Dictionary)>,
+    sample_size: usize,
+}
+
+impl DictionarySet {
+    /// Creates a new empty `DictionarySet`.
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            sample_size: usize::MAX,
+        }
+    }
+
+    /// Inserts a dictionary with the given id.
+    ///
+    /// If the set already has a dictionary with the same id, it is replaced.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dict_id` is `0`, which denotes a frame without a dictionary.
+    pub fn insert(&mut self, dict_id: u32, dict: Dictionary) {
+        assert_ne!(dict_id, 0, "dictionary id 0 is reserved");
+        if let Some(entry) = self.entries.iter_mut().find(|(id, _)| *id == dict_id) {
+            entry.1 = dict;
+        } else {
+            self.entries.push((dict_id, dict));
+        }
+    }
+
+    /// Returns the dictionary with the given id.
+    pub fn get(&self, dict_id: u32) -> Option<&Dictionary> {
+        self.entries
+            .iter()
+            .find(|(id, _)| *id == dict_id)
+            .map(|(_, dict)| dict)
+    }
+
+    /// Sets the maximum number of leading bytes of a message used to pick a dictionary.
+    ///
+    /// By default, every candidate compresses the whole message.
+    /// Smaller values make the selection cheaper for large messages.
+    pub fn set_sample_size(&mut self, size: usize) {
+        self.sample_size = cmp::max(size, 1);
+    }
+
+    /// Returns the id of the dictionary that gives the smallest output for `src`.
+    ///
+    /// `0` means that `src` compresses best without a dictionary.
+    pub fn select(&self, src: &[u8], prefs: &Preferences) -> Result<u32> {
+        let sample = &src[..cmp::min(src.len(), self.sample_size)];
+        let mut ctx = CompressionContext::new(None)?;
+        let mut buf = Vec::new();
+        let mut best = (0, usize::MAX);
+        for (dict_id, dict) in self.candidates() {
+            buf.clear();
+            let len = compress_to_vec(&mut ctx, sample, &mut buf, dict_id, dict, prefs)?;
+            if len < best.1 {
+                best = (dict_id, len);
+            }
+        }
+        Ok(best.0)
+    }
+
+    /// Compresses `src` with the best dictionary and appends the frame to `Vec<u8>`.
+    ///
+    /// The id of the selected dictionary is written into the frame header.
+    ///
+    /// Returns the number of bytes appended to the given `Vec<u8>`.
+    pub fn compress_to_vec(
+        &self,
+        src: &[u8],
+        dst: &mut Vec<u8>,
+        prefs: &Preferences,
+    ) -> Result<usize> {
+        let dict_id = self.select(src, prefs)?;
+        let dict = self.get(dict_id);
+        let mut ctx = CompressionContext::new(None)?;
+        compress_to_vec(&mut ctx, src, dst, dict_id, dict, prefs)
+    }
+
+    /// Decompresses an LZ4 frame with the dictionary specified by its header.
+    ///
+    /// Returns the number of bytes appended to the given `Vec<u8>`.
+    pub fn decompress_to_vec(&self, src: &[u8], dst: &mut Vec<u8>) -> Result<usize> {
+        self.decompress_to_vec_with_limits(src, dst, &Default::default())
+    }
+
+    /// Decompresses an LZ4 frame within the given limits with the dictionary
+    /// specified by its header.
+    ///
+    /// See [`decompress_to_vec_with_limits`] for details.
+    ///
+    /// [`decompress_to_vec_with_limits`]: fn.decompress_to_vec_with_limits.html
+    pub fn decompress_to_vec_with_limits(
+        &self,
+        src: &[u8],
+        dst: &mut Vec<u8>,
+        limits: &DecompressionLimits,
+    ) -> Result<usize> {
+        let mut ctx = DecompressionContext::new()?;
+        let (frame_info, header_len) = ctx.get_frame_info(src)?;
+        let dict = match frame_info.dict_id() {
+            0 => &[][..],
+            dict_id => self
+                .get(dict_id)
+                .ok_or_else(|| Error::new(ErrorKind::DictionaryNotFound))?
+                .data(),
+        };
+        frame::decompress_body_to_vec_with_ctx(
+            &mut ctx,
+            &frame_info,
+            src,
+            header_len,
+            dst,
+            dict,
+            limits,
+        )
+    }
+
+    fn candidates(&self) -> impl Iterator<Item = (u32, Option<&Dictionary>)> {
+        std::iter::once((0, None)).chain(self.entries.iter().map(|(id, dict)| (*id, Some(dict))))
+    }
+}
+
+impl Default for DictionarySet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn compress_to_vec(
+    ctx: &mut CompressionContext,
+    src: &[u8],
+    dst: &mut Vec<u8>,
+    dict_id: u32,
+    dict: Option<&Dictionary>,
+    prefs: &Preferences,
+) -> Result<usize> {
+    let mut prefs = *prefs;
+    prefs.set_dict_id(dict_id);
+    frame::compress_to_vec_with_ctx(ctx, src, dst, dict, &prefs)
+}
@@ -1,6 +1,10 @@
-use std::{convert, fmt, io};
+use std::fmt;
 
 /// A list specifying general categories of LZ4F error.
+///
+/// LZ4F errors are reported as [`lzzzz::ErrorKind::Lz4f`].
+///
+/// [`lzzzz::ErrorKind::Lz4f`]: ../enum.ErrorKind.html#variant.Lz4f
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[non_exhaustive]
 #[allow(missing_docs)]
@@ -24,6 +28,10 @@ pub enum ErrorKind {
     HeaderChecksumInvalid,
     ContentChecksumInvalid,
     FrameDecodingAlreadyStarted,
+    CompressionStateUninitialized,
+    ParameterNull,
+    IoWrite,
+    IoRead,
 }
 
 impl fmt::Display for ErrorKind {
@@ -32,41 +40,10 @@ impl fmt::Display for ErrorKind {
     }
 }
 
-/// The error type for LZ4F operations.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-pub enum Error {
-    Lz4f(ErrorKind),
-    Common(crate::ErrorKind),
-}
-
-impl Error {
-    pub(super) const fn new(kind: ErrorKind) -> Self {
-        Self::Lz4f(kind)
-    }
-}
-
-impl convert::From<Error> for io::Error {
-    fn from(err: Error) -> Self {
-        Self::new(io::ErrorKind::Other, err)
-    }
-}
-
-impl convert::From<crate::Error> for Error {
-    fn from(err: crate::Error) -> Self {
-        Self::Common(err.kind())
-    }
-}
-
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), fmt::Error> {
-        match self {
-            Self::Lz4f(kind) => <ErrorKind as fmt::Display>::fmt(kind, f),
-            Self::Common(kind) => <crate::ErrorKind as fmt::Display>::fmt(kind, f),
-        }
-    }
-}
-
-impl std::error::Error for Error {}
+/// The error type for LZ4F operations, which is the same as [`lzzzz::Error`].
+///
+/// [`lzzzz::Error`]: ../struct.Error.html
+pub type Error = crate::Error;
 
 /// A specialized [`Result`] type for LZ4F operations.
 ///
@@ -1,8 +1,16 @@
 //! LZ4 Frame Compressor/Decompressor
 
-use super::{api, Result};
-use crate::{common::DEFAULT_BUF_SIZE, lz4f::Preferences, Error, ErrorKind};
-use std::{cell::RefCell, ops::Deref};
+use super::{
+    api,
+    limits::{DecompressionLimits, LimitTracker},
+    stats::{self, StatsTracker},
+    Dictionary, Error, FrameInfo, Result,
+};
+use crate::{
+    lz4f::{AutoFlush, ContentChecksum, Preferences},
+    ErrorKind,
+};
+use std::{cell::RefCell, cmp};
 
 /// Calculates the maximum size of the compressed output.
 ///
@@ -50,6 +58,50 @@ pub fn compress(src: &[u8], dst: &mut [u8], prefs: &Preferences) -> Result<usize
     compress_to_ptr(src, dst.as_mut_ptr(), dst.len(), prefs)
 }
 
+/// Performs LZ4F compression, and returns the content checksum of the frame.
+///
+/// This behaves like [`compress`], and also returns the content checksum written
+/// at the end of the frame if [`ContentChecksum`] is enabled in `prefs`.
+/// The checksum is read from the output, so the data is not hashed again.
+///
+/// Returns the number of bytes written into the destination buffer and the checksum.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::{lz4f, xxhash};
+///
+/// let prefs = lz4f::PreferencesBuilder::new()
+///     .content_checksum(lz4f::ContentChecksum::Enabled)
+///     .build();
+/// let data = b"The quick brown fox jumps over the lazy dog.";
+/// let mut buf = [0u8; 2048];
+///
+/// let (len, checksum) = lz4f::compress_with_checksum(data, &mut buf, &prefs)?;
+/// assert_eq!(checksum, Some(xxhash::lz4f_content_checksum(data)));
+/// assert_eq!(lz4f::content_checksum(&buf[..len]), checksum);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// [`compress`]: fn.compress.html
+/// [`ContentChecksum`]: enum.ContentChecksum.html
+pub fn compress_with_checksum(
+    src: &[u8],
+    dst: &mut [u8],
+    prefs: &Preferences,
+) -> Result<(usize, Option<u32>)> {
+    let len = compress(src, dst, prefs)?;
+    let checksum = match prefs.frame_info().content_checksum() {
+        ContentChecksum::Enabled => {
+            let mut checksum = [0; 4];
+            checksum.copy_from_slice(&dst[len - 4..len]);
+            Some(u32::from_le_bytes(checksum))
+        }
+        ContentChecksum::Disabled => None,
+    };
+    Ok((len, checksum))
+}
+
 fn compress_to_ptr(src: &[u8], dst: *mut u8, dst_len: usize, prefs: &Preferences) -> Result<usize> {
     let mut prefs = *prefs;
     if prefs.frame_info().content_size() > 0 {
@@ -58,6 +110,51 @@ fn compress_to_ptr(src: &[u8], dst: *mut u8, dst_len: usize, prefs: &Preferences
     api::compress(src, dst, dst_len, &prefs)
 }
 
+/// Performs LZ4F compression on scattered input.
+///
+/// The slices of `src` are compressed into a single frame as if they were concatenated,
+/// without copying them into a temporary buffer.
+///
+/// Ensure that the destination slice has enough capacity.
+/// If `dst.len()` is smaller than `lz4f::max_compressed_size()` of the total length
+/// of `src`, this function may fail.
+///
+/// Returns the number of bytes written into the destination buffer.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f;
+///
+/// let prefs = lz4f::Preferences::default();
+/// let src: &[&[u8]] = &[b"The quick brown fox ", b"jumps over the lazy dog."];
+/// let mut buf = [0u8; 2048];
+///
+/// let len = lz4f::compress_slices(src, &mut buf, &prefs)?;
+/// let compressed = &buf[..len];
+/// # let mut buf = Vec::new();
+/// # lz4f::decompress_to_vec(compressed, &mut buf)?;
+/// # assert_eq!(buf.as_slice(), &src.concat()[..]);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn compress_slices(src: &[&[u8]], dst: &mut [u8], prefs: &Preferences) -> Result<usize> {
+    let mut prefs = *prefs;
+    if prefs.frame_info().content_size() > 0 {
+        prefs.set_content_size(src.iter().map(|src| src.len()).sum());
+    }
+    // Flushing after every slice would end a block at each of them.
+    prefs.set_auto_flush(AutoFlush::Disabled);
+
+    let mut ctx = api::CompressionContext::new(None)?;
+    let mut offset = ctx.begin(dst.as_mut_ptr(), dst.len(), &prefs)?;
+    for src in src {
+        let dst = &mut dst[offset..];
+        offset += ctx.update(dst.as_mut_ptr(), dst.len(), src, false)?;
+    }
+    let dst = &mut dst[offset..];
+    Ok(offset + ctx.end(dst.as_mut_ptr(), dst.len(), false)?)
+}
+
 /// Appends a compressed frame to Vec<u8>.
 ///
 /// Returns the number of bytes appended to the given `Vec<u8>`.
@@ -95,6 +192,66 @@ pub fn compress_to_vec(src: &[u8], dst: &mut Vec<u8>, prefs: &Preferences) -> Re
     }
 }
 
+/// Returns the content checksum stored at the end of an LZ4F frame.
+///
+/// The checksum is the xxHash32 of the decompressed content, as computed by liblz4
+/// during compression. This is handy for deduplication, because the content need
+/// not be hashed again. If `frame` holds several frames, the last one is used.
+///
+/// Returns `None` if the frame is incomplete or has no content checksum.
+/// The checksum is not verified against the content.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::{lz4f, xxhash};
+///
+/// let prefs = lz4f::PreferencesBuilder::new()
+///     .content_checksum(lz4f::ContentChecksum::Enabled)
+///     .build();
+/// let data = b"The quick brown fox jumps over the lazy dog.";
+/// let mut buf = Vec::new();
+/// lz4f::compress_to_vec(data, &mut buf, &prefs)?;
+///
+/// assert_eq!(
+///     lz4f::content_checksum(&buf),
+///     Some(xxhash::lz4f_content_checksum(data))
+/// );
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn content_checksum(frame: &[u8]) -> Option<u32> {
+    stats::stored_content_checksum(frame)
+}
+
+/// Appends a compressed frame to `Vec<u8>` with the given context and dictionary.
+pub(super) fn compress_to_vec_with_ctx(
+    ctx: &mut api::CompressionContext,
+    src: &[u8],
+    dst: &mut Vec<u8>,
+    dict: Option<&Dictionary>,
+    prefs: &Preferences,
+) -> Result<usize> {
+    let mut prefs = *prefs;
+    if prefs.frame_info().content_size() > 0 {
+        prefs.set_content_size(src.len());
+    }
+
+    let orig_len = dst.len();
+    dst.reserve(max_compressed_size(src.len(), &prefs));
+    #[allow(unsafe_code)]
+    unsafe {
+        let result = ctx.compress_frame(
+            dst.as_mut_ptr().add(orig_len),
+            dst.capacity() - orig_len,
+            src,
+            dict,
+            &prefs,
+        );
+        dst.set_len(orig_len + result.as_ref().unwrap_or(&0));
+        result
+    }
+}
+
 /// Decompresses an LZ4 frame.
 ///
 /// Returns the number of bytes appended to the given `Vec<u8>`.
@@ -119,56 +276,236 @@ pub fn compress_to_vec(src: &[u8], dst: &mut Vec<u8>, prefs: &Preferences) -> Re
 /// # Ok::<(), std::io::Error>(())
 /// ```
 pub fn decompress_to_vec(src: &[u8], dst: &mut Vec<u8>) -> Result<usize> {
-    let header_len = dst.len();
-    let mut src_offset = 0;
-    let mut dst_offset = header_len;
-    DecompressionCtx::with(|ctx| {
-        let mut ctx = ctx.borrow_mut();
-        ctx.reset();
-        loop {
-            dst.resize_with(dst.len() + DEFAULT_BUF_SIZE, Default::default);
-            match ctx.decompress_dict(&src[src_offset..], &mut dst[dst_offset..], &[], false) {
-                Ok((src_len, dst_len, expected)) => {
-                    src_offset += src_len;
-                    dst_offset += dst_len;
-                    if expected == 0 {
-                        dst.resize_with(dst_offset, Default::default);
-                        return Ok(dst_offset - header_len);
-                    } else if src_offset >= src.len() {
-                        dst.resize_with(header_len, Default::default);
-                        return Err(Error::new(ErrorKind::CompressedDataIncomplete).into());
-                    }
-                }
-                Err(err) => {
-                    dst.resize_with(header_len, Default::default);
-                    return Err(err);
-                }
-            }
+    DecompressionState::with_thread_local(|state| state.decompress_to_vec(src, dst))
+}
+
+/// Decompresses an LZ4 frame within the given limits.
+///
+/// This behaves like [`decompress_to_vec`], but fails with
+/// [`ErrorKind::DecompressionLimitExceeded`] as soon as the frame exceeds `limits`.
+/// Nothing is appended to `dst` in that case.
+///
+/// [`decompress_to_vec`]: fn.decompress_to_vec.html
+/// [`ErrorKind::DecompressionLimitExceeded`]: ../enum.ErrorKind.html#variant.DecompressionLimitExceeded
+pub fn decompress_to_vec_with_limits(
+    src: &[u8],
+    dst: &mut Vec<u8>,
+    limits: &DecompressionLimits,
+) -> Result<usize> {
+    DecompressionState::with_thread_local(|state| {
+        state.decompress_to_vec_with_limits(src, dst, limits)
+    })
+}
+
+/// Decompresses an LZ4 frame without verifying its checksums.
+///
+/// This behaves like [`decompress_to_vec`], but neither computes nor verifies
+/// block and content checksums. Use it only for trusted data,
+/// such as frames you produced yourself and already verified at rest.
+///
+/// [`decompress_to_vec`]: fn.decompress_to_vec.html
+pub fn decompress_to_vec_skip_checksums(src: &[u8], dst: &mut Vec<u8>) -> Result<usize> {
+    DecompressionState::with_thread_local(|state| {
+        state.skip_checksums(true);
+        let result = state.decompress_to_vec(src, dst);
+        state.skip_checksums(false);
+        result
+    })
+}
+
+/// Decompresses a frame with the given context.
+pub(super) fn decompress_to_vec_with_ctx(
+    ctx: &mut api::DecompressionContext,
+    src: &[u8],
+    dst: &mut Vec<u8>,
+    dict: &[u8],
+    limits: &DecompressionLimits,
+) -> Result<usize> {
+    let (frame_info, header_len) = match ctx.get_frame_info(src) {
+        Err(err) if err.kind() == super::ErrorKind::FrameHeaderIncomplete.into() => {
+            Err(Error::new(ErrorKind::CompressedDataIncomplete))
         }
+        result => result,
+    }
+    .map_err(|err| err.with_offsets(0, 0).with_frame_index(0))?;
+    decompress_body_to_vec_with_ctx(ctx, &frame_info, src, header_len, dst, dict, limits)
+}
+
+/// Decompresses the rest of a frame whose header of `header_len` bytes
+/// has already been decoded by `ctx`.
+pub(super) fn decompress_body_to_vec_with_ctx(
+    ctx: &mut api::DecompressionContext,
+    frame_info: &FrameInfo,
+    src: &[u8],
+    header_len: usize,
+    dst: &mut Vec<u8>,
+    dict: &[u8],
+    limits: &DecompressionLimits,
+) -> Result<usize> {
+    let orig_len = dst.len();
+    let mut src_offset = header_len;
+    let skip_checksums = ctx.skip_checksums();
+    let result =
+        decompress_body_to_vec_uninit(ctx, frame_info, src, &mut src_offset, dst, dict, limits);
+    ctx.set_skip_checksums(skip_checksums);
+    result.map_err(|err| {
+        let produced = dst.len() - orig_len;
+        dst.truncate(orig_len);
+        err.with_offsets(src_offset as u64, produced as u64)
+            .with_frame_index(0)
     })
 }
 
-struct DecompressionCtx(RefCell<api::DecompressionContext>);
+/// Decompresses `src` from `src_offset` on, which is kept at the start of the data
+/// passed to liblz4 last.
+fn decompress_body_to_vec_uninit(
+    ctx: &mut api::DecompressionContext,
+    frame_info: &FrameInfo,
+    src: &[u8],
+    src_offset: &mut usize,
+    dst: &mut Vec<u8>,
+    dict: &[u8],
+    limits: &DecompressionLimits,
+) -> Result<usize> {
+    // The checksums are verified by `stats`, which can report the values it compared.
+    let mut stats = StatsTracker::decompression();
+    stats.verify_checksums(!ctx.skip_checksums());
+    ctx.set_skip_checksums(true);
+    stats.record(0, &src[..*src_offset]);
 
-impl DecompressionCtx {
-    fn new() -> Self {
-        Self(RefCell::new(api::DecompressionContext::new().unwrap()))
+    let mut src = &src[*src_offset..];
+    let orig_len = dst.len();
+    let mut tracker = LimitTracker::new(*limits);
+    tracker.consume(src.len());
+    tracker.begin_frame(frame_info)?;
+
+    // Reserve the announced content size upfront. A block expands at most
+    // about 255 times, which bounds the reservation for a forged header.
+    let block_size = frame_info.block_size().bytes();
+    let expected_len = match frame_info.content_size() {
+        0 => block_size,
+        len => len,
+    };
+    dst.reserve(cmp::min(
+        cmp::min(expected_len, tracker.remaining()),
+        src.len().saturating_mul(MAX_EXPANSION),
+    ));
+
+    loop {
+        let spare = dst.spare_capacity_mut();
+        let spare_len = cmp::min(spare.len(), tracker.remaining());
+        let (src_len, dst_len, expected) = ctx
+            .decompress_dict_uninit(src, &mut spare[..spare_len], dict, false)
+            .map_err(|err| stats.locate_error(err, src))?;
+        #[allow(unsafe_code)]
+        unsafe {
+            dst.set_len(dst.len() + dst_len);
+        }
+        stats.record_decompressed(&dst[dst.len() - dst_len..], &src[..src_len])?;
+        tracker.produce(dst_len);
+        src = &src[src_len..];
+        *src_offset += src_len;
+        if expected == 0 {
+            return Ok(dst.len() - orig_len);
+        } else if src.is_empty() {
+            return Err(stats.locate_error(Error::new(ErrorKind::CompressedDataIncomplete), src));
+        } else if src_len == 0 && dst_len == 0 {
+            // Grow geometrically, but never past the limits.
+            let additional = cmp::max(block_size, dst.len() - orig_len);
+            match tracker.remaining() {
+                0 => return Err(Error::new(ErrorKind::DecompressionLimitExceeded)),
+                remaining => dst.reserve_exact(cmp::min(additional, remaining)),
+            }
+        }
     }
+}
 
-    fn with<F, R>(f: F) -> R
+/// The upper bound of the LZ4 compression ratio.
+const MAX_EXPANSION: usize = 255;
+
+/// A reusable LZ4F decompression state.
+///
+/// [`decompress_to_vec`] keeps a `DecompressionState` per thread.
+/// Owning one explicitly lets you decide when it is allocated and freed,
+/// e.g. by pooling states across short-lived worker threads.
+///
+/// [`decompress_to_vec`]: fn.decompress_to_vec.html
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f;
+///
+/// let prefs = lz4f::Preferences::default();
+/// let mut state = lz4f::DecompressionState::new()?;
+/// let mut comp = Vec::new();
+/// let mut buf = Vec::new();
+///
+/// for data in [
+///     &b"The quick brown fox"[..],
+///     &b"jumps over the lazy dog."[..],
+/// ] {
+///     comp.clear();
+///     buf.clear();
+///     lz4f::compress_to_vec(data, &mut comp, &prefs)?;
+///     state.decompress_to_vec(&comp, &mut buf)?;
+///     assert_eq!(buf, data);
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct DecompressionState(api::DecompressionContext);
+
+impl DecompressionState {
+    /// Creates a new `DecompressionState`.
+    pub fn new() -> Result<Self> {
+        api::DecompressionContext::new().map(Self)
+    }
+
+    /// Decompresses an LZ4 frame with this state.
+    ///
+    /// See [`decompress_to_vec`] for details.
+    ///
+    /// [`decompress_to_vec`]: fn.decompress_to_vec.html
+    pub fn decompress_to_vec(&mut self, src: &[u8], dst: &mut Vec<u8>) -> Result<usize> {
+        self.decompress_to_vec_with_limits(src, dst, &Default::default())
+    }
+
+    /// Decompresses an LZ4 frame within the given limits with this state.
+    ///
+    /// See [`decompress_to_vec_with_limits`] for details.
+    ///
+    /// [`decompress_to_vec_with_limits`]: fn.decompress_to_vec_with_limits.html
+    pub fn decompress_to_vec_with_limits(
+        &mut self,
+        src: &[u8],
+        dst: &mut Vec<u8>,
+        limits: &DecompressionLimits,
+    ) -> Result<usize> {
+        self.0.reset();
+        decompress_to_vec_with_ctx(&mut self.0, src, dst, &[], limits)
+    }
+
+    /// Sets the 'skip-checksums' mode.
+    ///
+    /// When the 'skip-checksums' mode is enabled, block and content checksums
+    /// are neither computed nor verified. Use it only for trusted data.
+    pub fn skip_checksums(&mut self, flag: bool) {
+        self.0.set_skip_checksums(flag);
+    }
+
+    fn with_thread_local<F, R>(f: F) -> Result<R>
     where
-        F: FnOnce(&RefCell<api::DecompressionContext>) -> R,
+        F: FnOnce(&mut Self) -> Result<R>,
     {
-        DECOMPRESSION_CTX.with(|state| (f)(state))
+        STATE.with(|cell| match &mut *cell.borrow_mut() {
+            Some(state) => f(state),
+            slot @ None => f(slot.insert(Self::new()?)),
+        })
     }
-}
-
-impl Deref for DecompressionCtx {
-    type Target = RefCell<api::DecompressionContext>;
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+    pub(crate) fn free_thread_local() {
+        let _ = STATE.try_with(|state| state.borrow_mut().take());
     }
 }
 
-thread_local!(static DECOMPRESSION_CTX: DecompressionCtx = DecompressionCtx::new());
+thread_local!(static STATE: RefCell<Option<DecompressionState>> = const { RefCell::new(None) });
@@ -0,0 +1,237 @@
+use super::{
+    api::{CompressionContext, DecompressionContext},
+    Dictionary, FrameInfo, Preferences, Result,
+};
+use std::marker::PhantomData;
+
+/// A low-level LZ4F frame encoder working on caller-provided buffers.
+///
+/// A frame is produced by calling [`begin`] once, [`update`] any number of times
+/// and [`end`] once. Every call writes into `dst` and returns the number of bytes written.
+/// After [`end`], the encoder can start a new frame with [`begin`].
+///
+/// [`begin`]: #method.begin
+/// [`update`]: #method.update
+/// [`end`]: #method.end
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f;
+///
+/// let data = b"The quick brown fox jumps over the lazy dog.";
+/// let mut enc = lz4f::FrameEncoder::new(Default::default())?;
+///
+/// let mut buf = vec![0; enc.compress_bound(data.len()) + 32];
+/// let mut len = enc.begin(&mut buf)?;
+/// len += enc.update(data, &mut buf[len..])?;
+/// len += enc.end(&mut buf[len..])?;
+///
+/// # let mut decomp = Vec::new();
+/// # lz4f::decompress_to_vec(&buf[..len], &mut decomp)?;
+/// # assert_eq!(decomp.as_slice(), &data[..]);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct FrameEncoder<'a> {
+    ctx: CompressionContext,
+    prefs: Preferences,
+    phantom: PhantomData<&'a [u8]>,
+}
+
+impl<'a> FrameEncoder<'a> {
+    /// Creates a new `FrameEncoder`.
+    pub fn new(prefs: Preferences) -> Result<Self> {
+        Self::with_ctx(CompressionContext::new(None)?, prefs)
+    }
+
+    /// Creates a new `FrameEncoder` with a dictionary.
+    pub fn with_dict(prefs: Preferences, dict: Dictionary) -> Result<Self> {
+        Self::with_ctx(CompressionContext::new(Some(dict))?, prefs)
+    }
+
+    fn with_ctx(ctx: CompressionContext, prefs: Preferences) -> Result<Self> {
+        Ok(Self {
+            ctx,
+            prefs,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Returns the maximum number of bytes written by a single [`update`] call
+    /// with `src_len` bytes of input, including the output of a following [`end`].
+    ///
+    /// [`update`]: #method.update
+    /// [`end`]: #method.end
+    #[must_use]
+    pub fn compress_bound(&self, src_len: usize) -> usize {
+        CompressionContext::compress_bound(src_len, &self.prefs)
+    }
+
+    /// Writes a frame header and starts a new frame.
+    ///
+    /// `dst` must have room for at least 19 bytes.
+    pub fn begin(&mut self, dst: &mut [u8]) -> Result<usize> {
+        self.ctx.begin(dst.as_mut_ptr(), dst.len(), &self.prefs)
+    }
+
+    /// Compresses `src` into the current frame.
+    ///
+    /// The whole `src` is consumed. Part of it may be kept in the internal buffer
+    /// until the next call. `dst` must be at least [`compress_bound(src.len())`] bytes long.
+    ///
+    /// [`compress_bound(src.len())`]: #method.compress_bound
+    pub fn update(&mut self, src: &[u8], dst: &mut [u8]) -> Result<usize> {
+        self.ctx.update(dst.as_mut_ptr(), dst.len(), src, false)
+    }
+
+    /// Compresses `src` into the current frame, referring to it in place for
+    /// the following blocks.
+    ///
+    /// With linked blocks, this skips copying the tail of `src` into the internal
+    /// dictionary buffer. `src` stays borrowed as long as the encoder lives.
+    pub fn update_stable(&mut self, src: &'a [u8], dst: &mut [u8]) -> Result<usize> {
+        self.ctx.update(dst.as_mut_ptr(), dst.len(), src, true)
+    }
+
+    /// Compresses the buffered input, if any, and writes it out as a block.
+    pub fn flush(&mut self, dst: &mut [u8]) -> Result<usize> {
+        self.ctx.flush(dst.as_mut_ptr(), dst.len(), false)
+    }
+
+    /// Flushes the buffered input and writes the frame footer.
+    pub fn end(&mut self, dst: &mut [u8]) -> Result<usize> {
+        self.ctx.end(dst.as_mut_ptr(), dst.len(), false)
+    }
+}
+
+/// The result of a [`FrameDecoder`] call.
+///
+/// [`FrameDecoder`]: struct.FrameDecoder.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DecodeProgress {
+    consumed: usize,
+    produced: usize,
+    hint: usize,
+}
+
+impl DecodeProgress {
+    /// Returns the number of bytes consumed from `src`.
+    pub const fn consumed(&self) -> usize {
+        self.consumed
+    }
+
+    /// Returns the number of bytes written into `dst`.
+    pub const fn produced(&self) -> usize {
+        self.produced
+    }
+
+    /// Returns the preferred size of the next `src` chunk.
+    ///
+    /// This is only a hint; any size can be passed to the decoder.
+    pub const fn src_size_hint(&self) -> usize {
+        self.hint
+    }
+
+    /// Returns `true` if the frame has been fully decoded and flushed.
+    pub const fn is_frame_end(&self) -> bool {
+        self.hint == 0
+    }
+}
+
+/// A low-level LZ4F frame decoder working on caller-provided buffers.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f;
+///
+/// let data = b"The quick brown fox jumps over the lazy dog.";
+/// let mut comp = Vec::new();
+/// lz4f::compress_to_vec(data, &mut comp, &Default::default())?;
+///
+/// let mut dec = lz4f::FrameDecoder::new()?;
+/// let mut buf = [0u8; 256];
+/// let (mut src, mut dst) = (&comp[..], &mut buf[..]);
+/// let mut len = 0;
+/// loop {
+///     let progress = dec.decode(src, dst)?;
+///     src = &src[progress.consumed()..];
+///     dst = &mut dst[progress.produced()..];
+///     len += progress.produced();
+///     if progress.is_frame_end() {
+///         break;
+///     }
+/// }
+/// assert_eq!(&buf[..len], &data[..]);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct FrameDecoder<'a> {
+    ctx: DecompressionContext,
+    dict: &'a [u8],
+}
+
+impl<'a> FrameDecoder<'a> {
+    /// Creates a new `FrameDecoder`.
+    pub fn new() -> Result<Self> {
+        Self::with_dict(&[])
+    }
+
+    /// Creates a new `FrameDecoder` with a dictionary.
+    pub fn with_dict(dict: &'a [u8]) -> Result<Self> {
+        Ok(Self {
+            ctx: DecompressionContext::new()?,
+            dict,
+        })
+    }
+
+    /// Decodes the frame header at the beginning of `src`.
+    ///
+    /// Returns the frame information and the number of bytes consumed from `src`.
+    /// If the header has already been decoded, nothing is consumed.
+    pub fn frame_info(&mut self, src: &[u8]) -> Result<(FrameInfo, usize)> {
+        self.ctx.get_frame_info(src)
+    }
+
+    /// Decodes `src` into `dst`.
+    ///
+    /// The decoder may stop before consuming the whole `src` when `dst` fills up.
+    pub fn decode(&mut self, src: &[u8], dst: &mut [u8]) -> Result<DecodeProgress> {
+        let (consumed, produced, hint) = self.ctx.decompress_dict(src, dst, self.dict, false)?;
+        Ok(DecodeProgress {
+            consumed,
+            produced,
+            hint,
+        })
+    }
+
+    /// Decodes `src` into `dst`, referring to the decoded data in place
+    /// for the following blocks.
+    ///
+    /// With linked blocks, this skips copying the decoded data into the internal
+    /// history buffer. `dst` stays borrowed as long as the decoder lives, so the
+    /// decoded part is returned as a shared slice, together with the unused rest of `dst`.
+    pub fn decode_stable(
+        &mut self,
+        src: &[u8],
+        dst: &'a mut [u8],
+    ) -> Result<(DecodeProgress, &'a [u8], &'a mut [u8])> {
+        let (consumed, produced, hint) = self.ctx.decompress_dict(src, dst, self.dict, true)?;
+        let (decoded, rest) = dst.split_at_mut(produced);
+        Ok((
+            DecodeProgress {
+                consumed,
+                produced,
+                hint,
+            },
+            decoded,
+            rest,
+        ))
+    }
+
+    /// Resets the decoder to the initial state.
+    ///
+    /// A frame being decoded is discarded.
+    pub fn reset(&mut self) {
+        self.ctx.reset();
+    }
+}
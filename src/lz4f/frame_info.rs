@@ -1,10 +1,11 @@
 use std::os::raw::{c_uint, c_ulonglong};
 
 /// Block size flag.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
 #[non_exhaustive]
 #[repr(C)]
 pub enum BlockSize {
+    #[default]
     Default = 0,
     Max64KB = 4,
     Max256KB = 5,
@@ -12,68 +13,54 @@ pub enum BlockSize {
     Max4MB = 7,
 }
 
-impl Default for BlockSize {
-    fn default() -> Self {
-        Self::Default
+impl BlockSize {
+    /// Returns the maximum size of a block in bytes.
+    pub(crate) const fn bytes(self) -> usize {
+        match self {
+            Self::Default | Self::Max64KB => 64 << 10,
+            Self::Max256KB => 256 << 10,
+            Self::Max1MB => 1 << 20,
+            Self::Max4MB => 4 << 20,
+        }
     }
 }
 
 /// Block mode flag.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
 #[repr(C)]
 pub enum BlockMode {
+    #[default]
     Linked,
     Independent,
 }
 
-impl Default for BlockMode {
-    fn default() -> Self {
-        Self::Linked
-    }
-}
-
 /// Content checksum flag.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
 #[repr(C)]
 pub enum ContentChecksum {
+    #[default]
     Disabled,
     Enabled,
 }
 
-impl Default for ContentChecksum {
-    fn default() -> Self {
-        Self::Disabled
-    }
-}
-
 /// Frame type flag.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
 #[repr(C)]
 pub enum FrameType {
+    #[default]
     Frame,
     SkippableFrame,
 }
 
-impl Default for FrameType {
-    fn default() -> Self {
-        Self::Frame
-    }
-}
-
 /// Block checksum flag.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
 #[repr(C)]
 pub enum BlockChecksum {
+    #[default]
     Disabled,
     Enabled,
 }
 
-impl Default for BlockChecksum {
-    fn default() -> Self {
-        Self::Disabled
-    }
-}
-
 /// LZ4 Frame parameters.
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
 #[repr(C)]
@@ -115,7 +102,7 @@ impl FrameInfo {
 
     /// Returns the dictionary id.
     pub const fn dict_id(&self) -> u32 {
-        self.dict_id as u32
+        self.dict_id
     }
 
     /// Returns the block checksum.
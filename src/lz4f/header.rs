@@ -0,0 +1,132 @@
+//! Parsing of LZ4F frame and block headers.
+
+use super::{
+    api::{
+        LZ4F_BLOCK_UNCOMPRESSED_FLAG, LZ4F_MAGIC_NUMBER, LZ4F_MAGIC_SKIPPABLE_MASK,
+        LZ4F_MAGIC_SKIPPABLE_START,
+    },
+    BlockSize, Error, ErrorKind, Result,
+};
+use crate::xxhash::lz4f_header_checksum;
+
+/// The kind of frame a magic number starts.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Magic {
+    Frame,
+    Skippable,
+    Unknown,
+}
+
+impl Magic {
+    pub const fn parse(value: u32) -> Self {
+        if value == LZ4F_MAGIC_NUMBER {
+            Self::Frame
+        } else if value & LZ4F_MAGIC_SKIPPABLE_MASK == LZ4F_MAGIC_SKIPPABLE_START {
+            Self::Skippable
+        } else {
+            Self::Unknown
+        }
+    }
+}
+
+/// The FLG byte of a frame descriptor.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Flags(pub u8);
+
+impl Flags {
+    pub const fn independent_blocks(self) -> bool {
+        self.0 & 0x20 != 0
+    }
+
+    pub const fn block_checksum(self) -> bool {
+        self.0 & 0x10 != 0
+    }
+
+    pub const fn content_size(self) -> bool {
+        self.0 & 0x08 != 0
+    }
+
+    pub const fn content_checksum(self) -> bool {
+        self.0 & 0x04 != 0
+    }
+
+    pub const fn dict_id(self) -> bool {
+        self.0 & 0x01 != 0
+    }
+
+    /// Returns the length of the frame header, from the magic number to the header checksum.
+    pub const fn header_len(self) -> usize {
+        7 + if self.content_size() { 8 } else { 0 } + if self.dict_id() { 4 } else { 0 }
+    }
+}
+
+/// A frame header which has passed the checks of liblz4.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct FrameHeader {
+    pub flags: Flags,
+    pub block_size: BlockSize,
+    pub content_size: Option<u64>,
+}
+
+impl FrameHeader {
+    /// Parses a frame header, including the magic number.
+    ///
+    /// `header` must be as long as [`Flags::header_len`] tells.
+    pub fn parse(header: &[u8]) -> Result<Self> {
+        let flags = Flags(header[4]);
+        let bd = header[5];
+        let expected = header[header.len() - 1];
+        let actual = lz4f_header_checksum(&header[4..header.len() - 1]);
+        if expected != actual {
+            return Err(Error::lz4f(ErrorKind::HeaderChecksumInvalid)
+                .with_checksums(expected.into(), actual.into()));
+        }
+        if flags.0 >> 6 != 1 {
+            return Err(Error::lz4f(ErrorKind::HeaderVersionWrong));
+        }
+        if flags.0 & 0x02 != 0 || bd & 0x8f != 0 {
+            return Err(Error::lz4f(ErrorKind::ReservedFlagSet));
+        }
+        let block_size = match bd >> 4 {
+            4 => BlockSize::Max64KB,
+            5 => BlockSize::Max256KB,
+            6 => BlockSize::Max1MB,
+            7 => BlockSize::Max4MB,
+            _ => return Err(Error::lz4f(ErrorKind::MaxBlockSizeInvalid)),
+        };
+        let content_size = if flags.content_size() {
+            Some(u64::from_le_bytes(header[6..14].try_into().unwrap()))
+        } else {
+            None
+        };
+        Ok(Self {
+            flags,
+            block_size,
+            content_size,
+        })
+    }
+}
+
+/// The header of a data block, or the end mark of a frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum BlockHeader {
+    EndMark,
+    Data { size: usize, stored: bool },
+}
+
+impl BlockHeader {
+    pub const fn parse(value: u32) -> Self {
+        if value == 0 {
+            Self::EndMark
+        } else {
+            Self::Data {
+                size: (value & !LZ4F_BLOCK_UNCOMPRESSED_FLAG) as usize,
+                stored: value & LZ4F_BLOCK_UNCOMPRESSED_FLAG != 0,
+            }
+        }
+    }
+}
+
+pub(crate) fn read_u32(data: &[u8]) -> u32 {
+    u32::from_le_bytes(data[..4].try_into().unwrap())
+}
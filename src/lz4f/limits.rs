@@ -0,0 +1,221 @@
+use super::{BlockSize, FrameInfo};
+use crate::{Error, ErrorKind, Result};
+use std::cmp;
+
+/// Limits on the resources spent on decompression.
+///
+/// A small LZ4F frame can expand enormously. `DecompressionLimits` bounds the output
+/// of a decompressor, so that untrusted input fails with
+/// [`ErrorKind::DecompressionLimitExceeded`] before the memory for it is allocated.
+///
+/// The default value imposes no limits.
+///
+/// [`ErrorKind::DecompressionLimitExceeded`]: ../enum.ErrorKind.html#variant.DecompressionLimitExceeded
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::{lz4f, lz4f::DecompressionLimitsBuilder};
+///
+/// let data = vec![0; 1 << 20];
+/// let mut comp = Vec::new();
+/// lz4f::compress_to_vec(&data, &mut comp, &Default::default())?;
+///
+/// let limits = DecompressionLimitsBuilder::new()
+///     .max_output_size(64 << 10)
+///     .build();
+///
+/// let mut buf = Vec::new();
+/// let err = lz4f::decompress_to_vec_with_limits(&comp, &mut buf, &limits).unwrap_err();
+/// assert_eq!(err.kind(), lzzzz::ErrorKind::DecompressionLimitExceeded);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DecompressionLimits {
+    max_output_size: usize,
+    max_ratio: usize,
+    max_frames: usize,
+    max_block_size: BlockSize,
+    reject_content_size: bool,
+}
+
+impl DecompressionLimits {
+    /// Returns the maximum number of decompressed bytes.
+    pub const fn max_output_size(&self) -> usize {
+        self.max_output_size
+    }
+
+    /// Returns the maximum ratio of decompressed bytes to compressed bytes.
+    pub const fn max_ratio(&self) -> usize {
+        self.max_ratio
+    }
+
+    /// Returns the maximum number of frames in a stream.
+    pub const fn max_frames(&self) -> usize {
+        self.max_frames
+    }
+
+    /// Returns the maximum accepted block size.
+    pub const fn max_block_size(&self) -> BlockSize {
+        self.max_block_size
+    }
+
+    /// Returns `true` if a frame is rejected upfront when its header announces
+    /// a content size over the maximum output size.
+    pub const fn reject_content_size(&self) -> bool {
+        self.reject_content_size
+    }
+}
+
+impl Default for DecompressionLimits {
+    fn default() -> Self {
+        Self {
+            max_output_size: usize::MAX,
+            max_ratio: usize::MAX,
+            max_frames: usize::MAX,
+            max_block_size: BlockSize::Max4MB,
+            reject_content_size: false,
+        }
+    }
+}
+
+/// Builds a custom `DecompressionLimits`.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f::{BlockSize, DecompressionLimitsBuilder};
+///
+/// let limits = DecompressionLimitsBuilder::new()
+///     .max_output_size(16 << 20)
+///     .max_ratio(100)
+///     .max_block_size(BlockSize::Max256KB)
+///     .reject_content_size(true)
+///     .build();
+/// ```
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DecompressionLimitsBuilder {
+    limits: DecompressionLimits,
+}
+
+impl DecompressionLimitsBuilder {
+    /// Creates a new `DecompressionLimitsBuilder`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the maximum number of decompressed bytes.
+    pub fn max_output_size(&mut self, size: usize) -> &mut Self {
+        self.limits.max_output_size = size;
+        self
+    }
+
+    /// Sets the maximum ratio of decompressed bytes to compressed bytes.
+    pub fn max_ratio(&mut self, ratio: usize) -> &mut Self {
+        self.limits.max_ratio = ratio;
+        self
+    }
+
+    /// Sets the maximum number of frames in a stream.
+    pub fn max_frames(&mut self, frames: usize) -> &mut Self {
+        self.limits.max_frames = frames;
+        self
+    }
+
+    /// Sets the maximum accepted block size.
+    ///
+    /// Frames with larger blocks are rejected when their header is decoded.
+    pub fn max_block_size(&mut self, block_size: BlockSize) -> &mut Self {
+        self.limits.max_block_size = block_size;
+        self
+    }
+
+    /// Sets whether a frame is rejected upfront when its header announces
+    /// a content size over the maximum output size.
+    ///
+    /// Otherwise, such a frame fails only when its output reaches the limit.
+    pub fn reject_content_size(&mut self, flag: bool) -> &mut Self {
+        self.limits.reject_content_size = flag;
+        self
+    }
+
+    /// Builds a `DecompressionLimits` with this configuration.
+    pub const fn build(&self) -> DecompressionLimits {
+        self.limits
+    }
+}
+
+impl From<DecompressionLimits> for DecompressionLimitsBuilder {
+    fn from(limits: DecompressionLimits) -> Self {
+        Self { limits }
+    }
+}
+
+/// Tracks the resources spent by a decompressor against `DecompressionLimits`.
+#[derive(Default)]
+pub(crate) struct LimitTracker {
+    limits: DecompressionLimits,
+    consumed: usize,
+    produced: usize,
+    frames: usize,
+}
+
+impl LimitTracker {
+    pub fn new(limits: DecompressionLimits) -> Self {
+        Self {
+            limits,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_limits(&mut self, limits: DecompressionLimits) {
+        self.limits = limits;
+    }
+
+    /// Clears the totals for a new stream, keeping the limits.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.limits);
+    }
+
+    /// Returns the number of bytes that may still be produced.
+    pub fn remaining(&self) -> usize {
+        let ratio_limit = self.limits.max_ratio.saturating_mul(self.consumed);
+        cmp::min(self.limits.max_output_size, ratio_limit).saturating_sub(self.produced)
+    }
+
+    /// Checks the header of a new frame.
+    pub fn begin_frame(&mut self, frame: &FrameInfo) -> Result<()> {
+        self.begin_frame_with(frame.block_size(), frame.content_size())
+    }
+
+    /// Checks a new frame by its block size and content size, which is 0 if unknown.
+    pub fn begin_frame_with(&mut self, block_size: BlockSize, content_size: usize) -> Result<()> {
+        self.frames += 1;
+        if self.frames > self.limits.max_frames
+            || block_size.bytes() > self.limits.max_block_size.bytes()
+            || (self.limits.reject_content_size
+                && content_size > self.limits.max_output_size.saturating_sub(self.produced))
+        {
+            return Err(Error::new(ErrorKind::DecompressionLimitExceeded));
+        }
+        Ok(())
+    }
+
+    pub fn consume(&mut self, len: usize) {
+        self.consumed = self.consumed.saturating_add(len);
+    }
+
+    pub fn produce(&mut self, len: usize) {
+        self.produced += len;
+    }
+
+    /// Returns the number of frames started so far.
+    pub const fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Returns the numbers of bytes consumed and produced so far.
+    pub const fn totals(&self) -> (usize, usize) {
+        (self.consumed, self.produced)
+    }
+}
@@ -3,16 +3,27 @@
 //! LZ4F: LZ4 Frame Format.
 mod api;
 mod binding;
+mod context_pool;
 mod dictionary;
+mod dictionary_set;
 mod error;
 mod frame;
+mod frame_codec;
 mod frame_info;
+mod header;
+mod limits;
 mod preferences;
+mod stats;
 mod stream;
 
+pub use context_pool::*;
 pub use dictionary::*;
+pub use dictionary_set::*;
 pub use error::*;
 pub use frame::*;
+pub use frame_codec::*;
 pub use frame_info::*;
+pub use limits::{DecompressionLimits, DecompressionLimitsBuilder};
 pub use preferences::*;
-pub use stream::{comp::*, decomp::*};
+pub use stats::Stats;
+pub use stream::{comp::*, copy::*, decomp::*};
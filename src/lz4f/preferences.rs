@@ -11,33 +11,23 @@ pub const CLEVEL_HIGH: i32 = 10;
 pub const CLEVEL_MAX: i32 = 12;
 
 /// Auto flush mode flag.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
 #[repr(C)]
 pub enum AutoFlush {
+    #[default]
     Disabled,
     Enabled,
 }
 
-impl Default for AutoFlush {
-    fn default() -> Self {
-        Self::Disabled
-    }
-}
-
 /// Decompression speed mode flag.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
 #[repr(C)]
 pub enum FavorDecSpeed {
+    #[default]
     Disabled,
     Enabled,
 }
 
-impl Default for FavorDecSpeed {
-    fn default() -> Self {
-        Self::Disabled
-    }
-}
-
 /// Compression preferences.
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
 #[repr(C)]
@@ -57,7 +47,7 @@ impl Preferences {
 
     /// Returns the compression level.
     pub const fn compression_level(&self) -> i32 {
-        self.compression_level as i32
+        self.compression_level
     }
 
     /// Returns the auto flush mode flag.
@@ -205,7 +195,6 @@ mod tests {
         BlockChecksum, BlockMode, BlockSize, ContentChecksum, FavorDecSpeed, Preferences,
         PreferencesBuilder, CLEVEL_DEFAULT, CLEVEL_HIGH, CLEVEL_MAX,
     };
-    use std::{i32, u32};
 
     #[test]
     fn preferences_builder() {
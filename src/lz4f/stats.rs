@@ -0,0 +1,380 @@
+use super::{
+    header::{BlockHeader, Flags, Magic},
+    Error, ErrorKind, Result,
+};
+use crate::xxhash::Xxh32;
+use std::cmp;
+
+/// Statistics of a streaming compressor or decompressor.
+///
+/// On compression, the input is the uncompressed data and the output is
+/// the LZ4F stream. On decompression, it is the other way round.
+/// Blocks and frames are counted as soon as their headers pass through the stream.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f::WriteCompressor;
+/// use std::io::prelude::*;
+///
+/// let mut w = WriteCompressor::new(Vec::new(), Default::default())?;
+/// w.write_all(b"Hello world!")?;
+/// w.try_finish()?;
+///
+/// let stats = w.stats();
+/// assert_eq!(stats.bytes_in(), 12);
+/// assert_eq!(stats.bytes_out(), w.get_ref().len() as u64);
+/// assert_eq!(stats.frames(), 1);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Stats {
+    bytes_in: u64,
+    bytes_out: u64,
+    blocks: u64,
+    stored_blocks: u64,
+    frames: u64,
+}
+
+impl Stats {
+    /// Returns the number of bytes consumed.
+    pub const fn bytes_in(&self) -> u64 {
+        self.bytes_in
+    }
+
+    /// Returns the number of bytes produced.
+    ///
+    /// This includes the output not yet written out or read by the caller.
+    pub const fn bytes_out(&self) -> u64 {
+        self.bytes_out
+    }
+
+    /// Returns the number of data blocks emitted or consumed.
+    pub const fn blocks(&self) -> u64 {
+        self.blocks
+    }
+
+    /// Returns the number of data blocks stored uncompressed.
+    pub const fn stored_blocks(&self) -> u64 {
+        self.stored_blocks
+    }
+
+    /// Returns the number of LZ4F frames seen, not counting skippable frames.
+    pub const fn frames(&self) -> u64 {
+        self.frames
+    }
+}
+
+pub(crate) type Progress = Box<dyn FnMut(&Stats) + Send>;
+
+/// Counts the data passing through a stream, and reports it to the progress callback.
+pub(crate) struct StatsTracker {
+    stats: Stats,
+    scanner: Scanner,
+    compress: bool,
+    progress: Option<Progress>,
+}
+
+impl StatsTracker {
+    pub fn compression() -> Self {
+        Self::new(true)
+    }
+
+    pub fn decompression() -> Self {
+        Self::new(false)
+    }
+
+    fn new(compress: bool) -> Self {
+        Self {
+            stats: Stats::default(),
+            scanner: Scanner::default(),
+            compress,
+            progress: None,
+        }
+    }
+
+    pub const fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    pub fn set_progress(&mut self, progress: Progress) {
+        self.progress = Some(progress);
+    }
+
+    /// Clears the statistics for a new stream, keeping the progress callback
+    /// and the checksum verification setting.
+    pub fn reset(&mut self) {
+        self.stats = Stats::default();
+        self.scanner = Scanner {
+            verify: self.scanner.verify,
+            ..Scanner::default()
+        };
+    }
+
+    /// Enables the verification of block and content checksums from the next frame on,
+    /// or disables it right away.
+    ///
+    /// This takes over from liblz4, which does not tell the values it compared.
+    pub fn verify_checksums(&mut self, flag: bool) {
+        self.scanner.verify = flag;
+        self.scanner.verifying &= flag;
+    }
+
+    /// Returns the index of the block in progress within its frame,
+    /// or of the next one between two blocks.
+    pub fn block_index(&self) -> Option<u64> {
+        self.scanner.block_index()
+    }
+
+    /// Returns the content checksum stored in the last frame that has ended.
+    pub const fn content_checksum(&self) -> Option<u32> {
+        self.scanner.last_checksum
+    }
+
+    /// Records `uncompressed` bytes of data and the corresponding LZ4F stream `compressed`.
+    pub fn record(&mut self, uncompressed: usize, compressed: &[u8]) {
+        if uncompressed == 0 && compressed.is_empty() {
+            return;
+        }
+        let (plain, lz4f) = if self.compress {
+            (&mut self.stats.bytes_in, &mut self.stats.bytes_out)
+        } else {
+            (&mut self.stats.bytes_out, &mut self.stats.bytes_in)
+        };
+        *plain += uncompressed as u64;
+        *lz4f += compressed.len() as u64;
+        self.scanner.scan(compressed, &mut self.stats);
+        if let Some(progress) = &mut self.progress {
+            progress(&self.stats);
+        }
+    }
+
+    /// Records the decompressed `output` and the LZ4F stream `compressed` it came from,
+    /// and verifies their checksums if enabled.
+    ///
+    /// `compressed` must not extend past the end of the frame `output` belongs to.
+    pub fn record_decompressed(&mut self, output: &[u8], compressed: &[u8]) -> Result<()> {
+        self.record(output.len(), compressed);
+        self.scanner.verify_content(output)
+    }
+
+    /// Adds the index of the current block to `err`, which occurred somewhere in
+    /// `compressed`, the data following what has been recorded so far.
+    ///
+    /// liblz4 decodes a block before its checksum is verified here, so a corrupted
+    /// block may fail to decode first. A checksum mismatch in `compressed` is reported
+    /// instead of such an error, since it tells more about the cause.
+    pub fn locate_error(&self, err: Error, compressed: &[u8]) -> Error {
+        if let crate::ErrorKind::Lz4f(_) = err.kind() {
+            let mut scanner = self.scanner.clone();
+            scanner.scan(compressed, &mut Stats::default());
+            if let Some(err) = scanner.error {
+                return err;
+            }
+        }
+        match self.block_index() {
+            Some(index) => err.with_block_index(index),
+            None => err,
+        }
+    }
+}
+
+/// The field of the LZ4F stream to read next.
+#[derive(Clone, Copy)]
+enum Field {
+    Magic,
+    FrameDescriptor,
+    SkippableSize,
+    BlockSize,
+    BlockChecksum,
+    ContentChecksum,
+}
+
+impl Field {
+    const fn len(self) -> usize {
+        match self {
+            Self::FrameDescriptor => 2,
+            _ => 4,
+        }
+    }
+}
+
+/// Follows the block structure of an LZ4F stream fed in arbitrary pieces.
+///
+/// The stream is assumed to be valid; the scanner stops at anything it does not recognize.
+/// When verifying, it also hashes the blocks and the content to check their checksums.
+#[derive(Clone)]
+struct Scanner {
+    field: Field,
+    buf: [u8; 4],
+    buf_len: usize,
+    skip: u64,
+    block_checksum: bool,
+    content_checksum: bool,
+    last_checksum: Option<u32>,
+    stopped: bool,
+    frame_blocks: u64,
+    verify: bool,
+    verifying: bool,
+    block_hash: Xxh32,
+    content_hash: Xxh32,
+    stored_content_checksum: Option<u32>,
+    error: Option<Error>,
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Self {
+            field: Field::Magic,
+            buf: [0; 4],
+            buf_len: 0,
+            skip: 0,
+            block_checksum: false,
+            content_checksum: false,
+            last_checksum: None,
+            stopped: false,
+            frame_blocks: 0,
+            verify: false,
+            verifying: false,
+            block_hash: Xxh32::default(),
+            content_hash: Xxh32::default(),
+            stored_content_checksum: None,
+            error: None,
+        }
+    }
+}
+
+/// Returns the content checksum stored at the end of `frame`.
+///
+/// Returns `None` if `frame` does not end with a complete frame which has the checksum.
+pub(crate) fn stored_content_checksum(frame: &[u8]) -> Option<u32> {
+    let mut scanner = Scanner::default();
+    scanner.scan(frame, &mut Stats::default());
+    match (scanner.field, scanner.buf_len, scanner.skip) {
+        (Field::Magic, 0, 0) if !scanner.stopped => scanner.last_checksum,
+        _ => None,
+    }
+}
+
+impl Scanner {
+    fn scan(&mut self, mut data: &[u8], stats: &mut Stats) {
+        while !data.is_empty() && !self.stopped {
+            if self.skip > 0 {
+                let len = cmp::min(self.skip, data.len() as u64) as usize;
+                if let (Field::BlockChecksum, true) = (self.field, self.verifying) {
+                    self.block_hash.update(&data[..len]);
+                }
+                self.skip -= len as u64;
+                data = &data[len..];
+                continue;
+            }
+            let len = cmp::min(self.field.len() - self.buf_len, data.len());
+            self.buf[self.buf_len..][..len].copy_from_slice(&data[..len]);
+            self.buf_len += len;
+            data = &data[len..];
+            if self.buf_len == self.field.len() {
+                self.buf_len = 0;
+                self.next(stats);
+            }
+        }
+    }
+
+    fn next(&mut self, stats: &mut Stats) {
+        let value = u32::from_le_bytes(self.buf);
+        match self.field {
+            Field::Magic => match Magic::parse(value) {
+                Magic::Frame => {
+                    stats.frames += 1;
+                    self.frame_blocks = 0;
+                    self.verifying = self.verify;
+                    self.content_hash.reset();
+                    self.field = Field::FrameDescriptor;
+                }
+                Magic::Skippable => self.field = Field::SkippableSize,
+                Magic::Unknown => self.stopped = true,
+            },
+            Field::FrameDescriptor => {
+                let flags = Flags(self.buf[0]);
+                self.block_checksum = flags.block_checksum();
+                self.content_checksum = flags.content_checksum();
+                // The rest of the header after the magic number, FLG and BD.
+                self.skip = flags.header_len() as u64 - 6;
+                self.field = Field::BlockSize;
+            }
+            Field::SkippableSize => {
+                self.skip = u64::from(value);
+                self.field = Field::Magic;
+            }
+            Field::BlockSize => match BlockHeader::parse(value) {
+                BlockHeader::EndMark if self.content_checksum => {
+                    self.field = Field::ContentChecksum;
+                }
+                BlockHeader::EndMark => {
+                    self.last_checksum = None;
+                    self.field = Field::Magic;
+                }
+                BlockHeader::Data { size, stored } => {
+                    stats.blocks += 1;
+                    if stored {
+                        stats.stored_blocks += 1;
+                    }
+                    self.frame_blocks += 1;
+                    self.skip = size as u64;
+                    if self.block_checksum {
+                        self.block_hash.reset();
+                        self.field = Field::BlockChecksum;
+                    }
+                }
+            },
+            Field::BlockChecksum => {
+                let actual = self.block_hash.digest();
+                if self.verifying && actual != value && self.error.is_none() {
+                    self.error = Some(
+                        Error::lz4f(ErrorKind::BlockChecksumInvalid)
+                            .with_block_index(self.frame_blocks - 1)
+                            .with_checksums(value, actual),
+                    );
+                }
+                self.field = Field::BlockSize;
+            }
+            Field::ContentChecksum => {
+                self.last_checksum = Some(value);
+                if self.verifying {
+                    self.stored_content_checksum = Some(value);
+                }
+                self.field = Field::Magic;
+            }
+        }
+    }
+
+    /// Hashes the decompressed `output` of the data scanned last, and checks the content
+    /// checksum if that data has ended the frame.
+    ///
+    /// Returns the first checksum error found since the last call.
+    fn verify_content(&mut self, output: &[u8]) -> Result<()> {
+        if self.verifying && self.content_checksum {
+            self.content_hash.update(output);
+        }
+        if let Some(expected) = self.stored_content_checksum.take() {
+            let actual = self.content_hash.digest();
+            if actual != expected && self.error.is_none() {
+                self.error = Some(
+                    Error::lz4f(ErrorKind::ContentChecksumInvalid).with_checksums(expected, actual),
+                );
+            }
+        }
+        self.error.take().map_or(Ok(()), Err)
+    }
+
+    /// Returns the index of the block in progress, or of the next one between two blocks.
+    fn block_index(&self) -> Option<u64> {
+        match self.field {
+            _ if self.stopped => None,
+            Field::BlockChecksum => Some(self.frame_blocks - 1),
+            // Skipping the rest of the frame header, or the data of a block.
+            Field::BlockSize if self.skip > 0 => self.frame_blocks.checked_sub(1),
+            Field::BlockSize => Some(self.frame_blocks),
+            _ => None,
+        }
+    }
+}
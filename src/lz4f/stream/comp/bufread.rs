@@ -1,5 +1,5 @@
 use super::{Compressor, Dictionary, Preferences};
-use crate::lz4f::Result;
+use crate::lz4f::{Result, Stats};
 use std::{
     fmt,
     io::{BufRead, Read},
@@ -35,7 +35,6 @@ use std::{
 /// ```
 ///
 /// [`BufRead`]: https://doc.rust-lang.org/std/io/trait.BufRead.html
-
 pub struct BufReadCompressor<R: BufRead> {
     pub(super) inner: R,
     pub(super) comp: Compressor,
@@ -61,6 +60,64 @@ impl<R: BufRead> BufReadCompressor<R> {
         })
     }
 
+    /// Returns the statistics of the stream so far.
+    ///
+    /// See [`Stats`] for how the data is counted.
+    ///
+    /// [`Stats`]: struct.Stats.html
+    pub fn stats(&self) -> Stats {
+        self.comp.stats()
+    }
+
+    /// Sets a callback which receives the statistics whenever the stream makes progress.
+    ///
+    /// The callback runs on the thread driving the stream, so keep it cheap.
+    pub fn set_progress<F>(&mut self, callback: F)
+    where
+        F: FnMut(&Stats) + Send + 'static,
+    {
+        self.comp.set_progress(Box::new(callback));
+    }
+
+    /// Returns the content checksum written at the end of the frame.
+    ///
+    /// This is available once the frame has ended, if [`ContentChecksum`] is enabled.
+    /// The value is the xxHash32 of all the data written, so it can serve as
+    /// a content hash without hashing the data again.
+    ///
+    /// [`ContentChecksum`]: enum.ContentChecksum.html
+    pub fn content_checksum(&self) -> Option<u32> {
+        self.comp.content_checksum()
+    }
+
+    /// Replaces the reader with `reader` and starts a new frame from it,
+    /// returning the old reader.
+    ///
+    /// The compression context, the dictionary and the buffer are reused.
+    /// The output of the current frame which has not been read yet is discarded,
+    /// and the statistics start over.
+    pub fn reset(&mut self, reader: R) -> R {
+        let prefs = *self.comp.prefs();
+        self.reset_with_prefs(reader, prefs)
+    }
+
+    /// Replaces the reader with `reader` and starts a new frame from it with `prefs`,
+    /// returning the old reader.
+    ///
+    /// See [`reset`] for details.
+    ///
+    /// [`reset`]: #method.reset
+    pub fn reset_with_prefs(&mut self, reader: R, prefs: Preferences) -> R {
+        self.restart(prefs);
+        std::mem::replace(&mut self.inner, reader)
+    }
+
+    /// Starts a new frame with `prefs` on the current reader.
+    pub(super) fn restart(&mut self, prefs: Preferences) {
+        self.comp.reset(prefs);
+        self.consumed = 0;
+    }
+
     /// Returns ownership of the reader.
     pub fn into_inner(self) -> R {
         self.inner
@@ -91,20 +148,21 @@ where
 
 impl<R: BufRead> Read for BufReadCompressor<R> {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        let consumed = {
+        // Pending output is served before the reader is touched again,
+        // so an error from the reader never affects the data already compressed.
+        while self.consumed >= self.comp.buf().len() {
             let inner_buf = self.inner.fill_buf()?;
             if inner_buf.is_empty() {
                 self.comp.end(false)?;
                 if self.comp.buf().is_empty() {
                     return Ok(0);
                 }
-                0
             } else {
                 self.comp.update(inner_buf, false)?;
-                inner_buf.len()
+                let len = inner_buf.len();
+                self.inner.consume(len);
             }
-        };
-        self.inner.consume(consumed);
+        }
 
         let len = std::cmp::min(buf.len(), self.comp.buf().len() - self.consumed);
         buf[..len].copy_from_slice(&self.comp.buf()[self.consumed..][..len]);
@@ -11,7 +11,8 @@ pub use write::*;
 
 use crate::lz4f::{
     api::{CompressionContext, LZ4F_HEADER_SIZE_MAX},
-    Dictionary, Preferences,
+    stats::{Progress, StatsTracker},
+    Dictionary, Preferences, Stats,
 };
 
 pub(crate) struct Compressor {
@@ -19,6 +20,7 @@ pub(crate) struct Compressor {
     prefs: Preferences,
     state: State,
     buffer: Vec<u8>,
+    stats: StatsTracker,
 }
 
 impl Compressor {
@@ -28,6 +30,7 @@ impl Compressor {
             prefs,
             state: State::Created,
             buffer: Vec::with_capacity(LZ4F_HEADER_SIZE_MAX),
+            stats: StatsTracker::compression(),
         })
     }
 
@@ -35,6 +38,29 @@ impl Compressor {
         &self.prefs
     }
 
+    pub fn stats(&self) -> Stats {
+        self.stats.stats()
+    }
+
+    pub fn set_progress(&mut self, progress: Progress) {
+        self.stats.set_progress(progress);
+    }
+
+    pub fn content_checksum(&self) -> Option<u32> {
+        self.stats.content_checksum()
+    }
+
+    /// Discards the current frame and the pending output, so that the next call
+    /// starts a new frame with `prefs`.
+    ///
+    /// The context, the dictionary and the buffer are kept.
+    pub fn reset(&mut self, prefs: Preferences) {
+        self.prefs = prefs;
+        self.state = State::Created;
+        self.buffer.clear();
+        self.stats.reset();
+    }
+
     fn begin(&mut self) -> Result<()> {
         if let State::Created = self.state {
             assert!(self.buffer.is_empty());
@@ -48,6 +74,7 @@ impl Compressor {
             unsafe {
                 self.buffer.set_len(len);
             }
+            self.stats.record(0, &self.buffer);
         }
         Ok(())
     }
@@ -66,12 +93,9 @@ impl Compressor {
                 stable_src,
             )?;
             self.buffer.set_len(offset + len);
-            if len == 0 {
-                self.flush(stable_src)
-            } else {
-                Ok(())
-            }
         }
+        self.stats.record(src.len(), &self.buffer[offset..]);
+        Ok(())
     }
 
     pub fn flush(&mut self, stable_src: bool) -> Result<()> {
@@ -88,6 +112,7 @@ impl Compressor {
             )?;
             self.buffer.set_len(offset + len);
         }
+        self.stats.record(0, &self.buffer[offset..]);
         Ok(())
     }
 
@@ -107,6 +132,7 @@ impl Compressor {
                 )?;
                 self.buffer.set_len(offset + len);
             }
+            self.stats.record(0, &self.buffer[offset..]);
         }
         Ok(())
     }
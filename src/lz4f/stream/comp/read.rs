@@ -1,8 +1,8 @@
 use super::{BufReadCompressor, Dictionary, Preferences};
-use crate::lz4f::Result;
+use crate::lz4f::{Result, Stats};
 use std::{
     fmt,
-    io::{BufReader, Read},
+    io::{BufRead, BufReader, Read},
 };
 
 /// The [`Read`]-based streaming compressor.
@@ -31,7 +31,6 @@ use std::{
 /// ```
 ///
 /// [`Read`]: https://doc.rust-lang.org/std/io/trait.Read.html
-
 pub struct ReadCompressor<R: Read> {
     inner: BufReadCompressor<BufReader<R>>,
 }
@@ -40,17 +39,78 @@ impl<R: Read> ReadCompressor<R> {
     /// Creates a new `ReadCompressor<R>`.
     pub fn new(reader: R, prefs: Preferences) -> Result<Self> {
         Ok(Self {
-            inner: BufReadCompressor::new(BufReader::new(reader), prefs)?,
+            inner: BufReadCompressor::new(buf_reader(reader, &prefs), prefs)?,
         })
     }
 
     /// Creates a new `ReadCompressor<R>` with a dictionary.
     pub fn with_dict(reader: R, prefs: Preferences, dict: Dictionary) -> Result<Self> {
         Ok(Self {
-            inner: BufReadCompressor::with_dict(BufReader::new(reader), prefs, dict)?,
+            inner: BufReadCompressor::with_dict(buf_reader(reader, &prefs), prefs, dict)?,
         })
     }
 
+    /// Returns the statistics of the stream so far.
+    ///
+    /// See [`Stats`] for how the data is counted.
+    ///
+    /// [`Stats`]: struct.Stats.html
+    pub fn stats(&self) -> Stats {
+        self.inner.stats()
+    }
+
+    /// Sets a callback which receives the statistics whenever the stream makes progress.
+    ///
+    /// The callback runs on the thread driving the stream, so keep it cheap.
+    pub fn set_progress<F>(&mut self, callback: F)
+    where
+        F: FnMut(&Stats) + Send + 'static,
+    {
+        self.inner.set_progress(callback);
+    }
+
+    /// Returns the content checksum written at the end of the frame.
+    ///
+    /// This is available once the frame has ended, if [`ContentChecksum`] is enabled.
+    /// The value is the xxHash32 of all the data written, so it can serve as
+    /// a content hash without hashing the data again.
+    ///
+    /// [`ContentChecksum`]: enum.ContentChecksum.html
+    pub fn content_checksum(&self) -> Option<u32> {
+        self.inner.content_checksum()
+    }
+
+    /// Replaces the reader with `reader` and starts a new frame from it,
+    /// returning the old reader.
+    ///
+    /// The compression context, the dictionary and the buffers are reused.
+    /// The output of the current frame which has not been read yet is discarded,
+    /// along with the data buffered from the old reader, and the statistics start over.
+    pub fn reset(&mut self, reader: R) -> R {
+        let prefs = *self.inner.comp.prefs();
+        self.reset_with_prefs(reader, prefs)
+    }
+
+    /// Replaces the reader with `reader` and starts a new frame from it with `prefs`,
+    /// returning the old reader.
+    ///
+    /// See [`reset`] for details.
+    /// The input buffer is allocated again only if it cannot hold a block of `prefs`.
+    ///
+    /// [`reset`]: #method.reset
+    pub fn reset_with_prefs(&mut self, reader: R, prefs: Preferences) -> R {
+        let buf = self.inner.get_mut();
+        if buf.capacity() < prefs.frame_info().block_size().bytes() {
+            let reader = buf_reader(reader, &prefs);
+            return self.inner.reset_with_prefs(reader, prefs).into_inner();
+        }
+        let len = buf.buffer().len();
+        buf.consume(len);
+        let reader = std::mem::replace(buf.get_mut(), reader);
+        self.inner.restart(prefs);
+        reader
+    }
+
     /// Returns ownership of the reader.
     pub fn into_inner(self) -> R {
         self.inner.into_inner().into_inner()
@@ -79,6 +139,12 @@ where
     }
 }
 
+/// Creates a `BufReader` that holds a whole block, so that liblz4 can compress
+/// blocks straight from it.
+fn buf_reader<R: Read>(reader: R, prefs: &Preferences) -> BufReader<R> {
+    BufReader::with_capacity(prefs.frame_info().block_size().bytes(), reader)
+}
+
 impl<R: Read> Read for ReadCompressor<R> {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         self.inner.read(buf)
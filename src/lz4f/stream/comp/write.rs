@@ -1,9 +1,20 @@
 use super::{Compressor, Dictionary, Preferences};
-use crate::lz4f::Result;
-use std::{fmt, io::Write};
+use crate::{
+    lz4f::{Result, Stats},
+    Error, ErrorKind,
+};
+use std::{
+    cmp, fmt,
+    io::{IoSlice, Write},
+};
 
 /// The [`Write`]-based streaming compressor.
 ///
+/// Call [`finish`] or [`try_finish`] to end the frame and catch the errors on
+/// writing its end mark. Dropping the compressor also ends the frame and writes out
+/// the remaining data, but ignores errors; debug builds print a warning to stderr
+/// in that case.
+///
 /// # Example
 ///
 /// ```
@@ -20,14 +31,18 @@ use std::{fmt, io::Write};
 /// let mut w = WriteCompressor::new(&mut f, Default::default())?;
 ///
 /// w.write_all(b"Hello world!")?;
+/// w.finish()?;
 /// # Ok::<(), std::io::Error>(())
 /// ```
 ///
 /// [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
-
+/// [`finish`]: #method.finish
+/// [`try_finish`]: #method.try_finish
 pub struct WriteCompressor<W: Write> {
     inner: Option<W>,
     comp: Compressor,
+    consumed: usize,
+    finished: bool,
 }
 
 impl<W: Write> WriteCompressor<W> {
@@ -36,6 +51,8 @@ impl<W: Write> WriteCompressor<W> {
         Ok(Self {
             inner: Some(writer),
             comp: Compressor::new(prefs, None)?,
+            consumed: 0,
+            finished: false,
         })
     }
 
@@ -44,9 +61,41 @@ impl<W: Write> WriteCompressor<W> {
         Ok(Self {
             inner: Some(writer),
             comp: Compressor::new(prefs, Some(dict))?,
+            consumed: 0,
+            finished: false,
         })
     }
 
+    /// Returns the statistics of the stream so far.
+    ///
+    /// See [`Stats`] for how the data is counted.
+    ///
+    /// [`Stats`]: struct.Stats.html
+    pub fn stats(&self) -> Stats {
+        self.comp.stats()
+    }
+
+    /// Sets a callback which receives the statistics whenever the stream makes progress.
+    ///
+    /// The callback runs on the thread driving the stream, so keep it cheap.
+    pub fn set_progress<F>(&mut self, callback: F)
+    where
+        F: FnMut(&Stats) + Send + 'static,
+    {
+        self.comp.set_progress(Box::new(callback));
+    }
+
+    /// Returns the content checksum written at the end of the frame.
+    ///
+    /// This is available once the frame has ended, if [`ContentChecksum`] is enabled.
+    /// The value is the xxHash32 of all the data written, so it can serve as
+    /// a content hash without hashing the data again.
+    ///
+    /// [`ContentChecksum`]: enum.ContentChecksum.html
+    pub fn content_checksum(&self) -> Option<u32> {
+        self.comp.content_checksum()
+    }
+
     /// Returns a mutable reference to the writer.
     pub fn get_mut(&mut self) -> &mut W {
         self.inner.as_mut().unwrap()
@@ -57,20 +106,154 @@ impl<W: Write> WriteCompressor<W> {
         self.inner.as_ref().unwrap()
     }
 
+    /// Ends the frame on the current writer, and replaces the writer with `writer`
+    /// to start a new frame, returning the old writer.
+    ///
+    /// The compression context, the dictionary and the buffer are reused,
+    /// and the statistics start over.
+    ///
+    /// See [`try_finish`] for the errors. On error, the current writer is kept.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lzzzz::lz4f::{self, WriteCompressor};
+    /// use std::io::prelude::*;
+    ///
+    /// let mut w = WriteCompressor::new(Vec::new(), Default::default())?;
+    /// w.write_all(b"Hello world!")?;
+    ///
+    /// let first = w.reset(Vec::new())?;
+    /// w.write_all(b"Hello again!")?;
+    /// let second = w.finish()?;
+    ///
+    /// let mut buf = Vec::new();
+    /// lz4f::decompress_to_vec(&first, &mut buf)?;
+    /// lz4f::decompress_to_vec(&second, &mut buf)?;
+    /// assert_eq!(buf, b"Hello world!Hello again!");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    ///
+    /// [`try_finish`]: #method.try_finish
+    pub fn reset(&mut self, writer: W) -> std::io::Result<W> {
+        let prefs = *self.comp.prefs();
+        self.reset_with_prefs(writer, prefs)
+    }
+
+    /// Ends the frame on the current writer, and replaces the writer with `writer`
+    /// to start a new frame with `prefs`, returning the old writer.
+    ///
+    /// See [`reset`] for details.
+    ///
+    /// [`reset`]: #method.reset
+    pub fn reset_with_prefs(&mut self, writer: W, prefs: Preferences) -> std::io::Result<W> {
+        self.end()?;
+        self.comp.reset(prefs);
+        self.finished = false;
+        Ok(self.inner.replace(writer).unwrap())
+    }
+
     /// Returns the ownership of the writer, finishing the stream in the process.
+    ///
+    /// Errors on finishing the stream are ignored. Use [`finish`] to catch them.
+    ///
+    /// [`finish`]: #method.finish
     pub fn into_inner(mut self) -> W {
         let _ = self.end();
         self.inner.take().unwrap()
     }
 
+    /// Ends the frame and writes out all the compressed data.
+    ///
+    /// On error, the unwritten data stays in the buffer, and calling this again
+    /// resumes from there. Once the frame has ended, further writes fail with
+    /// [`ErrorKind::CompressionFailed`].
+    ///
+    /// [`ErrorKind::CompressionFailed`]: ../enum.ErrorKind.html#variant.CompressionFailed
+    pub fn try_finish(&mut self) -> std::io::Result<()> {
+        self.end()
+    }
+
+    /// Ends the frame and returns the ownership of the writer.
+    ///
+    /// See [`try_finish`] for the errors. Use it instead to keep the compressor on error,
+    /// or [`finish_with_checksum`] to get the content checksum as well.
+    ///
+    /// [`try_finish`]: #method.try_finish
+    /// [`finish_with_checksum`]: #method.finish_with_checksum
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.try_finish()?;
+        Ok(self.inner.take().unwrap())
+    }
+
+    /// Ends the frame and returns the ownership of the writer
+    /// together with the content checksum written at the end of the frame.
+    ///
+    /// The checksum is `None` unless [`ContentChecksum`] is enabled.
+    /// See [`content_checksum`] for details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lzzzz::{lz4f, xxhash};
+    /// use std::io::prelude::*;
+    ///
+    /// let prefs = lz4f::PreferencesBuilder::new()
+    ///     .content_checksum(lz4f::ContentChecksum::Enabled)
+    ///     .build();
+    /// let mut w = lz4f::WriteCompressor::new(Vec::new(), prefs)?;
+    /// w.write_all(b"Hello world!")?;
+    ///
+    /// let (buf, checksum) = w.finish_with_checksum()?;
+    /// assert_eq!(
+    ///     checksum,
+    ///     Some(xxhash::lz4f_content_checksum(b"Hello world!"))
+    /// );
+    /// assert_eq!(lz4f::content_checksum(&buf), checksum);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    ///
+    /// [`ContentChecksum`]: enum.ContentChecksum.html
+    /// [`content_checksum`]: #method.content_checksum
+    pub fn finish_with_checksum(mut self) -> std::io::Result<(W, Option<u32>)> {
+        self.try_finish()?;
+        let checksum = self.content_checksum();
+        Ok((self.inner.take().unwrap(), checksum))
+    }
+
     fn end(&mut self) -> std::io::Result<()> {
-        if let Some(device) = &mut self.inner {
+        if self.inner.is_some() {
+            self.write_pending()?;
             self.comp.end(false)?;
-            device.write_all(self.comp.buf())?;
-            self.comp.clear_buf();
-            device.flush()?;
+            self.finished = true;
+            self.write_pending()?;
+            self.inner.as_mut().unwrap().flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Fails if the frame has ended, since new data would start another frame.
+    fn check_active(&self) -> std::io::Result<()> {
+        if self.finished {
+            return Err(Error::new(ErrorKind::CompressionFailed).into());
         }
+        Ok(())
+    }
 
+    /// Writes out the compressed data left in the buffer.
+    ///
+    /// On error, the unwritten part stays in the buffer for the next call.
+    fn write_pending(&mut self) -> std::io::Result<()> {
+        let device = self.inner.as_mut().unwrap();
+        while self.consumed < self.comp.buf().len() {
+            match device.write(&self.comp.buf()[self.consumed..])? {
+                0 => return Err(std::io::ErrorKind::WriteZero.into()),
+                len => self.consumed += len,
+            }
+        }
+        self.comp.clear_buf();
+        self.consumed = 0;
         Ok(())
     }
 }
@@ -89,21 +272,63 @@ where
 
 impl<W: Write> Write for WriteCompressor<W> {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.check_active()?;
+        self.write_pending()?;
+        // Taking at most a block per call keeps the output buffer bounded.
+        let block_size = self.comp.prefs().frame_info().block_size().bytes();
+        let buf = &buf[..cmp::min(buf.len(), block_size)];
+        // `buf` does not outlive this call, so liblz4 has to keep its own copy of
+        // the history for linked blocks, which rules out `stable_src`.
         self.comp.update(buf, false)?;
-        self.inner.as_mut().unwrap().write_all(self.comp.buf())?;
-        self.comp.clear_buf();
+        // `buf` has been consumed, so a failure here must not be reported.
+        // The output stays pending and is written out on the next call.
+        let _ = self.write_pending();
         Ok(buf.len())
     }
 
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> std::io::Result<usize> {
+        self.check_active()?;
+        self.write_pending()?;
+        // All slices go through the same frame, up to a block per call as in `write`.
+        let mut remaining = self.comp.prefs().frame_info().block_size().bytes();
+        let mut len = 0;
+        for buf in bufs {
+            if remaining == 0 {
+                break;
+            }
+            let buf = &buf[..cmp::min(buf.len(), remaining)];
+            if let Err(err) = self.comp.update(buf, false) {
+                if len == 0 {
+                    return Err(err.into());
+                }
+                break;
+            }
+            remaining -= buf.len();
+            len += buf.len();
+        }
+        let _ = self.write_pending();
+        Ok(len)
+    }
+
     fn flush(&mut self) -> std::io::Result<()> {
-        self.comp.flush(false)?;
-        self.inner.as_mut().unwrap().write_all(self.comp.buf())?;
+        self.write_pending()?;
+        if !self.finished {
+            self.comp.flush(false)?;
+            self.write_pending()?;
+        }
         self.inner.as_mut().unwrap().flush()
     }
 }
 
 impl<W: Write> Drop for WriteCompressor<W> {
     fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        if self.inner.is_some() && !self.finished && !std::thread::panicking() {
+            eprintln!(
+                "warning: lzzzz::lz4f::WriteCompressor dropped without `finish`; errors on ending \
+                 the frame are ignored"
+            );
+        }
         let _ = self.end();
     }
 }
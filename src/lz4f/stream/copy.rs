@@ -0,0 +1,71 @@
+//! Copying between readers and writers.
+use crate::lz4f::{Preferences, Stats, WriteCompressor, WriteDecompressor};
+use std::io::{self, Read, Write};
+
+/// Compresses the entire contents of a reader into a writer as an LZ4F frame.
+///
+/// This works like [`io::copy`], and returns the statistics of the compression.
+/// The frame is complete when this returns `Ok`.
+///
+/// [`io::copy`]: https://doc.rust-lang.org/std/io/fn.copy.html
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f;
+///
+/// let data = b"The quick brown fox jumps over the lazy dog.";
+/// let mut comp = Vec::new();
+/// let stats = lz4f::copy_compress(&mut &data[..], &mut comp, &Default::default())?;
+///
+/// assert_eq!(stats.bytes_in(), data.len() as u64);
+/// assert_eq!(stats.bytes_out(), comp.len() as u64);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn copy_compress<R, W>(reader: &mut R, writer: &mut W, prefs: &Preferences) -> io::Result<Stats>
+where
+    R: Read + ?Sized,
+    W: Write + ?Sized,
+{
+    let mut w = WriteCompressor::new(writer, *prefs)?;
+    io::copy(reader, &mut w)?;
+    w.try_finish()?;
+    Ok(w.stats())
+}
+
+/// Decompresses the entire LZ4F stream of a reader into a writer.
+///
+/// This works like [`io::copy`], and returns the statistics of the decompression.
+/// The stream may contain multiple frames. If it ends in the middle of a frame,
+/// this fails with [`ErrorKind::CompressedDataIncomplete`].
+///
+/// [`io::copy`]: https://doc.rust-lang.org/std/io/fn.copy.html
+/// [`ErrorKind::CompressedDataIncomplete`]: ../enum.ErrorKind.html#variant.CompressedDataIncomplete
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f;
+///
+/// let data = b"The quick brown fox jumps over the lazy dog.";
+/// let mut comp = Vec::new();
+/// lz4f::compress_to_vec(data, &mut comp, &Default::default())?;
+///
+/// let mut buf = Vec::new();
+/// let stats = lz4f::copy_decompress(&mut comp.as_slice(), &mut buf)?;
+///
+/// assert_eq!(buf, &data[..]);
+/// assert_eq!(stats.bytes_in(), comp.len() as u64);
+/// assert_eq!(stats.frames(), 1);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn copy_decompress<R, W>(reader: &mut R, writer: &mut W) -> io::Result<Stats>
+where
+    R: Read + ?Sized,
+    W: Write + ?Sized,
+{
+    let mut w = WriteDecompressor::new(writer)?;
+    io::copy(reader, &mut w)?;
+    w.try_finish()?;
+    Ok(w.stats())
+}
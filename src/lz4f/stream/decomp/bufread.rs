@@ -1,5 +1,5 @@
 use super::Decompressor;
-use crate::lz4f::{FrameInfo, Result};
+use crate::lz4f::{DecompressionLimits, FrameInfo, Result, Stats};
 use std::{
     borrow::Cow,
     fmt,
@@ -8,6 +8,13 @@ use std::{
 
 /// The [`BufRead`]-based streaming decompressor.
 ///
+/// If the reader reaches EOF in the middle of a frame, reading fails with
+/// [`ErrorKind::CompressedDataIncomplete`] after the data decompressed so far.
+///
+/// Reads at least as large as the frame's block size are decoded directly into
+/// the caller's buffer. On nightly, building with `RUSTFLAGS="--cfg lzzzz_read_buf"`
+/// also implements `Read::read_buf`, which skips initializing the buffer.
+///
 /// # Example
 ///
 /// ```
@@ -38,7 +45,7 @@ use std::{
 /// ```
 ///
 /// [`BufRead`]: https://doc.rust-lang.org/std/io/trait.BufRead.html
-
+/// [`ErrorKind::CompressedDataIncomplete`]: ../enum.ErrorKind.html#variant.CompressedDataIncomplete
 pub struct BufReadDecompressor<'a, R: BufRead> {
     pub(super) inner: R,
     decomp: Decompressor<'a>,
@@ -63,6 +70,25 @@ impl<'a, R: BufRead> BufReadDecompressor<'a, R> {
         self.decomp.set_dict(dict);
     }
 
+    /// Sets the decompression limits.
+    ///
+    /// Once the stream exceeds `limits`, reads and writes fail with
+    /// [`ErrorKind::DecompressionLimitExceeded`].
+    ///
+    /// [`ErrorKind::DecompressionLimitExceeded`]: ../enum.ErrorKind.html#variant.DecompressionLimitExceeded
+    pub fn set_limits(&mut self, limits: DecompressionLimits) {
+        self.decomp.set_limits(limits);
+    }
+
+    /// Sets the 'skip-checksums' mode.
+    ///
+    /// When the 'skip-checksums' mode is enabled, the decompressor neither computes
+    /// nor verifies block and content checksums. Use it only for trusted data.
+    /// Once enabled in the middle of a frame, checksums stay skipped until the end of the frame.
+    pub fn skip_checksums(&mut self, flag: bool) {
+        self.decomp.skip_checksums(flag);
+    }
+
     /// Reads the frame header and returns `FrameInfo`.
     ///
     /// Calling this function before any `Read` or `BufRead` operations
@@ -78,6 +104,79 @@ impl<'a, R: BufRead> BufReadDecompressor<'a, R> {
         }
     }
 
+    /// Returns `true` if a read of `len` bytes should bypass the internal buffer.
+    fn is_direct(&self, len: usize) -> bool {
+        self.consumed >= self.decomp.buf().len()
+            && self
+                .decomp
+                .frame_info()
+                .is_some_and(|frame| len >= frame.block_size().bytes())
+    }
+
+    /// Decompresses more data into the internal buffer if it has been consumed.
+    ///
+    /// Pending output is served before the reader is touched again,
+    /// so an error from the reader never affects the data already decompressed.
+    fn fill_pending(&mut self) -> std::io::Result<()> {
+        while self.consumed >= self.decomp.buf().len() {
+            let inner_buf = self.inner.fill_buf()?;
+            let eof = inner_buf.is_empty();
+            let consumed = self.decomp.decompress(inner_buf)?;
+            self.inner.consume(consumed);
+            if consumed == 0 {
+                if eof && self.consumed >= self.decomp.buf().len() {
+                    self.decomp.finish()?;
+                }
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the statistics of the stream so far.
+    ///
+    /// See [`Stats`] for how the data is counted.
+    ///
+    /// [`Stats`]: struct.Stats.html
+    pub fn stats(&self) -> Stats {
+        self.decomp.stats()
+    }
+
+    /// Sets a callback which receives the statistics whenever the stream makes progress.
+    ///
+    /// The callback runs on the thread driving the stream, so keep it cheap.
+    pub fn set_progress<F>(&mut self, callback: F)
+    where
+        F: FnMut(&Stats) + Send + 'static,
+    {
+        self.decomp.set_progress(Box::new(callback));
+    }
+
+    /// Returns the content checksum of the last frame that has reached its end.
+    ///
+    /// The checksum has been verified against the decompressed data unless checksums
+    /// are skipped. Returns `None` before the end of the first frame, or if the last
+    /// frame has no content checksum.
+    pub fn content_checksum(&self) -> Option<u32> {
+        self.decomp.content_checksum()
+    }
+
+    /// Replaces the reader with `reader` to decompress a new stream, returning the old reader.
+    ///
+    /// The decompression context, the dictionary, the buffer and the settings are reused.
+    /// The decompressed data which has not been read yet is discarded,
+    /// and the statistics start over.
+    pub fn reset(&mut self, reader: R) -> R {
+        self.restart();
+        std::mem::replace(&mut self.inner, reader)
+    }
+
+    /// Starts over on the current reader.
+    pub(super) fn restart(&mut self) {
+        self.decomp.reset();
+        self.consumed = 0;
+    }
+
     /// Returns ownership of the reader.
     pub fn into_inner(self) -> R {
         self.inner
@@ -107,29 +206,70 @@ where
 
 impl<R: BufRead> Read for BufReadDecompressor<'_, R> {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        loop {
-            let inner_buf = self.inner.fill_buf()?;
-            let consumed = self.decomp.decompress(inner_buf)?;
-            self.inner.consume(consumed);
-            if consumed == 0 {
-                break;
+        if self.is_direct(buf.len()) {
+            // Large reads bypass the internal buffer.
+            self.decomp.clear_buf();
+            self.consumed = 0;
+            loop {
+                let inner_buf = self.inner.fill_buf()?;
+                let eof = inner_buf.is_empty();
+                let (consumed, produced) = self.decomp.decompress_into(inner_buf, buf)?;
+                self.inner.consume(consumed);
+                if eof && produced == 0 {
+                    self.decomp.finish()?;
+                }
+                if consumed == 0 || produced > 0 {
+                    return Ok(produced);
+                }
             }
         }
 
+        self.fill_pending()?;
         let len = std::cmp::min(buf.len(), self.decomp.buf().len() - self.consumed);
         buf[..len].copy_from_slice(&self.decomp.buf()[self.consumed..][..len]);
-        self.consumed += len;
-        if self.consumed >= self.decomp.buf().len() {
+        self.consume(len);
+        Ok(len)
+    }
+
+    #[cfg(lzzzz_read_buf)]
+    fn read_buf(&mut self, mut cursor: std::io::BorrowedCursor<'_>) -> std::io::Result<()> {
+        if self.is_direct(cursor.capacity()) {
+            // Large reads bypass the internal buffer and skip initializing `cursor`.
             self.decomp.clear_buf();
             self.consumed = 0;
+            loop {
+                let inner_buf = self.inner.fill_buf()?;
+                let eof = inner_buf.is_empty();
+                #[allow(unsafe_code)]
+                let (consumed, produced) = self
+                    .decomp
+                    .decompress_into_uninit(inner_buf, unsafe { cursor.as_mut() })?;
+                self.inner.consume(consumed);
+                // liblz4 has initialized the first `produced` bytes.
+                #[allow(unsafe_code)]
+                unsafe {
+                    cursor.advance(produced);
+                }
+                if eof && produced == 0 {
+                    self.decomp.finish()?;
+                }
+                if consumed == 0 || produced > 0 {
+                    return Ok(());
+                }
+            }
         }
-        Ok(len)
+
+        self.fill_pending()?;
+        let len = std::cmp::min(cursor.capacity(), self.decomp.buf().len() - self.consumed);
+        cursor.append(&self.decomp.buf()[self.consumed..][..len]);
+        self.consume(len);
+        Ok(())
     }
 }
 
 impl<R: BufRead> BufRead for BufReadDecompressor<'_, R> {
     fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
-        let _ = self.read(&mut [])?;
+        self.fill_pending()?;
         Ok(&self.decomp.buf()[self.consumed..])
     }
 
@@ -1,20 +1,24 @@
 //! Streaming LZ4F decompressors.
 mod bufread;
 mod read;
+mod salvage;
 mod write;
 
 pub use bufread::*;
 pub use read::*;
+pub use salvage::*;
 pub use write::*;
 
 use crate::{
-    common::DEFAULT_BUF_SIZE,
+    lz4f,
     lz4f::{
         api::{
             header_size, DecompressionContext, LZ4F_HEADER_SIZE_MAX,
             LZ4F_MIN_SIZE_TO_KNOW_HEADER_LENGTH,
         },
-        FrameInfo, Result,
+        limits::LimitTracker,
+        stats::{Progress, StatsTracker},
+        DecompressionLimits, FrameInfo, Result, Stats,
     },
     Error, ErrorKind,
 };
@@ -37,28 +41,62 @@ enum State {
     },
 }
 
+enum Target<'a> {
+    Buffer,
+    Init(&'a mut [u8]),
+    #[cfg(lzzzz_read_buf)]
+    Uninit(&'a mut [std::mem::MaybeUninit<u8>]),
+}
+
 pub(crate) struct Decompressor<'a> {
     ctx: DecompressionContext,
     state: State,
     buffer: Vec<u8>,
+    buffer_len: usize,
     dict: Pin<Cow<'a, [u8]>>,
     header_only: bool,
+    tracker: LimitTracker,
+    stats: StatsTracker,
 }
 
 impl<'a> Decompressor<'a> {
     pub fn new() -> Result<Self> {
+        // The checksums are verified by `stats`, which can report the values it compared.
+        let mut ctx = DecompressionContext::new()?;
+        ctx.set_skip_checksums(true);
+        let mut stats = StatsTracker::decompression();
+        stats.verify_checksums(true);
         Ok(Self {
-            ctx: DecompressionContext::new()?,
+            ctx,
             state: State::Header {
                 header: [0; LZ4F_HEADER_SIZE_MAX],
                 header_len: 0,
             },
             buffer: Vec::new(),
+            buffer_len: 0,
             dict: Pin::new(Cow::Borrowed(&[])),
             header_only: false,
+            tracker: LimitTracker::default(),
+            stats,
         })
     }
 
+    pub fn set_limits(&mut self, limits: DecompressionLimits) {
+        self.tracker.set_limits(limits);
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats.stats()
+    }
+
+    pub fn set_progress(&mut self, progress: Progress) {
+        self.stats.set_progress(progress);
+    }
+
+    pub fn content_checksum(&self) -> Option<u32> {
+        self.stats.content_checksum()
+    }
+
     pub fn set_dict<D>(&mut self, dict: D)
     where
         D: Into<Cow<'a, [u8]>>,
@@ -66,6 +104,21 @@ impl<'a> Decompressor<'a> {
         self.dict = Pin::new(dict.into());
     }
 
+    /// Discards the current frame and the pending output, so that the next call
+    /// starts with the header of a new frame.
+    ///
+    /// The context, the dictionary, the buffer and the settings are kept.
+    pub fn reset(&mut self) {
+        self.ctx.reset();
+        self.state = State::Header {
+            header: [0; LZ4F_HEADER_SIZE_MAX],
+            header_len: 0,
+        };
+        self.buffer_len = 0;
+        self.tracker.reset();
+        self.stats.reset();
+    }
+
     pub fn frame_info(&self) -> Option<FrameInfo> {
         if let State::Body { frame_info, .. } = self.state {
             Some(frame_info)
@@ -78,7 +131,155 @@ impl<'a> Decompressor<'a> {
         self.header_only = flag;
     }
 
+    pub fn skip_checksums(&mut self, flag: bool) {
+        self.stats.verify_checksums(!flag);
+    }
+
+    /// Decompresses `src` into the internal buffer.
+    ///
+    /// Returns the number of bytes consumed from `src`.
     pub fn decompress(&mut self, src: &[u8]) -> Result<usize> {
+        self.decompress_to(src, Target::Buffer)
+            .map(|(src_len, _)| src_len)
+    }
+
+    /// Decompresses `src` directly into `dst`, bypassing the internal buffer.
+    ///
+    /// The internal buffer must be empty.
+    /// Returns the number of bytes consumed from `src` and written into `dst`.
+    pub fn decompress_into(&mut self, src: &[u8], dst: &mut [u8]) -> Result<(usize, usize)> {
+        debug_assert!(self.buf().is_empty());
+        self.decompress_to(src, Target::Init(dst))
+    }
+
+    /// Decompresses `src` directly into uninitialized `dst`, bypassing the internal buffer.
+    ///
+    /// The internal buffer must be empty.
+    /// Returns the number of bytes consumed from `src` and written into `dst`.
+    #[cfg(lzzzz_read_buf)]
+    pub fn decompress_into_uninit(
+        &mut self,
+        src: &[u8],
+        dst: &mut [std::mem::MaybeUninit<u8>],
+    ) -> Result<(usize, usize)> {
+        debug_assert!(self.buf().is_empty());
+        self.decompress_to(src, Target::Uninit(dst))
+    }
+
+    fn decompress_to(&mut self, src: &[u8], mut dst: Target<'_>) -> Result<(usize, usize)> {
+        // liblz4 does not tell where in `src` an error occurs,
+        // so errors are located at the start of `src`.
+        let locate = self.locator();
+        let (src_len, dst_len) = match self.decompress_src(src, &mut dst) {
+            Ok(len) => len,
+            Err(err) => return Err(locate(self.stats.locate_error(err, src))),
+        };
+
+        let output = match &dst {
+            Target::Buffer => &self.buffer[self.buffer_len - dst_len..self.buffer_len],
+            Target::Init(dst) => &dst[..dst_len],
+            #[cfg(lzzzz_read_buf)]
+            Target::Uninit(dst) => {
+                // liblz4 has initialized the first `dst_len` bytes.
+                #[allow(unsafe_code)]
+                unsafe {
+                    &*(&dst[..dst_len] as *const [std::mem::MaybeUninit<u8>] as *const [u8])
+                }
+            }
+        };
+        if let Err(err) = self.stats.record_decompressed(output, &src[..src_len]) {
+            if let Target::Buffer = dst {
+                self.buffer_len -= dst_len;
+            }
+            return Err(locate(err));
+        }
+        Ok((src_len, dst_len))
+    }
+
+    /// Returns a function which adds the current position in the stream to an error.
+    fn locator(&self) -> impl FnOnce(Error) -> Error {
+        let (consumed, produced) = self.tracker.totals();
+        let frame_index = match self.state {
+            State::Header { .. } => self.tracker.frames(),
+            State::Body { .. } => self.tracker.frames() - 1,
+        };
+        move |err| {
+            err.with_offsets(consumed as u64, produced as u64)
+                .with_frame_index(frame_index as u64)
+        }
+    }
+
+    fn decompress_src(&mut self, src: &[u8], dst: &mut Target<'_>) -> Result<(usize, usize)> {
+        let header_consumed = self.decode_header(src)?;
+        if self.header_only {
+            return Ok((header_consumed, 0));
+        }
+
+        let src = &src[header_consumed..];
+        let dict_ptr = self.dict_ptr();
+        if let State::Body {
+            frame_info,
+            ref mut comp_dict,
+        } = &mut self.state
+        {
+            if dict_ptr != *comp_dict.get_or_insert(dict_ptr) {
+                return Err(Error::new(ErrorKind::DictionaryChangedDuringDecompression));
+            }
+
+            let remaining = self.tracker.remaining();
+            let (src_len, dst_len, expected) = match dst {
+                Target::Buffer => {
+                    // A buffer as large as a block lets liblz4 decode blocks in place
+                    // instead of going through its own temporary buffer. The buffer is
+                    // refilled from the start, so `stable_dst` does not apply either.
+                    let block_size = frame_info.block_size().bytes();
+                    if self.buffer.len() < block_size {
+                        self.buffer.resize_with(block_size, Default::default);
+                    }
+                    let end =
+                        cmp::min(self.buffer.len(), self.buffer_len.saturating_add(remaining));
+                    let dst = &mut self.buffer[self.buffer_len..end];
+                    let result = self.ctx.decompress_dict(src, dst, &self.dict, false)?;
+                    self.buffer_len += result.1;
+                    result
+                }
+                Target::Init(dst) => {
+                    // The caller may overwrite `dst` before the next call, so the history
+                    // of linked blocks cannot stay there as `stable_dst` would require.
+                    let len = cmp::min(dst.len(), remaining);
+                    let dst = &mut dst[..len];
+                    self.ctx.decompress_dict(src, dst, &self.dict, false)?
+                }
+                #[cfg(lzzzz_read_buf)]
+                Target::Uninit(dst) => {
+                    let len = cmp::min(dst.len(), remaining);
+                    let dst = &mut dst[..len];
+                    self.ctx
+                        .decompress_dict_uninit(src, dst, &self.dict, false)?
+                }
+            };
+            self.tracker.consume(src_len);
+            self.tracker.produce(dst_len);
+
+            if expected == 0 {
+                // liblz4 has reset the context, so the next frame starts with its header.
+                self.state = State::Header {
+                    header: [0; LZ4F_HEADER_SIZE_MAX],
+                    header_len: 0,
+                };
+            } else if remaining == 0 && src_len == 0 && !src.is_empty() {
+                return Err(Error::new(ErrorKind::DecompressionLimitExceeded));
+            }
+            Ok((src_len + header_consumed, dst_len))
+        } else {
+            Ok((header_consumed, 0))
+        }
+    }
+
+    /// Feeds the frame header to the context.
+    ///
+    /// Returns the number of bytes consumed from `src`.
+    fn decode_header(&mut self, src: &[u8]) -> Result<usize> {
         let mut header_consumed = 0;
         if let State::Header {
             ref mut header,
@@ -94,7 +295,7 @@ impl<'a> Decompressor<'a> {
             if *header_len >= LZ4F_MIN_SIZE_TO_KNOW_HEADER_LENGTH {
                 let exact_header_len = header_size(&header[..*header_len]);
                 if exact_header_len > LZ4F_HEADER_SIZE_MAX {
-                    return Err(Error::new(ErrorKind::FrameHeaderInvalid).into());
+                    return Err(Error::new(ErrorKind::FrameHeaderInvalid));
                 }
                 let src = &src[header_consumed..];
                 if *header_len < exact_header_len {
@@ -106,6 +307,7 @@ impl<'a> Decompressor<'a> {
                 if *header_len >= exact_header_len {
                     let (frame, rep) = self.ctx.get_frame_info(&header[..*header_len])?;
                     header_consumed = cmp::min(header_consumed, rep);
+                    self.tracker.begin_frame(&frame)?;
 
                     self.state = State::Body {
                         frame_info: frame,
@@ -115,37 +317,35 @@ impl<'a> Decompressor<'a> {
             }
         }
 
+        self.tracker.consume(header_consumed);
+
         if let State::Header { header, header_len } = self.state {
-            if src.is_empty() {
-                self.ctx.get_frame_info(&header[..header_len])?;
+            // An empty `src` right after a frame is a clean end of the stream.
+            if src.is_empty() && (header_len > 0 || self.tracker.frames() == 0) {
+                self.ctx
+                    .get_frame_info(&header[..header_len])
+                    .map_err(|err| match err.kind() {
+                        ErrorKind::Lz4f(lz4f::ErrorKind::FrameHeaderIncomplete)
+                            if header_len > 0 =>
+                        {
+                            Error::new(ErrorKind::CompressedDataIncomplete)
+                        }
+                        _ => err,
+                    })?;
             }
         }
 
-        if self.header_only {
-            return Ok(header_consumed);
-        }
-
-        let src = &src[header_consumed..];
-        let dict_ptr = self.dict_ptr();
-        if let State::Body {
-            ref mut comp_dict, ..
-        } = &mut self.state
-        {
-            if dict_ptr != *comp_dict.get_or_insert(dict_ptr) {
-                return Err(Error::new(ErrorKind::DictionaryChangedDuringDecompression).into());
-            }
+        Ok(header_consumed)
+    }
 
-            let len = self.buffer.len();
-            if len < DEFAULT_BUF_SIZE {
-                self.buffer.resize_with(DEFAULT_BUF_SIZE, Default::default)
-            }
-            let (src_len, dst_len, _) =
-                self.ctx
-                    .decompress_dict(src, &mut self.buffer[len..], &self.dict, false)?;
-            self.buffer.resize_with(len + dst_len, Default::default);
-            Ok(src_len + header_consumed)
-        } else {
-            Ok(header_consumed)
+    /// Returns an error if the stream has ended in the middle of a frame.
+    pub fn finish(&self) -> Result<()> {
+        match self.state {
+            State::Header { header_len: 0, .. } => Ok(()),
+            State::Body { .. } if self.header_only => Ok(()),
+            _ => Err(self.locator()(Error::new(
+                ErrorKind::CompressedDataIncomplete,
+            ))),
         }
     }
 
@@ -159,10 +359,10 @@ impl<'a> Decompressor<'a> {
     }
 
     pub fn buf(&self) -> &[u8] {
-        &self.buffer
+        &self.buffer[..self.buffer_len]
     }
 
     pub fn clear_buf(&mut self) {
-        self.buffer.clear();
+        self.buffer_len = 0;
     }
 }
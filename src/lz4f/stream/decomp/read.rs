@@ -1,13 +1,16 @@
 use super::BufReadDecompressor;
-use crate::lz4f::{FrameInfo, Result};
+use crate::lz4f::{DecompressionLimits, FrameInfo, Result, Stats};
 use std::{
     borrow::Cow,
     fmt,
-    io::{BufReader, Read},
+    io::{BufRead, BufReader, Read},
 };
 
 /// The [`Read`]-based streaming decompressor.
 ///
+/// If the reader reaches EOF in the middle of a frame, reading fails with
+/// [`ErrorKind::CompressedDataIncomplete`] after the data decompressed so far.
+///
 /// # Example
 ///
 /// ```
@@ -34,7 +37,7 @@ use std::{
 /// ```
 ///
 /// [`Read`]: https://doc.rust-lang.org/std/io/trait.Read.html
-
+/// [`ErrorKind::CompressedDataIncomplete`]: ../enum.ErrorKind.html#variant.CompressedDataIncomplete
 pub struct ReadDecompressor<'a, R: Read> {
     inner: BufReadDecompressor<'a, BufReader<R>>,
 }
@@ -66,6 +69,25 @@ impl<'a, R: Read> ReadDecompressor<'a, R> {
         self.inner.set_dict(dict);
     }
 
+    /// Sets the decompression limits.
+    ///
+    /// Once the stream exceeds `limits`, reads and writes fail with
+    /// [`ErrorKind::DecompressionLimitExceeded`].
+    ///
+    /// [`ErrorKind::DecompressionLimitExceeded`]: ../enum.ErrorKind.html#variant.DecompressionLimitExceeded
+    pub fn set_limits(&mut self, limits: DecompressionLimits) {
+        self.inner.set_limits(limits);
+    }
+
+    /// Sets the 'skip-checksums' mode.
+    ///
+    /// When the 'skip-checksums' mode is enabled, the decompressor neither computes
+    /// nor verifies block and content checksums. Use it only for trusted data.
+    /// Once enabled in the middle of a frame, checksums stay skipped until the end of the frame.
+    pub fn skip_checksums(&mut self, flag: bool) {
+        self.inner.skip_checksums(flag);
+    }
+
     /// Reads the frame header and returns `FrameInfo`.
     ///
     /// Calling this function before any `Read` operations
@@ -74,6 +96,48 @@ impl<'a, R: Read> ReadDecompressor<'a, R> {
         self.inner.read_frame_info()
     }
 
+    /// Returns the statistics of the stream so far.
+    ///
+    /// See [`Stats`] for how the data is counted.
+    ///
+    /// [`Stats`]: struct.Stats.html
+    pub fn stats(&self) -> Stats {
+        self.inner.stats()
+    }
+
+    /// Sets a callback which receives the statistics whenever the stream makes progress.
+    ///
+    /// The callback runs on the thread driving the stream, so keep it cheap.
+    pub fn set_progress<F>(&mut self, callback: F)
+    where
+        F: FnMut(&Stats) + Send + 'static,
+    {
+        self.inner.set_progress(callback);
+    }
+
+    /// Returns the content checksum of the last frame that has reached its end.
+    ///
+    /// The checksum has been verified against the decompressed data unless checksums
+    /// are skipped. Returns `None` before the end of the first frame, or if the last
+    /// frame has no content checksum.
+    pub fn content_checksum(&self) -> Option<u32> {
+        self.inner.content_checksum()
+    }
+
+    /// Replaces the reader with `reader` to decompress a new stream, returning the old reader.
+    ///
+    /// The decompression context, the dictionary, the buffer and the settings are reused.
+    /// The decompressed data which has not been read yet is discarded,
+    /// along with the data buffered from the old reader, and the statistics start over.
+    pub fn reset(&mut self, reader: R) -> R {
+        let buf = self.inner.get_mut();
+        let len = buf.buffer().len();
+        buf.consume(len);
+        let reader = std::mem::replace(buf.get_mut(), reader);
+        self.inner.restart();
+        reader
+    }
+
     /// Returns ownership of the reader.
     pub fn into_inner(self) -> R {
         self.inner.into_inner().into_inner()
@@ -94,4 +158,9 @@ impl<R: Read> Read for ReadDecompressor<'_, R> {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         self.inner.read(buf)
     }
+
+    #[cfg(lzzzz_read_buf)]
+    fn read_buf(&mut self, cursor: std::io::BorrowedCursor<'_>) -> std::io::Result<()> {
+        self.inner.read_buf(cursor)
+    }
 }
@@ -0,0 +1,740 @@
+use crate::{
+    common::{DEFAULT_BUF_SIZE, DICTIONARY_SIZE},
+    lz4,
+    lz4f::{
+        header::{read_u32, BlockHeader, Flags, FrameHeader, Magic},
+        limits::LimitTracker,
+        DecompressionLimits, Error, ErrorKind, Result,
+    },
+    xxhash::{lz4f_block_checksum, Xxh32},
+};
+use std::{
+    borrow::Cow,
+    cmp, fmt,
+    io::{self, Read},
+    mem,
+    ops::Range,
+};
+
+/// The trial decoding work earned by each byte skipped while looking for an intact block.
+///
+/// It keeps resynchronization linear in the size of the damaged region
+/// even if the region is full of block headers which look valid.
+const RESYNC_CREDIT: u64 = 256;
+
+/// A damaged region skipped by [`SalvageDecompressor`].
+///
+/// [`SalvageDecompressor`]: struct.SalvageDecompressor.html
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Damage {
+    error: Error,
+    input: Range<u64>,
+    output_offset: u64,
+    output: Option<Range<u64>>,
+}
+
+impl Damage {
+    /// Returns the error which made the region unreadable.
+    pub const fn error(&self) -> Error {
+        self.error
+    }
+
+    /// Returns the byte range of the compressed stream that was skipped.
+    pub fn input_range(&self) -> Range<u64> {
+        self.input.clone()
+    }
+
+    /// Returns the offset in the decompressed output where the data of the region is missing.
+    pub const fn output_offset(&self) -> u64 {
+        self.output_offset
+    }
+
+    /// Returns the byte range of the original data that was lost, if it is known.
+    ///
+    /// Unlike [`output_offset`], the range counts the data lost in earlier regions too.
+    /// The decompressed length of a damaged block is not recorded in the stream, so
+    /// it is known only if the frame header has the content size and every damaged block
+    /// of the frame can be told apart, or if the region held no data at all.
+    /// Otherwise, and for all the regions after it, this returns `None`.
+    /// It also returns `None` until the frame of the region has been read to the end.
+    ///
+    /// [`output_offset`]: #method.output_offset
+    pub fn output_range(&self) -> Option<Range<u64>> {
+        self.output.clone()
+    }
+}
+
+/// The [`Read`]-based streaming decompressor that recovers from corrupted data.
+///
+/// Unlike [`ReadDecompressor`], `SalvageDecompressor` does not stop at the first
+/// damaged block. In frames with independent blocks, it skips the block and
+/// resumes at the next intact one. In frames with linked blocks, the rest of
+/// the frame depends on the damaged block, so it resumes at the next frame.
+/// Garbage between frames is skipped in the same way.
+///
+/// Every skipped region is reported by [`damage`].
+/// Use block checksums for reliable detection; without them,
+/// a damaged block may decode into wrong data.
+///
+/// The search for the next intact block takes time linear in the size of the damaged
+/// region. If the region is crowded with block headers which look valid, the search may
+/// pass over the intact block right after it and resume at a later block or frame.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f::{self, BlockChecksum, BlockMode, PreferencesBuilder, SalvageDecompressor};
+/// use std::io::prelude::*;
+///
+/// let data = vec![b'a'; 1 << 20];
+/// let prefs = PreferencesBuilder::new()
+///     .block_mode(BlockMode::Independent)
+///     .block_checksum(BlockChecksum::Enabled)
+///     .build();
+/// let mut comp = Vec::new();
+/// lz4f::compress_to_vec(&data, &mut comp, &prefs)?;
+///
+/// // Damage the first block.
+/// comp[12] ^= 0xff;
+///
+/// let mut r = SalvageDecompressor::new(comp.as_slice())?;
+/// let mut buf = Vec::new();
+/// r.read_to_end(&mut buf)?;
+///
+/// assert!(buf.len() < data.len());
+/// assert_eq!(r.damage()[0].output_offset(), 0);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// [`Read`]: https://doc.rust-lang.org/std/io/trait.Read.html
+/// [`ReadDecompressor`]: struct.ReadDecompressor.html
+/// [`damage`]: #method.damage
+pub struct SalvageDecompressor<'a, R: Read> {
+    input: Input<R>,
+    state: State,
+    dict: Cow<'a, [u8]>,
+    output: Vec<u8>,
+    output_pos: usize,
+    produced: u64,
+    lost: Option<u64>,
+    frames: u64,
+    damage: Vec<Damage>,
+    pending: Vec<usize>,
+    tracker: LimitTracker,
+    limit_exceeded: bool,
+}
+
+impl<R> fmt::Debug for SalvageDecompressor<'_, R>
+where
+    R: Read + fmt::Debug,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("SalvageDecompressor")
+            .field("reader", &self.input.inner)
+            .field("damage", &self.damage)
+            .finish()
+    }
+}
+
+impl<'a, R: Read> SalvageDecompressor<'a, R> {
+    /// Creates a new `SalvageDecompressor<R>`.
+    pub fn new(reader: R) -> Result<Self> {
+        Ok(Self {
+            input: Input::new(reader),
+            state: State::Magic,
+            dict: Cow::Borrowed(&[]),
+            output: Vec::new(),
+            output_pos: 0,
+            produced: 0,
+            lost: Some(0),
+            frames: 0,
+            damage: Vec::new(),
+            pending: Vec::new(),
+            tracker: LimitTracker::default(),
+            limit_exceeded: false,
+        })
+    }
+
+    /// Sets the dictionary.
+    pub fn set_dict<D>(&mut self, dict: D)
+    where
+        D: Into<Cow<'a, [u8]>>,
+    {
+        self.dict = dict.into();
+    }
+
+    /// Sets the decompression limits.
+    ///
+    /// Once the stream exceeds `limits`, reads fail with
+    /// [`ErrorKind::DecompressionLimitExceeded`]. Exceeding a limit is not
+    /// damage, so decoding does not resume after it.
+    /// Skipped regions count as consumed input for `max_ratio`.
+    ///
+    /// [`ErrorKind::DecompressionLimitExceeded`]: ../enum.ErrorKind.html#variant.DecompressionLimitExceeded
+    pub fn set_limits(&mut self, limits: DecompressionLimits) {
+        self.tracker.set_limits(limits);
+    }
+
+    /// Returns the damaged regions skipped so far, in stream order.
+    pub fn damage(&self) -> &[Damage] {
+        &self.damage
+    }
+
+    /// Returns ownership of the reader.
+    pub fn into_inner(self) -> R {
+        self.input.inner
+    }
+
+    /// Returns a mutable reference to the reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.input.inner
+    }
+
+    /// Returns a shared reference to the reader.
+    pub fn get_ref(&self) -> &R {
+        &self.input.inner
+    }
+
+    /// Advances the decoding by a header, a block or a skipped region.
+    ///
+    /// Decoded data is appended to the empty output buffer,
+    /// which is cut short if it exceeds the limits.
+    /// Returns `false` at the end of the stream.
+    fn step(&mut self) -> io::Result<bool> {
+        let more = match self.state {
+            State::Magic => self.step_magic(),
+            State::Skip(_) => self.step_skip(),
+            State::Block(_) => self.step_block(),
+            State::Resync { .. } => self.step_resync(),
+        }?;
+
+        let (consumed, _) = self.tracker.totals();
+        self.tracker
+            .consume((self.input.position() - consumed as u64) as usize);
+        let remaining = self.tracker.remaining();
+        if self.output.len() > remaining {
+            self.output.truncate(remaining);
+            self.limit_exceeded = true;
+        }
+        self.tracker.produce(self.output.len());
+        Ok(more)
+    }
+
+    fn step_magic(&mut self) -> io::Result<bool> {
+        let start = self.input.position();
+        if !self.input.fill(4)? {
+            if self.input.avail().is_empty() {
+                return Ok(false);
+            }
+            return Ok(self.truncate(start));
+        }
+
+        let magic = Magic::parse(read_u32(self.input.avail()));
+        if magic == Magic::Skippable {
+            if !self.input.fill(8)? {
+                return Ok(self.truncate(start));
+            }
+            let len = read_u32(&self.input.avail()[4..]);
+            self.input.advance(8);
+            if len > 0 {
+                self.state = State::Skip(len.into());
+            }
+            return Ok(true);
+        }
+        if magic != Magic::Frame {
+            self.input.advance(1);
+            self.state = State::Resync {
+                error: Error::lz4f(ErrorKind::FrameTypeUnknown),
+                start,
+                frame: None,
+                lost: Some(0),
+                budget: 0,
+            };
+            return Ok(true);
+        }
+
+        if !self.input.fill(7)? {
+            return Ok(self.truncate(start));
+        }
+        let len = Flags(self.input.avail()[4]).header_len();
+        if !self.input.fill(len)? {
+            return Ok(self.truncate(start));
+        }
+        let index = self.frames;
+        self.frames += 1;
+        match FrameHeader::parse(&self.input.avail()[..len]) {
+            Ok(header) => {
+                let content_size = header.content_size.unwrap_or(0);
+                let content_size = usize::try_from(content_size).unwrap_or(usize::MAX);
+                if self
+                    .tracker
+                    .begin_frame_with(header.block_size, content_size)
+                    .is_err()
+                {
+                    self.limit_exceeded = true;
+                    return Ok(true);
+                }
+                let frame = Frame::new(&header, &self.dict, index);
+                self.input.advance(len);
+                self.state = State::Block(frame);
+            }
+            Err(error) => {
+                // The block layout is unknown, so look for the next frame.
+                self.input.advance(1);
+                self.state = State::Resync {
+                    error: error.with_frame_index(index),
+                    start,
+                    frame: None,
+                    lost: None,
+                    budget: 0,
+                };
+            }
+        }
+        Ok(true)
+    }
+
+    fn step_skip(&mut self) -> io::Result<bool> {
+        let start = self.input.position();
+        let State::Skip(remaining) = &mut self.state else {
+            unreachable!()
+        };
+        if !self.input.fill(1)? {
+            return Ok(self.truncate(start));
+        }
+        let len = cmp::min(self.input.avail().len() as u64, *remaining);
+        self.input.advance(len as usize);
+        *remaining -= len;
+        if *remaining == 0 {
+            self.state = State::Magic;
+        }
+        Ok(true)
+    }
+
+    fn step_block(&mut self) -> io::Result<bool> {
+        let start = self.input.position();
+        let State::Block(frame) = &mut self.state else {
+            unreachable!()
+        };
+        if !self.input.fill(4)? {
+            return Ok(self.truncate(start));
+        }
+
+        let BlockHeader::Data { size, .. } = BlockHeader::parse(read_u32(self.input.avail()))
+        else {
+            let len = if frame.content_checksum { 8 } else { 4 };
+            if !self.input.fill(len)? {
+                return Ok(self.truncate(start));
+            }
+            let (expected, actual) = if frame.content_checksum && !frame.damaged {
+                (read_u32(&self.input.avail()[4..]), frame.hasher.digest())
+            } else {
+                (0, 0)
+            };
+            let index = frame.index;
+            self.input.advance(len);
+            self.end_frame();
+            if expected != actual {
+                let error = Error::lz4f(ErrorKind::ContentChecksumInvalid)
+                    .with_frame_index(index)
+                    .with_checksums(expected, actual);
+                self.report(error, start, Some(0));
+            }
+            return Ok(true);
+        };
+
+        let index = frame.blocks;
+        if size > frame.block_size {
+            // The block header itself is broken.
+            self.input.advance(1);
+            self.resync(Error::lz4f(ErrorKind::MaxBlockSizeInvalid), index, start);
+            return Ok(true);
+        }
+        let len = 4 + size + if frame.block_checksum { 4 } else { 0 };
+        if !self.input.fill(len)? {
+            return Ok(self.truncate(start));
+        }
+
+        let dict = if frame.independent {
+            &self.dict[..]
+        } else {
+            &frame.history[..]
+        };
+        let result = frame.decode(&self.input.avail()[..len], dict, &mut self.output);
+        self.input.advance(len);
+        frame.blocks += 1;
+        match result {
+            Ok(()) => {
+                frame.hasher.update(&self.output);
+                if !frame.independent {
+                    frame.history.extend_from_slice(&self.output);
+                    let excess = frame.history.len().saturating_sub(DICTIONARY_SIZE);
+                    frame.history.drain(..excess);
+                }
+                frame.produced += self.output.len() as u64;
+                self.produced += self.output.len() as u64;
+            }
+            Err(error) => {
+                self.output.clear();
+                self.resync(error, index, start);
+            }
+        }
+        Ok(true)
+    }
+
+    fn step_resync(&mut self) -> io::Result<bool> {
+        let State::Resync { frame, budget, .. } = &mut self.state else {
+            unreachable!()
+        };
+        let mut found = false;
+        loop {
+            if !self.input.fill(4)? {
+                let len = self.input.avail().len();
+                self.input.advance(len);
+                break;
+            }
+            if Magic::parse(read_u32(self.input.avail())) == Magic::Frame {
+                break;
+            }
+            if let Some(frame) = frame.as_ref().filter(|frame| frame.independent) {
+                if frame.find_block(&mut self.input, &self.dict, &mut self.output, budget)? {
+                    found = true;
+                    break;
+                }
+            }
+            self.input.advance(1);
+            *budget += RESYNC_CREDIT;
+        }
+
+        let State::Resync {
+            error,
+            start,
+            frame,
+            lost,
+            ..
+        } = mem::replace(&mut self.state, State::Magic)
+        else {
+            unreachable!()
+        };
+        match frame {
+            Some(frame) => {
+                self.pending.push(self.damage.len());
+                self.report(error, start, None);
+                self.state = State::Block(frame);
+                if !found {
+                    self.end_frame();
+                }
+            }
+            None => self.report(error, start, lost),
+        }
+        Ok(true)
+    }
+
+    /// Skips the rest of the current block, resuming at the next one if
+    /// blocks are independent or at the next frame otherwise.
+    fn resync(&mut self, error: Error, block_index: u64, start: u64) {
+        let State::Block(mut frame) = mem::replace(&mut self.state, State::Magic) else {
+            unreachable!()
+        };
+        frame.damaged = true;
+        self.state = State::Resync {
+            error: error
+                .with_frame_index(frame.index)
+                .with_block_index(block_index),
+            start,
+            budget: frame.block_size as u64 + 8,
+            frame: Some(frame),
+            lost: None,
+        };
+    }
+
+    /// Leaves the current frame, working out the lengths of its damaged regions.
+    ///
+    /// The content size tells the total length of the data lost in the frame.
+    /// It is split among the regions only if there is one,
+    /// or if all of them are full blocks.
+    fn end_frame(&mut self) {
+        let State::Block(frame) = mem::replace(&mut self.state, State::Magic) else {
+            unreachable!()
+        };
+        let pending = mem::take(&mut self.pending);
+        let remaining = frame
+            .content_size
+            .and_then(|size| size.checked_sub(frame.produced));
+        let len = match (remaining, pending.len() as u64) {
+            (_, 0) => return,
+            (Some(remaining), 1) => Some(remaining),
+            (Some(remaining), n) if remaining == n * frame.block_size as u64 => {
+                Some(frame.block_size as u64)
+            }
+            _ => None,
+        };
+        for index in pending {
+            self.settle(index, len);
+        }
+    }
+
+    /// Skips the rest of the stream, which ends in the middle of a frame.
+    fn truncate(&mut self, start: u64) -> bool {
+        let error = Error::new(crate::ErrorKind::CompressedDataIncomplete);
+        let len = self.input.avail().len();
+        self.input.advance(len);
+        match &self.state {
+            State::Block(frame) => {
+                let error = error
+                    .with_frame_index(frame.index)
+                    .with_block_index(frame.blocks);
+                self.pending.push(self.damage.len());
+                self.report(error, start, None);
+                self.end_frame();
+            }
+            // A skippable frame holds no data.
+            State::Skip(_) => {
+                self.state = State::Magic;
+                self.report(error, start, Some(0));
+            }
+            _ => {
+                self.state = State::Magic;
+                self.report(error, start, None);
+            }
+        }
+        true
+    }
+
+    /// Records a damaged region which lost `len` bytes of data.
+    ///
+    /// The length of a region in a frame is settled later by [`end_frame`],
+    /// so `len` is not used for it.
+    ///
+    /// [`end_frame`]: #method.end_frame
+    fn report(&mut self, error: Error, start: u64, len: Option<u64>) {
+        let index = self.damage.len();
+        self.damage.push(Damage {
+            error: error.with_offsets(start, self.produced),
+            input: start..self.input.position(),
+            output_offset: self.produced,
+            output: None,
+        });
+        if self.pending.last() != Some(&index) {
+            self.settle(index, len);
+        }
+    }
+
+    /// Sets the output range of a damaged region once its length is known.
+    fn settle(&mut self, index: usize, len: Option<u64>) {
+        let damage = &mut self.damage[index];
+        self.lost = self.lost.zip(len).map(|(lost, len)| {
+            let start = damage.output_offset + lost;
+            damage.output = Some(start..start + len);
+            lost + len
+        });
+    }
+}
+
+impl<R: Read> Read for SalvageDecompressor<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.output_pos >= self.output.len() {
+            if self.limit_exceeded {
+                let (consumed, produced) = self.tracker.totals();
+                let err = Error::new(crate::ErrorKind::DecompressionLimitExceeded)
+                    .with_offsets(consumed as u64, produced as u64);
+                return Err(err.into());
+            }
+            self.output.clear();
+            self.output_pos = 0;
+            if !self.step()? {
+                return Ok(0);
+            }
+        }
+        let len = cmp::min(buf.len(), self.output.len() - self.output_pos);
+        buf[..len].copy_from_slice(&self.output[self.output_pos..][..len]);
+        self.output_pos += len;
+        Ok(len)
+    }
+}
+
+enum State {
+    Magic,
+    Skip(u64),
+    Block(Frame),
+    Resync {
+        error: Error,
+        start: u64,
+        /// The frame of the damaged block, which is resumed if its blocks are independent.
+        frame: Option<Frame>,
+        /// The length of the data lost outside of a frame.
+        lost: Option<u64>,
+        /// The trial decoding work that may still be spent.
+        budget: u64,
+    },
+}
+
+struct Frame {
+    index: u64,
+    blocks: u64,
+    block_size: usize,
+    content_size: Option<u64>,
+    produced: u64,
+    independent: bool,
+    block_checksum: bool,
+    content_checksum: bool,
+    hasher: Xxh32,
+    history: Vec<u8>,
+    damaged: bool,
+}
+
+impl Frame {
+    fn new(header: &FrameHeader, dict: &[u8], index: u64) -> Self {
+        let independent = header.flags.independent_blocks();
+        Self {
+            index,
+            blocks: 0,
+            block_size: header.block_size.bytes(),
+            content_size: header.content_size,
+            produced: 0,
+            independent,
+            block_checksum: header.flags.block_checksum(),
+            content_checksum: header.flags.content_checksum(),
+            hasher: Xxh32::default(),
+            history: if independent {
+                Vec::new()
+            } else {
+                dict[dict.len().saturating_sub(DICTIONARY_SIZE)..].to_vec()
+            },
+            damaged: false,
+        }
+    }
+
+    /// Decodes a block, including its header and checksum, into the empty `dst`.
+    fn decode(&self, block: &[u8], dict: &[u8], dst: &mut Vec<u8>) -> Result<()> {
+        let BlockHeader::Data { size, stored } = BlockHeader::parse(read_u32(block)) else {
+            unreachable!()
+        };
+        let data = &block[4..][..size];
+        if self.block_checksum {
+            let expected = read_u32(&block[4 + data.len()..]);
+            let actual = lz4f_block_checksum(data);
+            if expected != actual {
+                return Err(
+                    Error::lz4f(ErrorKind::BlockChecksumInvalid).with_checksums(expected, actual)
+                );
+            }
+        }
+        if stored {
+            dst.extend_from_slice(data);
+            return Ok(());
+        }
+        dst.resize(self.block_size, 0);
+        let len = lz4::decompress_with_dict(data, dst, dict)
+            .map_err(|_| Error::lz4f(ErrorKind::DecompressionFailed))?;
+        dst.truncate(len);
+        Ok(())
+    }
+
+    /// Returns `true` if an intact block or the end mark of the frame
+    /// starts at the current position of `input`.
+    ///
+    /// The data after the candidate has to look like the next block
+    /// or the next frame, which rules out most false positives.
+    /// The candidate is decoded only if its length fits in `budget`,
+    /// and the length is taken from it.
+    fn find_block<R: Read>(
+        &self,
+        input: &mut Input<R>,
+        dict: &[u8],
+        scratch: &mut Vec<u8>,
+        budget: &mut u64,
+    ) -> io::Result<bool> {
+        let BlockHeader::Data { size, .. } = BlockHeader::parse(read_u32(input.avail())) else {
+            let len = if self.content_checksum { 8 } else { 4 };
+            let complete = input.fill(len + 4)?;
+            let avail = input.avail();
+            return Ok(if complete {
+                Magic::parse(read_u32(&avail[len..])) == Magic::Frame
+            } else {
+                avail.len() == len
+            });
+        };
+        if size == 0 || size > self.block_size {
+            return Ok(false);
+        }
+        let len = 4 + size + if self.block_checksum { 4 } else { 0 };
+        if len as u64 > *budget {
+            return Ok(false);
+        }
+        let complete = input.fill(len + 4)?;
+        let avail = input.avail();
+        if avail.len() < len {
+            return Ok(false);
+        }
+        if complete {
+            let next = read_u32(&avail[len..]);
+            let plausible = match BlockHeader::parse(next) {
+                BlockHeader::Data { size, .. } => size <= self.block_size,
+                BlockHeader::EndMark => true,
+            };
+            if !plausible && Magic::parse(next) != Magic::Frame {
+                return Ok(false);
+            }
+        }
+        *budget -= len as u64;
+        let found = self.decode(&avail[..len], dict, scratch).is_ok();
+        scratch.clear();
+        Ok(found)
+    }
+}
+
+/// Buffers the input so that the decoder can look ahead and step back.
+struct Input<R: Read> {
+    inner: R,
+    buf: Vec<u8>,
+    pos: usize,
+    offset: u64,
+    eof: bool,
+}
+
+impl<R: Read> Input<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buf: Vec::new(),
+            pos: 0,
+            offset: 0,
+            eof: false,
+        }
+    }
+
+    /// Reads until at least `len` bytes are available.
+    ///
+    /// Returns `false` if the stream ends before that.
+    fn fill(&mut self, len: usize) -> io::Result<bool> {
+        while self.buf.len() - self.pos < len && !self.eof {
+            if self.pos >= self.buf.len() / 2 {
+                self.buf.drain(..self.pos);
+                self.offset += self.pos as u64;
+                self.pos = 0;
+            }
+            let filled = self.buf.len();
+            let additional = cmp::max(len - (filled - self.pos), DEFAULT_BUF_SIZE);
+            self.buf.resize(filled + additional, 0);
+            let result = self.inner.read(&mut self.buf[filled..]);
+            self.buf.truncate(filled + *result.as_ref().unwrap_or(&0));
+            if result? == 0 {
+                self.eof = true;
+            }
+        }
+        Ok(self.buf.len() - self.pos >= len)
+    }
+
+    fn avail(&self) -> &[u8] {
+        &self.buf[self.pos..]
+    }
+
+    fn advance(&mut self, len: usize) {
+        self.pos += len;
+    }
+
+    /// Returns the offset of the first available byte in the stream.
+    fn position(&self) -> u64 {
+        self.offset + self.pos as u64
+    }
+}
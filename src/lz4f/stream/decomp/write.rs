@@ -1,4 +1,4 @@
-use crate::lz4f::{Decompressor, FrameInfo, Result};
+use crate::lz4f::{DecompressionLimits, Decompressor, FrameInfo, Result, Stats};
 use std::{borrow::Cow, fmt, io::Write};
 
 /// The [`Write`]-based streaming decompressor.
@@ -22,14 +22,15 @@ use std::{borrow::Cow, fmt, io::Write};
 /// compress_to_vec(b"Hello world!", &mut buf, &Default::default())?;
 ///
 /// w.write_all(&buf)?;
+/// w.finish()?;
 /// # Ok::<(), std::io::Error>(())
 /// ```
 ///
 /// [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
-
 pub struct WriteDecompressor<'a, W: Write> {
     inner: W,
     decomp: Decompressor<'a>,
+    consumed: usize,
 }
 
 impl<'a, W: Write> WriteDecompressor<'a, W> {
@@ -38,6 +39,7 @@ impl<'a, W: Write> WriteDecompressor<'a, W> {
         Ok(Self {
             inner: writer,
             decomp: Decompressor::new()?,
+            consumed: 0,
         })
     }
 
@@ -64,6 +66,53 @@ impl<'a, W: Write> WriteDecompressor<'a, W> {
         self.decomp.decode_header_only(flag);
     }
 
+    /// Sets the decompression limits.
+    ///
+    /// Once the stream exceeds `limits`, reads and writes fail with
+    /// [`ErrorKind::DecompressionLimitExceeded`].
+    ///
+    /// [`ErrorKind::DecompressionLimitExceeded`]: ../enum.ErrorKind.html#variant.DecompressionLimitExceeded
+    pub fn set_limits(&mut self, limits: DecompressionLimits) {
+        self.decomp.set_limits(limits);
+    }
+
+    /// Sets the 'skip-checksums' mode.
+    ///
+    /// When the 'skip-checksums' mode is enabled, the decompressor neither computes
+    /// nor verifies block and content checksums. Use it only for trusted data.
+    /// Once enabled in the middle of a frame, checksums stay skipped until the end of the frame.
+    pub fn skip_checksums(&mut self, flag: bool) {
+        self.decomp.skip_checksums(flag);
+    }
+
+    /// Returns the statistics of the stream so far.
+    ///
+    /// See [`Stats`] for how the data is counted.
+    ///
+    /// [`Stats`]: struct.Stats.html
+    pub fn stats(&self) -> Stats {
+        self.decomp.stats()
+    }
+
+    /// Sets a callback which receives the statistics whenever the stream makes progress.
+    ///
+    /// The callback runs on the thread driving the stream, so keep it cheap.
+    pub fn set_progress<F>(&mut self, callback: F)
+    where
+        F: FnMut(&Stats) + Send + 'static,
+    {
+        self.decomp.set_progress(Box::new(callback));
+    }
+
+    /// Returns the content checksum of the last frame that has reached its end.
+    ///
+    /// The checksum has been verified against the decompressed data unless checksums
+    /// are skipped. Returns `None` before the end of the first frame, or if the last
+    /// frame has no content checksum.
+    pub fn content_checksum(&self) -> Option<u32> {
+        self.decomp.content_checksum()
+    }
+
     /// Returns a mutable reference to the writer.
     pub fn get_mut(&mut self) -> &mut W {
         &mut self.inner
@@ -74,10 +123,68 @@ impl<'a, W: Write> WriteDecompressor<'a, W> {
         &self.inner
     }
 
+    /// Writes out the pending data to the current writer, and replaces the writer with `writer`
+    /// to decompress a new stream, returning the old writer.
+    ///
+    /// The decompression context, the dictionary, the buffer and the settings are reused,
+    /// and the statistics start over. A frame which has not reached its end mark
+    /// is discarded; call [`try_finish`] first to check that the stream is complete.
+    ///
+    /// On error, the current writer is kept.
+    ///
+    /// [`try_finish`]: #method.try_finish
+    pub fn reset(&mut self, writer: W) -> std::io::Result<W> {
+        self.write_pending()?;
+        self.inner.flush()?;
+        self.decomp.reset();
+        Ok(std::mem::replace(&mut self.inner, writer))
+    }
+
     /// Returns ownership of the writer.
     pub fn into_inner(self) -> W {
         self.inner
     }
+
+    /// Writes out the pending data and checks that the stream is complete.
+    ///
+    /// Returns [`ErrorKind::CompressedDataIncomplete`] if the last frame has not
+    /// reached its end mark. The partial output has been written to the writer
+    /// by then, and stays accessible through [`get_ref`] or [`into_inner`].
+    ///
+    /// [`ErrorKind::CompressedDataIncomplete`]: ../enum.ErrorKind.html#variant.CompressedDataIncomplete
+    /// [`get_ref`]: #method.get_ref
+    /// [`into_inner`]: #method.into_inner
+    pub fn try_finish(&mut self) -> std::io::Result<()> {
+        self.write_pending()?;
+        self.inner.flush()?;
+        self.decomp.finish()?;
+        Ok(())
+    }
+
+    /// Checks that the stream is complete and returns ownership of the writer.
+    ///
+    /// See [`try_finish`] for the errors. Use it instead to keep the writer on error.
+    ///
+    /// [`try_finish`]: #method.try_finish
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.try_finish()?;
+        Ok(self.inner)
+    }
+
+    /// Writes out the decompressed data left in the buffer.
+    ///
+    /// On error, the unwritten part stays in the buffer for the next call.
+    fn write_pending(&mut self) -> std::io::Result<()> {
+        while self.consumed < self.decomp.buf().len() {
+            match self.inner.write(&self.decomp.buf()[self.consumed..])? {
+                0 => return Err(std::io::ErrorKind::WriteZero.into()),
+                len => self.consumed += len,
+            }
+        }
+        self.decomp.clear_buf();
+        self.consumed = 0;
+        Ok(())
+    }
 }
 
 impl<W> fmt::Debug for WriteDecompressor<'_, W>
@@ -93,13 +200,22 @@ where
 
 impl<W: Write> Write for WriteDecompressor<'_, W> {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        let report = self.decomp.decompress(buf)?;
-        self.inner.write_all(self.decomp.buf())?;
-        self.decomp.clear_buf();
-        Ok(report)
+        self.write_pending()?;
+        loop {
+            let report = self.decomp.decompress(buf)?;
+            if report > 0 || buf.is_empty() || self.decomp.buf().is_empty() {
+                // `buf` has been consumed, so a failure here must not be reported.
+                // The output stays pending and is written out on the next call.
+                let _ = self.write_pending();
+                return Ok(report);
+            }
+            // The decompressor only flushed its internal buffer.
+            self.write_pending()?;
+        }
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
+        self.write_pending()?;
         self.inner.flush()
     }
 }
@@ -1,2 +1,3 @@
 pub mod comp;
+pub mod copy;
 pub mod decomp;
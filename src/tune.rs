@@ -0,0 +1,238 @@
+//! Compression setting tuning.
+//!
+//! The functions in this module compress samples of your data with a range of
+//! settings, measure the compression ratio and throughput of each, and pick
+//! the setting that meets a [`Goal`].
+//!
+//! - LZ4: acceleration factors from [`ACC_LEVEL_DEFAULT`] up to 64.
+//! - LZ4_HC: compression levels from [`CLEVEL_MIN`] to [`CLEVEL_MAX`].
+//! - LZ4F: both ranges above, combined with the block sizes
+//!   that make a difference for the samples.
+//!
+//! Each sample is truncated to 1 MiB around its middle. Throughput depends on
+//! the machine and its load, so tune on the machine that runs the compression,
+//! and with optimizations enabled.
+//!
+//! # Example
+//!
+//! ```
+//! use lzzzz::{lz4f, tune};
+//!
+//! let data = b"The quick brown fox jumps over the lazy dog.".repeat(100);
+//!
+//! // The best ratio at 10 MB/s or faster.
+//! if let Some(prefs) = tune::lz4f_preferences(&[&data], tune::Goal::Throughput(10.0))? {
+//!     let mut buf = Vec::new();
+//!     lz4f::compress_to_vec(&data, &mut buf, &prefs)?;
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! [`Goal`]: enum.Goal.html
+//! [`ACC_LEVEL_DEFAULT`]: ../lz4/constant.ACC_LEVEL_DEFAULT.html
+//! [`CLEVEL_MIN`]: ../lz4_hc/constant.CLEVEL_MIN.html
+//! [`CLEVEL_MAX`]: ../lz4_hc/constant.CLEVEL_MAX.html
+
+use crate::{
+    lz4, lz4_hc,
+    lz4f::{self, BlockSize, Preferences, PreferencesBuilder},
+    Result,
+};
+use std::time::{Duration, Instant};
+
+const SAMPLE_SIZE_MAX: usize = 1 << 20;
+const MEASUREMENT_DURATION: Duration = Duration::from_millis(20);
+const ACC_LEVELS: [i32; 7] = [lz4::ACC_LEVEL_DEFAULT, 2, 4, 8, 16, 32, 64];
+const BLOCK_SIZES: [BlockSize; 4] = [
+    BlockSize::Max64KB,
+    BlockSize::Max256KB,
+    BlockSize::Max1MB,
+    BlockSize::Max4MB,
+];
+
+/// The requirement for a tuned setting.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Goal {
+    /// Compress at least this many megabytes (10^6 bytes) per second,
+    /// with the best compression ratio.
+    Throughput(f64),
+    /// Reach at least this compression ratio (original size / compressed size),
+    /// with the best throughput.
+    Ratio(f64),
+}
+
+/// The result of compressing the samples with a setting.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Measurement<T> {
+    setting: T,
+    ratio: f64,
+    throughput: f64,
+}
+
+impl<T: Copy> Measurement<T> {
+    /// Returns the measured setting.
+    pub fn setting(&self) -> T {
+        self.setting
+    }
+
+    /// Returns the compression ratio (original size / compressed size).
+    pub fn ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    /// Returns the compression throughput in megabytes (10^6 bytes) per second.
+    pub fn throughput(&self) -> f64 {
+        self.throughput
+    }
+}
+
+/// Measures LZ4 compression with each acceleration factor.
+pub fn measure_lz4(samples: &[&[u8]]) -> Result<Vec<Measurement<i32>>> {
+    let samples = select_samples(samples);
+    ACC_LEVELS
+        .iter()
+        .map(|&acc| {
+            measure(&samples, acc, |src, dst| {
+                lz4::compress_to_vec(src, dst, acc)
+            })
+        })
+        .collect()
+}
+
+/// Measures LZ4_HC compression with each compression level.
+pub fn measure_lz4_hc(samples: &[&[u8]]) -> Result<Vec<Measurement<i32>>> {
+    let samples = select_samples(samples);
+    (lz4_hc::CLEVEL_MIN..=lz4_hc::CLEVEL_MAX)
+        .map(|level| {
+            measure(&samples, level, |src, dst| {
+                lz4_hc::compress_to_vec(src, dst, level)
+            })
+        })
+        .collect()
+}
+
+/// Measures LZ4F compression with each compression level and block size.
+///
+/// Block sizes larger than the largest sample give the same result as
+/// the smallest one that holds it, so they are not measured.
+pub fn measure_lz4f(samples: &[&[u8]]) -> Result<Vec<Measurement<Preferences>>> {
+    let samples = select_samples(samples);
+    let max_len = samples.iter().map(|sample| sample.len()).max().unwrap_or(0);
+    let block_sizes = match BLOCK_SIZES.iter().position(|size| size.bytes() >= max_len) {
+        Some(pos) => &BLOCK_SIZES[..=pos],
+        None => &BLOCK_SIZES[..],
+    };
+    // LZ4F levels below zero select acceleration factors.
+    let fast_levels = ACC_LEVELS.iter().map(|acc| 1 - acc);
+    let hc_levels = lz4_hc::CLEVEL_MIN..=lz4_hc::CLEVEL_MAX;
+
+    let mut measurements = Vec::new();
+    for level in fast_levels.chain(hc_levels) {
+        for &block_size in block_sizes {
+            let prefs = PreferencesBuilder::new()
+                .compression_level(level)
+                .block_size(block_size)
+                .build();
+            measurements.push(measure(&samples, prefs, |src, dst| {
+                lz4f::compress_to_vec(src, dst, &prefs)
+            })?);
+        }
+    }
+    Ok(measurements)
+}
+
+/// Returns the LZ4 acceleration factor that meets `goal` on `samples`.
+///
+/// Returns `None` if no factor meets `goal`.
+pub fn lz4_acceleration(samples: &[&[u8]], goal: Goal) -> Result<Option<i32>> {
+    Ok(recommend(&measure_lz4(samples)?, goal))
+}
+
+/// Returns the LZ4_HC compression level that meets `goal` on `samples`.
+///
+/// Returns `None` if no level meets `goal`.
+pub fn lz4_hc_level(samples: &[&[u8]], goal: Goal) -> Result<Option<i32>> {
+    Ok(recommend(&measure_lz4_hc(samples)?, goal))
+}
+
+/// Returns the LZ4F preferences that meet `goal` on `samples`.
+///
+/// Only the compression level and the block size are tuned;
+/// the other preferences have their default values.
+/// Returns `None` if no setting meets `goal`.
+pub fn lz4f_preferences(samples: &[&[u8]], goal: Goal) -> Result<Option<Preferences>> {
+    Ok(recommend(&measure_lz4f(samples)?, goal))
+}
+
+/// Picks the setting that meets `goal` and is the best in the other respect.
+fn recommend<T: Copy>(measurements: &[Measurement<T>], goal: Goal) -> Option<T> {
+    let best = match goal {
+        Goal::Throughput(min) => {
+            measurements
+                .iter()
+                .filter(|m| m.throughput >= min)
+                .max_by(|a, b| {
+                    a.ratio
+                        .total_cmp(&b.ratio)
+                        .then(a.throughput.total_cmp(&b.throughput))
+                })
+        }
+        Goal::Ratio(min) => measurements
+            .iter()
+            .filter(|m| m.ratio >= min)
+            .max_by(|a, b| {
+                a.throughput
+                    .total_cmp(&b.throughput)
+                    .then(a.ratio.total_cmp(&b.ratio))
+            }),
+    };
+    best.map(Measurement::setting)
+}
+
+/// Truncates each sample to `SAMPLE_SIZE_MAX` bytes around its middle,
+/// which skips file headers that rarely represent the rest of the data.
+fn select_samples<'a>(samples: &[&'a [u8]]) -> Vec<&'a [u8]> {
+    samples
+        .iter()
+        .filter(|sample| !sample.is_empty())
+        .map(|sample| {
+            let len = sample.len().min(SAMPLE_SIZE_MAX);
+            let start = (sample.len() - len) / 2;
+            &sample[start..][..len]
+        })
+        .collect()
+}
+
+/// Compresses the samples repeatedly for `MEASUREMENT_DURATION`.
+fn measure<T, F>(samples: &[&[u8]], setting: T, mut compress: F) -> Result<Measurement<T>>
+where
+    F: FnMut(&[u8], &mut Vec<u8>) -> Result<usize>,
+{
+    let total = samples.iter().map(|sample| sample.len()).sum::<usize>();
+    if total == 0 {
+        return Ok(Measurement {
+            setting,
+            ratio: 0.0,
+            throughput: 0.0,
+        });
+    }
+
+    let mut buf = Vec::new();
+    let mut compressed = 0;
+    let mut rounds = 0;
+    let start = Instant::now();
+    while rounds == 0 || start.elapsed() < MEASUREMENT_DURATION {
+        compressed = 0;
+        for sample in samples {
+            buf.clear();
+            compressed += compress(sample, &mut buf)?;
+        }
+        rounds += 1;
+    }
+    let secs = start.elapsed().as_secs_f64();
+    Ok(Measurement {
+        setting,
+        ratio: total as f64 / compressed as f64,
+        throughput: (total * rounds) as f64 / secs / 1e6,
+    })
+}
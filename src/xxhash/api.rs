@@ -0,0 +1,66 @@
+#![allow(unsafe_code)]
+
+use super::binding::{self, XXH32State, XXH64State};
+use std::os::raw::c_void;
+
+pub fn xxh32(data: &[u8], seed: u32) -> u32 {
+    unsafe { binding::XXH32(data.as_ptr() as *const c_void, data.len(), seed) }
+}
+
+pub fn xxh64(data: &[u8], seed: u64) -> u64 {
+    unsafe { binding::XXH64(data.as_ptr() as *const c_void, data.len(), seed) }
+}
+
+#[derive(Clone)]
+pub struct Xxh32State(XXH32State);
+
+impl Xxh32State {
+    pub fn new(seed: u32) -> Self {
+        let mut state = Self(XXH32State::default());
+        state.reset(seed);
+        state
+    }
+
+    pub fn reset(&mut self, seed: u32) {
+        unsafe {
+            binding::XXH32_reset(&mut self.0, seed);
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        unsafe {
+            binding::XXH32_update(&mut self.0, data.as_ptr() as *const c_void, data.len());
+        }
+    }
+
+    pub fn digest(&self) -> u32 {
+        unsafe { binding::XXH32_digest(&self.0) }
+    }
+}
+
+#[derive(Clone)]
+pub struct Xxh64State(XXH64State);
+
+impl Xxh64State {
+    pub fn new(seed: u64) -> Self {
+        let mut state = Self(XXH64State::default());
+        state.reset(seed);
+        state
+    }
+
+    pub fn reset(&mut self, seed: u64) {
+        unsafe {
+            binding::XXH64_reset(&mut self.0, seed);
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        unsafe {
+            binding::XXH64_update(&mut self.0, data.as_ptr() as *const c_void, data.len());
+        }
+    }
+
+    pub fn digest(&self) -> u64 {
+        unsafe { binding::XXH64_digest(&self.0) }
+    }
+}
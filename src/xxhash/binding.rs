@@ -0,0 +1,27 @@
+use std::os::raw::{c_uint, c_ulonglong, c_void};
+
+/// `XXH32_state_t` of the vendored `xxhash.h`.
+#[derive(Default, Clone, Copy)]
+#[repr(C)]
+pub struct XXH32State {
+    _private: [c_uint; 12],
+}
+
+/// `XXH64_state_t` of the vendored `xxhash.h`.
+#[derive(Default, Clone, Copy)]
+#[repr(C)]
+pub struct XXH64State {
+    _private: [c_ulonglong; 11],
+}
+
+extern "C" {
+    pub fn XXH32(input: *const c_void, length: usize, seed: c_uint) -> c_uint;
+    pub fn XXH32_reset(state: *mut XXH32State, seed: c_uint) -> i32;
+    pub fn XXH32_update(state: *mut XXH32State, input: *const c_void, length: usize) -> i32;
+    pub fn XXH32_digest(state: *const XXH32State) -> c_uint;
+
+    pub fn XXH64(input: *const c_void, length: usize, seed: c_ulonglong) -> c_ulonglong;
+    pub fn XXH64_reset(state: *mut XXH64State, seed: c_ulonglong) -> i32;
+    pub fn XXH64_update(state: *mut XXH64State, input: *const c_void, length: usize) -> i32;
+    pub fn XXH64_digest(state: *const XXH64State) -> c_ulonglong;
+}
@@ -0,0 +1,228 @@
+//! xxHash32 and xxHash64 hashing.
+//!
+//! xxHash: Extremely fast non-cryptographic hash algorithm.
+//!
+//! This module exposes the xxHash implementation bundled with liblz4,
+//! which LZ4F uses for its checksums.
+//!
+//! # LZ4F checksums
+//!
+//! [`lz4f_header_checksum`], [`lz4f_block_checksum`] and [`lz4f_content_checksum`]
+//! compute the checksums exactly as they are stored in an LZ4F frame.
+//!
+//! ```
+//! use lzzzz::{lz4f, xxhash};
+//!
+//! let data = b"The quick brown fox jumps over the lazy dog.";
+//! let prefs = lz4f::PreferencesBuilder::new()
+//!     .content_checksum(lz4f::ContentChecksum::Enabled)
+//!     .build();
+//! let mut buf = Vec::new();
+//! lz4f::compress_to_vec(data, &mut buf, &prefs)?;
+//!
+//! // The content checksum is the last field of a frame.
+//! let stored = u32::from_le_bytes(buf[buf.len() - 4..].try_into().unwrap());
+//! assert_eq!(stored, xxhash::lz4f_content_checksum(data));
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! [`lz4f_header_checksum`]: fn.lz4f_header_checksum.html
+//! [`lz4f_block_checksum`]: fn.lz4f_block_checksum.html
+//! [`lz4f_content_checksum`]: fn.lz4f_content_checksum.html
+
+mod api;
+mod binding;
+
+use api::{Xxh32State, Xxh64State};
+use std::{fmt, hash::Hasher};
+
+/// Calculates the xxHash32 of `data`.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::xxhash;
+///
+/// assert_eq!(xxhash::xxh32(b"", 0), 0x02cc_5d05);
+/// ```
+pub fn xxh32(data: &[u8], seed: u32) -> u32 {
+    api::xxh32(data, seed)
+}
+
+/// Calculates the xxHash64 of `data`.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::xxhash;
+///
+/// assert_eq!(xxhash::xxh64(b"", 0), 0xef46_db37_51d8_e999);
+/// ```
+pub fn xxh64(data: &[u8], seed: u64) -> u64 {
+    api::xxh64(data, seed)
+}
+
+/// Calculates the header checksum of an LZ4F frame.
+///
+/// `descriptor` is the frame descriptor: the bytes between
+/// the magic number and the header checksum.
+pub fn lz4f_header_checksum(descriptor: &[u8]) -> u8 {
+    (api::xxh32(descriptor, 0) >> 8) as u8
+}
+
+/// Calculates the block checksum of an LZ4F data block.
+///
+/// `block` is the block data as stored in the frame, compressed or not,
+/// without the block size field.
+pub fn lz4f_block_checksum(block: &[u8]) -> u32 {
+    api::xxh32(block, 0)
+}
+
+/// Calculates the content checksum of an LZ4F frame holding `content`.
+pub fn lz4f_content_checksum(content: &[u8]) -> u32 {
+    api::xxh32(content, 0)
+}
+
+/// The streaming xxHash32 hasher.
+///
+/// The result equals [`xxh32`] over all the data passed to [`update`].
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::xxhash::{self, Xxh32};
+///
+/// let mut hasher = Xxh32::new(0);
+/// hasher.update(b"Hello ");
+/// hasher.update(b"world!");
+/// assert_eq!(hasher.digest(), xxhash::xxh32(b"Hello world!", 0));
+/// ```
+///
+/// [`xxh32`]: fn.xxh32.html
+/// [`update`]: #method.update
+#[derive(Clone)]
+pub struct Xxh32 {
+    state: Xxh32State,
+    seed: u32,
+}
+
+impl Xxh32 {
+    /// Creates a new `Xxh32` with `seed`.
+    pub fn new(seed: u32) -> Self {
+        Self {
+            state: Xxh32State::new(seed),
+            seed,
+        }
+    }
+
+    /// Discards the data passed so far, keeping the seed.
+    pub fn reset(&mut self) {
+        self.state.reset(self.seed);
+    }
+
+    /// Appends `data` to the hashed input.
+    pub fn update(&mut self, data: &[u8]) {
+        self.state.update(data);
+    }
+
+    /// Returns the hash of the data passed so far.
+    ///
+    /// The hasher stays usable, so more data can be appended afterwards.
+    pub fn digest(&self) -> u32 {
+        self.state.digest()
+    }
+}
+
+impl Default for Xxh32 {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl fmt::Debug for Xxh32 {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Xxh32").field("seed", &self.seed).finish()
+    }
+}
+
+impl Hasher for Xxh32 {
+    fn finish(&self) -> u64 {
+        u64::from(self.digest())
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+}
+
+/// The streaming xxHash64 hasher.
+///
+/// The result equals [`xxh64`] over all the data passed to [`update`].
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::xxhash::Xxh64;
+/// use std::{collections::HashMap, hash::BuildHasherDefault};
+///
+/// let mut map: HashMap<&str, i32, BuildHasherDefault<Xxh64>> = Default::default();
+/// map.insert("answer", 42);
+/// assert_eq!(map["answer"], 42);
+/// ```
+///
+/// [`xxh64`]: fn.xxh64.html
+/// [`update`]: #method.update
+#[derive(Clone)]
+pub struct Xxh64 {
+    state: Xxh64State,
+    seed: u64,
+}
+
+impl Xxh64 {
+    /// Creates a new `Xxh64` with `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: Xxh64State::new(seed),
+            seed,
+        }
+    }
+
+    /// Discards the data passed so far, keeping the seed.
+    pub fn reset(&mut self) {
+        self.state.reset(self.seed);
+    }
+
+    /// Appends `data` to the hashed input.
+    pub fn update(&mut self, data: &[u8]) {
+        self.state.update(data);
+    }
+
+    /// Returns the hash of the data passed so far.
+    ///
+    /// The hasher stays usable, so more data can be appended afterwards.
+    pub fn digest(&self) -> u64 {
+        self.state.digest()
+    }
+}
+
+impl Default for Xxh64 {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl fmt::Debug for Xxh64 {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Xxh64").field("seed", &self.seed).finish()
+    }
+}
+
+impl Hasher for Xxh64 {
+    fn finish(&self) -> u64 {
+        self.digest()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+}
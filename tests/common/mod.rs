@@ -2,9 +2,12 @@
 
 use bytes::Bytes;
 use lazy_static::lazy_static;
-use lzzzz::{lz4, lz4_hc, lz4f::*};
+use lzzzz::{lz4, lz4_hc, lz4f::*, xxhash};
 use rand::{distributions::Standard, rngs::SmallRng, Rng, SeedableRng};
-use std::{i32, u32};
+use std::{
+    cmp,
+    io::{self, Read, Write},
+};
 
 lazy_static! {
     static ref DATA_SET: Vec<Bytes> = {
@@ -69,9 +72,108 @@ fn preferences_set() -> impl Iterator<Item = Preferences> {
 }
 
 pub fn lz4f_test_set() -> impl Iterator<Item = (Bytes, Preferences)> {
+    generate_data().flat_map(|data| preferences_set().map(move |prefs| (data.clone(), prefs)))
+}
+
+/// Returns frames whose content or block checksum has been corrupted, with their original data.
+pub fn corrupted_checksum_set() -> impl Iterator<Item = (Bytes, Vec<u8>)> {
     generate_data()
-        .map(|data| preferences_set().map(move |prefs| (data.clone(), prefs)))
-        .flatten()
+        .filter(|data| data.len() <= 0x10000)
+        .flat_map(|data| {
+            let content = PreferencesBuilder::new()
+                .content_checksum(ContentChecksum::Enabled)
+                .build();
+            let block = PreferencesBuilder::new()
+                .block_checksum(BlockChecksum::Enabled)
+                .build();
+            // The content checksum is the last field of a frame,
+            // and the last block checksum precedes the 4-byte end mark.
+            [(content, 1), (block, 5)].map(|(prefs, offset)| {
+                let mut comp = Vec::new();
+                compress_to_vec(&data, &mut comp, &prefs).unwrap();
+                let len = comp.len();
+                comp[len - offset] ^= 0xff;
+                (data.clone(), comp)
+            })
+        })
+}
+
+/// A frame which fails a checksum, and the error it should fail with.
+pub struct ChecksumError {
+    pub frame: Vec<u8>,
+    pub kind: ErrorKind,
+    pub block_index: Option<u64>,
+    pub expected: u32,
+    pub actual: u32,
+}
+
+impl ChecksumError {
+    /// Checks that `err` reports this failure.
+    pub fn check(&self, err: &Error) {
+        assert_eq!(err.kind(), lzzzz::ErrorKind::Lz4f(self.kind));
+        assert_eq!(err.block_index(), self.block_index);
+        assert_eq!(err.expected_checksum(), Some(self.expected));
+        assert_eq!(err.actual_checksum(), Some(self.actual));
+    }
+}
+
+/// Returns frames of four blocks with block and content checksums, in which
+/// the checksum of the third block, the data of the third block or
+/// the content checksum has been corrupted.
+pub fn checksum_error_set() -> Vec<ChecksumError> {
+    let mut rng = SmallRng::seed_from_u64(0);
+    let src = (0..4 << 16)
+        .map(|_| rng.gen_range(b'a'..=b'h'))
+        .collect::<Vec<_>>();
+    let prefs = PreferencesBuilder::new()
+        .block_size(BlockSize::Max64KB)
+        .block_checksum(BlockChecksum::Enabled)
+        .content_checksum(ContentChecksum::Enabled)
+        .build();
+    let mut frame = Vec::new();
+    compress_to_vec(&src, &mut frame, &prefs).unwrap();
+
+    let read_u32 = |offset: usize| u32::from_le_bytes(frame[offset..][..4].try_into().unwrap());
+    let block_len = |offset: usize| (read_u32(offset) & 0x7fff_ffff) as usize;
+    // The header has neither a content size nor a dictionary ID.
+    let mut offset = 7;
+    for _ in 0..2 {
+        offset += 8 + block_len(offset);
+    }
+    let (data, len) = (offset + 4, block_len(offset));
+    let stored = read_u32(data + len);
+    let content_stored = read_u32(frame.len() - 4);
+
+    let corrupt = |offset: usize| {
+        let mut frame = frame.clone();
+        frame[offset] ^= 0xff;
+        frame
+    };
+    let corrupted_data = corrupt(data + len / 2);
+    let corrupted_checksum = xxhash::lz4f_block_checksum(&corrupted_data[data..][..len]);
+    vec![
+        ChecksumError {
+            frame: corrupt(data + len + 3),
+            kind: ErrorKind::BlockChecksumInvalid,
+            block_index: Some(2),
+            expected: stored ^ 0xff00_0000,
+            actual: stored,
+        },
+        ChecksumError {
+            frame: corrupted_data,
+            kind: ErrorKind::BlockChecksumInvalid,
+            block_index: Some(2),
+            expected: stored,
+            actual: corrupted_checksum,
+        },
+        ChecksumError {
+            frame: corrupt(frame.len() - 1),
+            kind: ErrorKind::ContentChecksumInvalid,
+            block_index: None,
+            expected: content_stored ^ 0xff00_0000,
+            actual: xxhash::lz4f_content_checksum(&src),
+        },
+    ]
 }
 
 fn compression_acc_set() -> impl Iterator<Item = i32> {
@@ -79,9 +181,7 @@ fn compression_acc_set() -> impl Iterator<Item = i32> {
 }
 
 pub fn lz4_test_set() -> impl Iterator<Item = (Bytes, i32)> {
-    generate_data()
-        .map(|data| compression_acc_set().map(move |acc| (data.clone(), acc)))
-        .flatten()
+    generate_data().flat_map(|data| compression_acc_set().map(move |acc| (data.clone(), acc)))
 }
 
 pub fn lz4_stream_test_set() -> impl Iterator<Item = (Vec<Bytes>, i32)> {
@@ -101,11 +201,162 @@ fn compression_level_set() -> impl Iterator<Item = i32> {
 }
 
 pub fn lz4_hc_test_set() -> impl Iterator<Item = (Bytes, i32)> {
-    generate_data()
-        .map(|data| compression_level_set().map(move |level| (data.clone(), level)))
-        .flatten()
+    generate_data().flat_map(|data| compression_level_set().map(move |level| (data.clone(), level)))
 }
 
 pub fn lz4_hc_stream_test_set() -> impl Iterator<Item = (Vec<Bytes>, i32)> {
     compression_level_set().map(|level| (generate_data().collect(), level))
 }
+
+/// Decides which calls to a flaky mock fail, counting the calls from zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Faults {
+    /// No call fails.
+    Never,
+    /// Every other call fails, starting with the first one.
+    Alternate,
+    /// The `len` calls from call `start` fail.
+    Run { start: usize, len: usize },
+}
+
+impl Faults {
+    fn fails(self, call: usize) -> bool {
+        match self {
+            Self::Never => false,
+            Self::Alternate => call % 2 == 0,
+            Self::Run { start, len } => (start..start + len).contains(&call),
+        }
+    }
+}
+
+/// Runs `f` with every schedule that fails call N, alone or with the two calls after it,
+/// for every N up to the number of calls `f` makes without faults.
+///
+/// `f` returns the number of calls made to the flaky mock.
+pub fn for_each_schedule<F>(f: F)
+where
+    F: Fn(Faults, io::ErrorKind) -> usize,
+{
+    let calls = f(Faults::Never, io::ErrorKind::WouldBlock);
+    for start in 0..calls {
+        for len in [1, 3] {
+            for err in TRANSIENT_ERRORS {
+                f(Faults::Run { start, len }, err);
+            }
+        }
+    }
+}
+
+/// Returns `err` on the calls chosen by [`Faults`] and accepts at most `chunk` bytes otherwise.
+pub struct FlakyWriter<W> {
+    pub inner: W,
+    pub err: Option<io::ErrorKind>,
+    faults: Faults,
+    chunk: usize,
+    calls: usize,
+}
+
+impl<W> FlakyWriter<W> {
+    /// Creates a writer which fails every other call.
+    pub fn new(inner: W, err: io::ErrorKind, chunk: usize) -> Self {
+        Self::with_faults(inner, err, chunk, Faults::Alternate)
+    }
+
+    pub fn with_faults(inner: W, err: io::ErrorKind, chunk: usize, faults: Faults) -> Self {
+        Self {
+            inner,
+            err: Some(err),
+            faults,
+            chunk,
+            calls: 0,
+        }
+    }
+
+    /// Returns the number of calls to `write` and `flush` so far.
+    pub fn calls(&self) -> usize {
+        self.calls
+    }
+
+    fn fail(&mut self) -> io::Result<()> {
+        let call = self.calls;
+        self.calls += 1;
+        match self.err {
+            Some(kind) if self.faults.fails(call) => Err(kind.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<W: Write> Write for FlakyWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.fail()?;
+        let len = cmp::min(buf.len(), self.chunk);
+        self.inner.write(&buf[..len])
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.fail()?;
+        self.inner.flush()
+    }
+}
+
+/// Returns `err` on the calls chosen by [`Faults`] and reads at most `chunk` bytes otherwise.
+pub struct FlakyReader<R> {
+    inner: R,
+    err: io::ErrorKind,
+    faults: Faults,
+    chunk: usize,
+    calls: usize,
+}
+
+impl<R> FlakyReader<R> {
+    /// Creates a reader which fails every other call.
+    pub fn new(inner: R, err: io::ErrorKind, chunk: usize) -> Self {
+        Self::with_faults(inner, err, chunk, Faults::Alternate)
+    }
+
+    pub fn with_faults(inner: R, err: io::ErrorKind, chunk: usize, faults: Faults) -> Self {
+        Self {
+            inner,
+            err,
+            faults,
+            chunk,
+            calls: 0,
+        }
+    }
+
+    /// Returns the number of calls to `read` so far.
+    pub fn calls(&self) -> usize {
+        self.calls
+    }
+}
+
+impl<R: Read> Read for FlakyReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let call = self.calls;
+        self.calls += 1;
+        if self.faults.fails(call) {
+            return Err(self.err.into());
+        }
+        let len = cmp::min(buf.len(), self.chunk);
+        self.inner.read(&mut buf[..len])
+    }
+}
+
+/// Retries `f` while it fails with `WouldBlock` or `Interrupted`.
+pub fn retry<T, F>(mut f: F) -> io::Result<T>
+where
+    F: FnMut() -> io::Result<T>,
+{
+    loop {
+        match f() {
+            Err(err)
+                if err.kind() == io::ErrorKind::WouldBlock
+                    || err.kind() == io::ErrorKind::Interrupted => {}
+            result => return result,
+        }
+    }
+}
+
+pub const TRANSIENT_ERRORS: [io::ErrorKind; 2] =
+    [io::ErrorKind::WouldBlock, io::ErrorKind::Interrupted];
@@ -1,4 +1,5 @@
-use lzzzz::lz4;
+use lzzzz::lz4::{self, BlockKind};
+use rand::{distributions::Standard, rngs::SmallRng, Rng, SeedableRng};
 use rayon::{iter::ParallelBridge, prelude::*};
 use std::cmp;
 
@@ -37,6 +38,76 @@ mod compress_to_vec {
     }
 }
 
+mod compress_or_store {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4_test_set().par_bridge().for_each(|(src, mode)| {
+            let mut comp_buf = vec![0; src.len()];
+            let mut decomp_buf = vec![0; src.len()];
+            let (len, kind) = lz4::compress_or_store(&src, &mut comp_buf, mode).unwrap();
+            if kind == BlockKind::Stored {
+                assert_eq!(&comp_buf[..len], &src[..]);
+            } else {
+                assert!(len < src.len());
+            }
+            let len = lz4::decompress_or_copy(&comp_buf[..len], &mut decomp_buf, kind).unwrap();
+            assert_eq!(&decomp_buf[..len], &src[..]);
+        });
+    }
+
+    #[test]
+    fn random() {
+        let src = SmallRng::seed_from_u64(0)
+            .sample_iter(Standard)
+            .take(1 << 16)
+            .collect::<Vec<u8>>();
+        let mut comp_buf = Vec::new();
+        let (len, kind) = lz4::compress_or_store_to_vec(&src, &mut comp_buf, 1).unwrap();
+        assert_eq!(kind, BlockKind::Stored);
+        assert_eq!(len, src.len());
+        assert_eq!(comp_buf, src);
+    }
+
+    #[test]
+    fn text() {
+        let src = b"The quick brown fox jumps over the lazy dog.".repeat(100);
+        let header = &b"HEADER"[..];
+        let mut comp_buf = Vec::from(header);
+        let mut decomp_buf = vec![0; src.len()];
+        let (len, kind) = lz4::compress_or_store_to_vec(&src, &mut comp_buf, 1).unwrap();
+        assert_eq!(kind, BlockKind::Compressed);
+        assert_eq!(comp_buf.len(), header.len() + len);
+        lz4::decompress_or_copy(&comp_buf[header.len()..], &mut decomp_buf, kind).unwrap();
+        assert_eq!(decomp_buf, src);
+    }
+
+    #[test]
+    fn small() {
+        let src = b"abc";
+        let mut comp_buf = [0; 3];
+        let (len, kind) = lz4::compress_or_store(src, &mut comp_buf, 1).unwrap();
+        assert_eq!(kind, BlockKind::Stored);
+        assert_eq!(&comp_buf[..len], src);
+    }
+
+    #[test]
+    fn empty() {
+        let (len, kind) = lz4::compress_or_store(&[], &mut [], 1).unwrap();
+        assert_eq!((len, kind), (0, BlockKind::Stored));
+        assert_eq!(lz4::decompress_or_copy(&[], &mut [], kind).unwrap(), 0);
+    }
+
+    #[test]
+    fn dst_too_small() {
+        let src = b"The quick brown fox jumps over the lazy dog.".repeat(100);
+        let mut comp_buf = vec![0; src.len() - 1];
+        assert!(lz4::compress_or_store(&src, &mut comp_buf, 1).is_err());
+        assert!(lz4::decompress_or_copy(&src, &mut comp_buf, BlockKind::Stored).is_err());
+    }
+}
+
 mod decompress {
     use super::*;
 
@@ -54,8 +125,7 @@ mod decompress {
     #[test]
     fn partial() {
         lz4_test_set()
-            .map(|(src, mode)| (0..20).map(move |n| (src.clone(), mode, 16 << n)))
-            .flatten()
+            .flat_map(|(src, mode)| (0..20).map(move |n| (src.clone(), mode, 16 << n)))
             .par_bridge()
             .for_each(|(src, mode, len)| {
                 let mut comp_buf = Vec::new();
@@ -78,3 +148,40 @@ mod decompress {
         });
     }
 }
+
+mod state {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4_test_set().par_bridge().for_each_init(
+            || lz4::State::new().unwrap(),
+            |state, (src, mode)| {
+                let mut comp_buf = vec![0; lz4::max_compressed_size(src.len())];
+                let mut decomp_buf = vec![0; src.len()];
+                let len = state.compress(&src, &mut comp_buf, mode).unwrap();
+                lz4::decompress(&comp_buf[..len], &mut decomp_buf).unwrap();
+                assert_eq!(decomp_buf, src);
+
+                let header = &b"HEADER"[..];
+                let mut comp_buf = Vec::from(header);
+                state.compress_to_vec(&src, &mut comp_buf, mode).unwrap();
+                lz4::decompress(&comp_buf[header.len()..], &mut decomp_buf).unwrap();
+                assert_eq!(decomp_buf, src);
+            },
+        );
+    }
+
+    #[test]
+    fn free_thread_local_states() {
+        let src = b"The quick brown fox jumps over the lazy dog.";
+        let mut decomp_buf = vec![0; src.len()];
+        for _ in 0..2 {
+            let mut comp_buf = Vec::new();
+            lz4::compress_to_vec(src, &mut comp_buf, lz4::ACC_LEVEL_DEFAULT).unwrap();
+            lz4::decompress(&comp_buf, &mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+            lzzzz::free_thread_local_states();
+        }
+    }
+}
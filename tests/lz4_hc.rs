@@ -26,8 +26,7 @@ mod compress_partial {
     #[test]
     fn default() {
         lz4_hc_test_set()
-            .map(|(src, level)| (0..20).map(move |n| (src.clone(), level, 16 << n)))
-            .flatten()
+            .flat_map(|(src, level)| (0..20).map(move |n| (src.clone(), level, 16 << n)))
             .par_bridge()
             .for_each(|(src, level, len)| {
                 let mut comp_buf = vec![0; len];
@@ -59,3 +58,60 @@ mod compress_to_vec {
         });
     }
 }
+
+mod compress_or_store {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4_hc_test_set().par_bridge().for_each(|(src, level)| {
+            let header = &b"HEADER"[..];
+            let mut comp_buf = Vec::from(header);
+            let mut decomp_buf = vec![0; src.len()];
+            let (len, kind) = lz4_hc::compress_or_store_to_vec(&src, &mut comp_buf, level).unwrap();
+            assert!(comp_buf.starts_with(header));
+            assert!(len <= src.len());
+            let len =
+                lz4::decompress_or_copy(&comp_buf[header.len()..], &mut decomp_buf, kind).unwrap();
+            assert_eq!(&decomp_buf[..len], &src[..]);
+        });
+    }
+
+    #[test]
+    fn text() {
+        let src = b"The quick brown fox jumps over the lazy dog.".repeat(100);
+        let mut comp_buf = vec![0; src.len()];
+        let (len, kind) =
+            lz4_hc::compress_or_store(&src, &mut comp_buf, lz4_hc::CLEVEL_DEFAULT).unwrap();
+        assert_eq!(kind, lz4::BlockKind::Compressed);
+        assert!(len < src.len());
+    }
+}
+
+mod state {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4_hc_test_set().par_bridge().for_each_init(
+            || lz4_hc::State::new().unwrap(),
+            |state, (src, level)| {
+                let header = &b"HEADER"[..];
+                let mut comp_buf = Vec::from(header);
+                let mut decomp_buf = vec![0; src.len()];
+                state.compress_to_vec(&src, &mut comp_buf, level).unwrap();
+                lz4::decompress(&comp_buf[header.len()..], &mut decomp_buf).unwrap();
+                assert_eq!(decomp_buf, src);
+
+                let mut comp_buf = vec![0; 1024];
+                let mut src = Cursor::new(src);
+                let len = state
+                    .compress_partial(&mut src, &mut comp_buf, level)
+                    .unwrap();
+                let mut decomp_buf = vec![0; src.position() as usize];
+                lz4::decompress(&comp_buf[..len], &mut decomp_buf).unwrap();
+                assert!(src.get_ref().starts_with(&decomp_buf));
+            },
+        );
+    }
+}
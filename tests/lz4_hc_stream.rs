@@ -2,11 +2,13 @@ use lzzzz::{lz4, lz4_hc};
 use rand::{distributions::Standard, rngs::SmallRng, Rng, SeedableRng};
 use rayon::{iter::ParallelBridge, prelude::*};
 use static_assertions::assert_impl_all;
+use std::io::prelude::*;
 
 mod common;
 use common::lz4_hc_stream_test_set;
 
 assert_impl_all!(lz4_hc::Compressor: Send);
+assert_impl_all!(lz4_hc::RingCompressor: Send);
 
 mod compressor {
     use super::*;
@@ -71,3 +73,173 @@ mod compressor {
             });
     }
 }
+
+mod ring_compressor {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4_hc_stream_test_set()
+            .par_bridge()
+            .for_each(|(src_set, level)| {
+                let max_len = src_set.iter().map(|src| src.len()).max().unwrap();
+                let mut comp = lz4_hc::RingCompressor::new(max_len).unwrap();
+                let mut decomp = lz4::Decompressor::new().unwrap();
+                comp.set_compression_level(level);
+                for src in src_set {
+                    let mut comp_buf = Vec::new();
+                    let len = comp.next_to_vec(&src, &mut comp_buf).unwrap();
+                    assert_eq!(len, comp_buf.len());
+                    assert_eq!(decomp.next(&comp_buf, src.len()).unwrap(), &src);
+                }
+            });
+    }
+
+    #[test]
+    fn wrap_around() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut ring = vec![0; 64 * 1024 + 1000];
+        let mut comp = lz4_hc::RingCompressor::with_buffer(&mut ring).unwrap();
+        let mut decomp = lz4::Decompressor::new().unwrap();
+        for _ in 0..1000 {
+            let len = rng.gen_range(1..=1000);
+            let src = b"The quick brown fox jumps over the lazy dog. "
+                .iter()
+                .cycle()
+                .skip(rng.gen_range(0..45))
+                .take(len)
+                .copied()
+                .collect::<Vec<_>>();
+            let mut comp_buf = vec![0; lz4::max_compressed_size(len)];
+            let len = comp
+                .next_with(src.len(), |slot| slot.copy_from_slice(&src), &mut comp_buf)
+                .unwrap();
+            assert_eq!(decomp.next(&comp_buf[..len], src.len()).unwrap(), &src);
+        }
+    }
+
+    #[test]
+    fn long_distance() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut src_set = (0..40)
+            .map(|_| (&mut rng).sample_iter(Standard).take(1000).collect())
+            .collect::<Vec<Vec<u8>>>();
+        src_set.push(src_set[0].clone());
+
+        let mut comp = lz4_hc::RingCompressor::new(1000).unwrap();
+        let mut decomp = lz4::Decompressor::new().unwrap();
+        let mut comp_buf = Vec::new();
+        for src in src_set {
+            comp_buf.clear();
+            comp.next_to_vec(&src, &mut comp_buf).unwrap();
+            assert_eq!(decomp.next(&comp_buf, src.len()).unwrap(), &src);
+        }
+        // The last message refers to the first one, 40 KB back.
+        assert!(comp_buf.len() < 100);
+    }
+}
+
+mod stream_writer {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4_hc_stream_test_set()
+            .par_bridge()
+            .for_each(|(src_set, level)| {
+                let mut w = lz4_hc::StreamWriter::new(Vec::new()).unwrap();
+                w.set_compression_level(level);
+                for src in &src_set {
+                    w.write_all(src).unwrap();
+                    w.flush().unwrap();
+                }
+                let comp = w.into_inner();
+
+                let mut r = lz4::StreamReader::new(comp.as_slice()).unwrap();
+                for src in &src_set {
+                    let mut buf = vec![0; src.len()];
+                    r.read_exact(&mut buf).unwrap();
+                    assert_eq!(buf, &src[..]);
+                }
+                assert_eq!(r.read(&mut [0; 1]).unwrap(), 0);
+            });
+    }
+
+    #[test]
+    fn dictionary() {
+        let dict = SmallRng::seed_from_u64(0)
+            .sample_iter(Standard)
+            .take(64 * 1024)
+            .collect::<Vec<_>>();
+        let src = &dict[32 * 1024..][..1000];
+        let mut w = lz4_hc::StreamWriter::with_dict(Vec::new(), &dict).unwrap();
+        w.write_all(src).unwrap();
+        let comp = w.into_inner();
+        assert!(comp.len() < 100);
+
+        let mut r = lz4::StreamReader::with_dict(comp.as_slice(), &dict).unwrap();
+        let mut buf = Vec::new();
+        r.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, src);
+    }
+}
+
+mod reset {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4_hc_stream_test_set()
+            .par_bridge()
+            .for_each(|(src_set, level)| {
+                let mut comp = lz4_hc::Compressor::new().unwrap();
+                let mut decomp = lz4::Decompressor::new().unwrap();
+                comp.set_compression_level(level);
+                comp.set_favor_dec_speed(lz4_hc::FavorDecSpeed::Enabled);
+                for src in &src_set {
+                    let mut comp_buf = Vec::new();
+                    comp.next_to_vec(src, &mut comp_buf).unwrap();
+                }
+                comp.reset();
+                for src in &src_set {
+                    let mut comp_buf = Vec::new();
+                    comp.next_to_vec(src, &mut comp_buf).unwrap();
+                    assert_eq!(decomp.next(&comp_buf, src.len()).unwrap(), &src[..]);
+                }
+            });
+    }
+
+    #[test]
+    fn keeps_compression_level() {
+        let src = b"The quick brown fox jumps over the lazy dog. ".repeat(1000);
+        let mut expected = Vec::new();
+        lz4_hc::compress_to_vec(&src, &mut expected, lz4_hc::CLEVEL_MAX).unwrap();
+
+        let mut comp = lz4_hc::Compressor::new().unwrap();
+        comp.set_compression_level(lz4_hc::CLEVEL_MAX);
+        for _ in 0..3 {
+            comp.reset();
+            let mut comp_buf = Vec::new();
+            comp.next_to_vec(&src, &mut comp_buf).unwrap();
+            assert_eq!(comp_buf, expected);
+        }
+    }
+
+    #[test]
+    fn dictionary() {
+        let dict = SmallRng::seed_from_u64(0)
+            .sample_iter(Standard)
+            .take(64 * 1024)
+            .collect::<Vec<_>>();
+        let src = dict[32 * 1024..][..1000].to_vec();
+        let mut comp = lz4_hc::Compressor::new().unwrap();
+        for _ in 0..3 {
+            comp.reset_with_dict(&dict);
+            let mut comp_buf = Vec::new();
+            comp.next_to_vec(&src, &mut comp_buf).unwrap();
+            assert!(comp_buf.len() < 100);
+            let mut decomp = lz4::Decompressor::with_dict(&dict).unwrap();
+            assert_eq!(decomp.next(&comp_buf, src.len()).unwrap(), &src[..]);
+        }
+    }
+}
@@ -2,12 +2,15 @@ use lzzzz::lz4;
 use rand::{distributions::Standard, rngs::SmallRng, Rng, SeedableRng};
 use rayon::{iter::ParallelBridge, prelude::*};
 use static_assertions::assert_impl_all;
+use std::io::{self, prelude::*};
 
 mod common;
-use common::lz4_stream_test_set;
+use common::{lz4_stream_test_set, retry, FlakyReader, FlakyWriter};
 
 assert_impl_all!(lz4::Compressor: Send);
 assert_impl_all!(lz4::Decompressor: Send);
+assert_impl_all!(lz4::RingCompressor: Send);
+assert_impl_all!(lz4::RingDecompressor: Send);
 
 mod compressor {
     use super::*;
@@ -27,6 +30,30 @@ mod compressor {
             });
     }
 
+    #[test]
+    fn slices() {
+        lz4_stream_test_set()
+            .par_bridge()
+            .for_each(|(src_set, mode)| {
+                let mut comp = lz4::Compressor::new().unwrap();
+                let mut decomp = lz4::Decompressor::new().unwrap();
+                for src in src_set.chunks(3) {
+                    let src = src.iter().map(|src| &src[..]).collect::<Vec<_>>();
+                    let len = src
+                        .iter()
+                        .map(|src| lz4::max_compressed_size(src.len()))
+                        .sum();
+                    let mut comp_buf = vec![0; len];
+                    let lens = comp.next_slices(&src, &mut comp_buf, mode).unwrap();
+                    let mut comp_buf = &comp_buf[..];
+                    for (src, len) in src.iter().zip(lens) {
+                        assert_eq!(decomp.next(&comp_buf[..len], src.len()).unwrap(), *src);
+                        comp_buf = &comp_buf[len..];
+                    }
+                }
+            });
+    }
+
     #[test]
     fn dictionary() {
         lz4_stream_test_set()
@@ -46,3 +73,391 @@ mod compressor {
             });
     }
 }
+
+mod ring_compressor {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4_stream_test_set()
+            .par_bridge()
+            .for_each(|(src_set, mode)| {
+                let max_len = src_set.iter().map(|src| src.len()).max().unwrap();
+                let mut comp = lz4::RingCompressor::new(max_len).unwrap();
+                let mut decomp = lz4::Decompressor::new().unwrap();
+                for src in src_set {
+                    let mut comp_buf = Vec::new();
+                    let len = comp.next_to_vec(&src, &mut comp_buf, mode).unwrap();
+                    assert_eq!(len, comp_buf.len());
+                    assert_eq!(decomp.next(&comp_buf, src.len()).unwrap(), &src);
+                }
+            });
+    }
+
+    #[test]
+    fn wrap_around() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut ring = vec![0; 64 * 1024 + 1000];
+        let mut comp = lz4::RingCompressor::with_buffer(&mut ring).unwrap();
+        let mut decomp = lz4::Decompressor::new().unwrap();
+        assert_eq!(comp.max_message_size(), 1000);
+        for _ in 0..1000 {
+            let len = rng.gen_range(0..=1000);
+            let src = b"The quick brown fox jumps over the lazy dog. "
+                .iter()
+                .cycle()
+                .skip(rng.gen_range(0..45))
+                .take(len)
+                .copied()
+                .collect::<Vec<_>>();
+            let mut comp_buf = vec![0; lz4::max_compressed_size(len)];
+            let len = comp
+                .next_with(
+                    src.len(),
+                    |slot| slot.copy_from_slice(&src),
+                    &mut comp_buf,
+                    1,
+                )
+                .unwrap();
+            assert_eq!(decomp.next(&comp_buf[..len], src.len()).unwrap(), &src);
+        }
+    }
+
+    #[test]
+    fn too_large() {
+        let mut comp = lz4::RingCompressor::new(16).unwrap();
+        let mut comp_buf = Vec::new();
+        let err = comp.next_to_vec(&[0; 17], &mut comp_buf, 1).unwrap_err();
+        assert_eq!(err.kind(), lzzzz::ErrorKind::CompressionFailed);
+        assert!(comp_buf.is_empty());
+
+        let mut ring = vec![0; 64 * 1024];
+        assert!(lz4::RingCompressor::with_buffer(&mut ring).is_err());
+    }
+}
+
+mod ring_decompressor {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4_stream_test_set()
+            .par_bridge()
+            .for_each(|(src_set, mode)| {
+                let max_len = src_set.iter().map(|src| src.len()).max().unwrap();
+                let mut comp = lz4::Compressor::new().unwrap();
+                let mut decomp = lz4::RingDecompressor::new(max_len).unwrap();
+                for src in src_set {
+                    let mut comp_buf = Vec::new();
+                    comp.next_to_vec(&src, &mut comp_buf, mode).unwrap();
+                    assert_eq!(decomp.next(&comp_buf, src.len()).unwrap(), &src);
+                }
+            });
+    }
+
+    #[test]
+    fn wrap_around() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut comp_ring = vec![0; 64 * 1024 + 1000];
+        let mut decomp_ring = vec![0; 64 * 1024 + 14 + 1000];
+        let mut comp = lz4::RingCompressor::with_buffer(&mut comp_ring).unwrap();
+        let mut decomp = lz4::RingDecompressor::with_buffer(&mut decomp_ring).unwrap();
+        let mut history = Vec::new();
+        for _ in 0..1000 {
+            let len = rng.gen_range(0..=1000);
+            let src = if rng.gen_bool(0.5) && history.len() >= 64 * 1024 {
+                // Repeat a part of the last 64 KiB to produce long-distance matches.
+                let start = history.len() - rng.gen_range(len..=64 * 1024);
+                history[start..][..len].to_vec()
+            } else {
+                (&mut rng).sample_iter(Standard).take(len).collect()
+            };
+            let mut comp_buf = Vec::new();
+            comp.next_to_vec(&src, &mut comp_buf, 1).unwrap();
+            assert_eq!(decomp.next(&comp_buf, src.len()).unwrap(), &src);
+            history.extend_from_slice(&src);
+        }
+    }
+
+    #[test]
+    fn too_large() {
+        let mut comp_buf = Vec::new();
+        lz4::compress_to_vec(&[0; 17], &mut comp_buf, 1).unwrap();
+
+        let mut decomp = lz4::RingDecompressor::new(16).unwrap();
+        let err = decomp.next(&comp_buf, 17).unwrap_err();
+        assert_eq!(err.kind(), lzzzz::ErrorKind::DecompressionFailed);
+        assert!(decomp.next(&comp_buf, 16).is_err());
+
+        let mut decomp = lz4::RingDecompressor::new(17).unwrap();
+        assert_eq!(decomp.next(&comp_buf, 17).unwrap(), &[0; 17][..]);
+
+        let mut ring = vec![0; 64 * 1024 + 14];
+        assert!(lz4::RingDecompressor::with_buffer(&mut ring).is_err());
+        let mut ring = vec![0; 64 * 1024 + 15];
+        let decomp = lz4::RingDecompressor::with_buffer(&mut ring).unwrap();
+        assert_eq!(decomp.max_message_size(), 1);
+    }
+
+    #[test]
+    fn long_distance_after_wrap() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        // The decompressor wraps around before the last block for any history
+        // longer than the ring buffer minus 1000 bytes.
+        for history_len in 64 * 1024..64 * 1024 + 40 {
+            let history = (&mut rng)
+                .sample_iter(Standard)
+                .take(history_len)
+                .collect::<Vec<u8>>();
+            let mut comp = lz4::Compressor::new().unwrap();
+            let mut decomp = lz4::RingDecompressor::new(1000).unwrap();
+            for src in history.chunks(1000) {
+                let mut comp_buf = Vec::new();
+                comp.next_to_vec(src, &mut comp_buf, 1).unwrap();
+                assert_eq!(decomp.next(&comp_buf, src.len()).unwrap(), src);
+            }
+
+            // 20 literals, a match of 968 bytes 65535 bytes back, and 12 literals.
+            let literals = (&mut rng)
+                .sample_iter(Standard)
+                .take(32)
+                .collect::<Vec<u8>>();
+            let mut block = vec![0xff, 20 - 15];
+            block.extend_from_slice(&literals[..20]);
+            block.extend_from_slice(&[0xff, 0xff, 255, 255, 255, (968 - 4 - 15 - 255 * 3) as u8]);
+            block.push(12 << 4);
+            block.extend_from_slice(&literals[20..]);
+
+            let mut src = literals[..20].to_vec();
+            src.extend_from_slice(&history[history_len + 20 - 65535..][..968]);
+            src.extend_from_slice(&literals[20..]);
+            assert_eq!(decomp.next(&block, src.len()).unwrap(), &src);
+        }
+    }
+}
+
+mod stream_writer {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4_stream_test_set()
+            .par_bridge()
+            .for_each(|(src_set, mode)| {
+                let mut w = lz4::StreamWriter::new(Vec::new()).unwrap();
+                w.set_acceleration(mode);
+                for src in &src_set {
+                    w.write_all(src).unwrap();
+                    w.flush().unwrap();
+                }
+                let comp = w.into_inner();
+
+                let mut r = lz4::StreamReader::new(comp.as_slice()).unwrap();
+                for src in &src_set {
+                    let mut buf = vec![0; src.len()];
+                    r.read_exact(&mut buf).unwrap();
+                    assert_eq!(buf, &src[..]);
+                }
+                assert_eq!(r.read(&mut [0; 1]).unwrap(), 0);
+            });
+    }
+
+    #[test]
+    fn dictionary() {
+        let dict = SmallRng::seed_from_u64(0)
+            .sample_iter(Standard)
+            .take(64 * 1024)
+            .collect::<Vec<_>>();
+        let src = &dict[32 * 1024..][..1000];
+        let mut w = lz4::StreamWriter::with_dict(Vec::new(), &dict).unwrap();
+        w.write_all(src).unwrap();
+        let comp = w.into_inner();
+        assert!(comp.len() < 100);
+
+        let mut r = lz4::StreamReader::with_dict(comp.as_slice(), &dict).unwrap();
+        let mut buf = Vec::new();
+        r.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, src);
+    }
+
+    #[test]
+    fn dynamic_acceleration() {
+        let src = b"The quick brown fox jumps over the lazy dog. ".repeat(10000);
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut w = lz4::StreamWriter::new(Vec::new()).unwrap();
+        for chunk in src.chunks(1000) {
+            w.set_acceleration(rng.gen_range(1..=64));
+            w.write_all(chunk).unwrap();
+            w.flush().unwrap();
+        }
+        let comp = w.into_inner();
+
+        let mut buf = Vec::new();
+        lz4::StreamReader::new(comp.as_slice())
+            .unwrap()
+            .read_to_end(&mut buf)
+            .unwrap();
+        assert_eq!(buf, src);
+    }
+
+    #[test]
+    fn flaky() {
+        let src = b"The quick brown fox jumps over the lazy dog. ".repeat(5000);
+        let mut w =
+            lz4::StreamWriter::new(FlakyWriter::new(Vec::new(), io::ErrorKind::WouldBlock, 7))
+                .unwrap();
+        for chunk in src.chunks(1000) {
+            let mut chunk = chunk;
+            while !chunk.is_empty() {
+                let len = retry(|| w.write(chunk)).unwrap();
+                chunk = &chunk[len..];
+            }
+            retry(|| w.flush()).unwrap();
+        }
+        let comp = w.into_inner().inner;
+
+        let mut r = lz4::StreamReader::new(FlakyReader::new(
+            comp.as_slice(),
+            io::ErrorKind::Interrupted,
+            5,
+        ))
+        .unwrap();
+        let mut buf = Vec::new();
+        let mut chunk = [0; 100];
+        loop {
+            match retry(|| r.read(&mut chunk)).unwrap() {
+                0 => break,
+                len => buf.extend_from_slice(&chunk[..len]),
+            }
+        }
+        assert_eq!(buf, src);
+    }
+
+    #[test]
+    fn truncated() {
+        let mut w = lz4::StreamWriter::new(Vec::new()).unwrap();
+        w.write_all(b"Hello world!").unwrap();
+        let comp = w.into_inner();
+
+        for len in 1..comp.len() {
+            let mut buf = Vec::new();
+            let err = lz4::StreamReader::new(&comp[..len])
+                .unwrap()
+                .read_to_end(&mut buf)
+                .unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        }
+    }
+
+    #[test]
+    fn invalid_header() {
+        let mut buf = Vec::new();
+        let err = lz4::StreamReader::new(&[0xff; 16][..])
+            .unwrap()
+            .read_to_end(&mut buf)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn corrupted_block() {
+        let mut w = lz4::StreamWriter::new(Vec::new()).unwrap();
+        w.write_all(b"Hello world!").unwrap();
+        w.flush().unwrap();
+        let len = w.get_ref().len();
+        w.write_all(b"Hello again!").unwrap();
+        let mut comp = w.into_inner();
+        for b in &mut comp[len + 8..] {
+            *b = 0xff;
+        }
+
+        let mut r = lz4::StreamReader::new(comp.as_slice()).unwrap();
+        let mut buf = [0; 12];
+        r.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"Hello world!");
+        for _ in 0..2 {
+            let err = r.read(&mut buf).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn mismatched_length() {
+        let src = b"The quick brown fox jumps over the lazy dog. ".repeat(30);
+        let mut w = lz4::StreamWriter::new(Vec::new()).unwrap();
+        w.write_all(&src[..1000]).unwrap();
+        let mut comp = w.into_inner();
+
+        // A valid block whose header announces more data than it holds.
+        let mut block = Vec::new();
+        lz4::compress_to_vec(b"hello", &mut block, lz4::ACC_LEVEL_DEFAULT).unwrap();
+        comp.extend_from_slice(&60000u32.to_le_bytes());
+        comp.extend_from_slice(&(block.len() as u32).to_le_bytes());
+        comp.extend_from_slice(&block);
+
+        let mut r = lz4::StreamReader::new(comp.as_slice()).unwrap();
+        let mut buf = [0; 1000];
+        r.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf[..], &src[..1000]);
+        for _ in 0..2 {
+            let err = r.read(&mut buf).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
+
+mod reset {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4_stream_test_set()
+            .par_bridge()
+            .for_each(|(src_set, mode)| {
+                let mut comp = lz4::Compressor::new().unwrap();
+                let mut decomp = lz4::Decompressor::new().unwrap();
+                for src in &src_set {
+                    let mut comp_buf = Vec::new();
+                    comp.next_to_vec(src, &mut comp_buf, mode).unwrap();
+                    decomp.next(&comp_buf, src.len()).unwrap();
+                }
+                comp.reset();
+                decomp.reset().unwrap();
+                for src in &src_set {
+                    let mut comp_buf = Vec::new();
+                    let mut expected = Vec::new();
+                    comp.next_to_vec(src, &mut comp_buf, mode).unwrap();
+                    if src == &src_set[0] {
+                        // A new stream starts without history.
+                        lz4::compress_to_vec(src, &mut expected, mode).unwrap();
+                        assert_eq!(comp_buf, expected);
+                    }
+                    assert_eq!(decomp.next(&comp_buf, src.len()).unwrap(), &src[..]);
+                }
+            });
+    }
+
+    #[test]
+    fn dictionary() {
+        let dict = SmallRng::seed_from_u64(0)
+            .sample_iter(Standard)
+            .take(64 * 1024)
+            .collect::<Vec<_>>();
+        let src = dict[32 * 1024..][..1000].to_vec();
+        let mut comp = lz4::Compressor::new().unwrap();
+        let mut decomp = lz4::Decompressor::new().unwrap();
+        for _ in 0..3 {
+            comp.reset_with_dict(&dict);
+            decomp.reset_with_dict(&dict).unwrap();
+            let mut comp_buf = Vec::new();
+            comp.next_to_vec(&src, &mut comp_buf, 1).unwrap();
+            assert!(comp_buf.len() < 100);
+            assert_eq!(decomp.next(&comp_buf, src.len()).unwrap(), &src[..]);
+        }
+
+        comp.reset();
+        let mut comp_buf = Vec::new();
+        comp.next_to_vec(&src, &mut comp_buf, 1).unwrap();
+        assert!(comp_buf.len() > src.len());
+    }
+}
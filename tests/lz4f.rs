@@ -1,8 +1,54 @@
 use lzzzz::{lz4f, lz4f::*};
 use rayon::{iter::ParallelBridge, prelude::*};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
 
 mod common;
-use common::lz4f_test_set;
+use common::{checksum_error_set, lz4f_test_set};
+
+/// Counts the allocations of each thread, so that tests can check how a buffer grew
+/// without relying on the capacity the allocator hands out.
+struct CountingAlloc;
+
+thread_local! {
+    /// The number of allocations and the largest size requested.
+    static ALLOCATIONS: Cell<(usize, usize)> = const { Cell::new((0, 0)) };
+}
+
+fn record(size: usize) {
+    let _ = ALLOCATIONS.try_with(|cell| {
+        let (count, largest) = cell.get();
+        cell.set((count + 1, largest.max(size)));
+    });
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record(new_size);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Runs `f` and returns the number of allocations it made and the largest size requested.
+fn allocations<F: FnOnce()>(f: F) -> (usize, usize) {
+    ALLOCATIONS.with(|cell| cell.set((0, 0)));
+    f();
+    ALLOCATIONS.with(Cell::get)
+}
 
 mod compress_to_vec {
     use super::*;
@@ -51,8 +97,8 @@ mod compress {
         lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
             let mut comp_buf = Vec::new();
             assert_eq!(
-                lz4f::compress(&src, &mut comp_buf, &prefs),
-                Err(Error::Lz4f(ErrorKind::DstMaxSizeTooSmall))
+                lz4f::compress(&src, &mut comp_buf, &prefs).map_err(Error::kind),
+                Err(lzzzz::ErrorKind::Lz4f(ErrorKind::DstMaxSizeTooSmall))
             );
         });
     }
@@ -79,6 +125,100 @@ mod compress {
     }
 }
 
+mod compress_slices {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let (header, rest) = src.split_at(src.len() / 3);
+            let (body, trailer) = rest.split_at(rest.len() / 2);
+            let mut comp_buf = vec![0; lz4f::max_compressed_size(src.len(), &prefs)];
+            let mut decomp_buf = Vec::new();
+
+            let len =
+                lz4f::compress_slices(&[header, body, trailer], &mut comp_buf, &prefs).unwrap();
+            comp_buf.resize_with(len, Default::default);
+            assert_eq!(
+                lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap(),
+                decomp_buf.len()
+            );
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
+    #[test]
+    fn content_size() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let prefs = PreferencesBuilder::from(prefs).content_size(1).build();
+            let (header, body) = src.split_at(src.len() / 2);
+            let mut comp_buf = vec![0; lz4f::max_compressed_size(src.len(), &prefs)];
+
+            let len = lz4f::compress_slices(&[header, body], &mut comp_buf, &prefs).unwrap();
+            let mut comp_buf = &comp_buf[..len];
+            let mut r = ReadDecompressor::new(&mut comp_buf).unwrap();
+            assert_eq!(r.read_frame_info().unwrap().content_size(), src.len());
+        });
+    }
+
+    #[test]
+    fn too_small_dst() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            assert_eq!(
+                lz4f::compress_slices(&[&src], &mut comp_buf, &prefs).map_err(Error::kind),
+                Err(lzzzz::ErrorKind::Lz4f(ErrorKind::DstMaxSizeTooSmall))
+            );
+        });
+    }
+}
+
+mod content_checksum {
+    use super::*;
+    use lzzzz::xxhash;
+
+    #[test]
+    fn default() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let prefs = PreferencesBuilder::from(prefs)
+                .content_checksum(ContentChecksum::Disabled)
+                .build();
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            assert_eq!(lz4f::content_checksum(&comp_buf), None);
+
+            let prefs = PreferencesBuilder::from(prefs)
+                .content_checksum(ContentChecksum::Enabled)
+                .build();
+            comp_buf.clear();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            assert_eq!(
+                lz4f::content_checksum(&comp_buf),
+                Some(xxhash::lz4f_content_checksum(&src))
+            );
+            assert_eq!(
+                lz4f::content_checksum(&comp_buf[..comp_buf.len() - 1]),
+                None
+            );
+        });
+    }
+
+    #[test]
+    fn compress_with_checksum() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = vec![0; lz4f::max_compressed_size(src.len(), &prefs)];
+            let (len, checksum) =
+                lz4f::compress_with_checksum(&src, &mut comp_buf, &prefs).unwrap();
+            assert_eq!(checksum, lz4f::content_checksum(&comp_buf[..len]));
+            if prefs.frame_info().content_checksum() == ContentChecksum::Enabled {
+                assert_eq!(checksum, Some(xxhash::lz4f_content_checksum(&src)));
+            } else {
+                assert_eq!(checksum, None);
+            }
+        });
+    }
+}
+
 mod decompress_to_vec {
     use super::*;
 
@@ -93,8 +233,8 @@ mod decompress_to_vec {
                 comp_buf.len() - header.len()
             );
             assert_eq!(
-                lz4f::decompress_to_vec(&comp_buf[1..], &mut decomp_buf),
-                Err(Error::Lz4f(ErrorKind::FrameTypeUnknown))
+                lz4f::decompress_to_vec(&comp_buf[1..], &mut decomp_buf).map_err(Error::kind),
+                Err(lzzzz::ErrorKind::Lz4f(ErrorKind::FrameTypeUnknown))
             );
             assert_eq!(decomp_buf, header);
         });
@@ -111,10 +251,461 @@ mod decompress_to_vec {
                 comp_buf.len()
             );
             assert_eq!(
-                lz4f::decompress_to_vec(&comp_buf[..comp_buf.len() - 1], &mut decomp_buf),
-                Err(Error::Common(lzzzz::ErrorKind::CompressedDataIncomplete))
+                lz4f::decompress_to_vec(&comp_buf[..comp_buf.len() - 1], &mut decomp_buf)
+                    .map_err(Error::kind),
+                Err(lzzzz::ErrorKind::CompressedDataIncomplete)
             );
             assert_eq!(decomp_buf, header);
         });
     }
+
+    #[test]
+    fn checksum_context() {
+        for case in checksum_error_set() {
+            let err = lz4f::decompress_to_vec(&case.frame, &mut Vec::new()).unwrap_err();
+            case.check(&err);
+            assert_eq!(err.frame_index(), Some(0));
+        }
+    }
+
+    #[test]
+    fn error_context() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            let mut decomp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            let len = comp_buf.len() - 1;
+            let err = lz4f::decompress_to_vec(&comp_buf[..len], &mut decomp_buf).unwrap_err();
+            assert_eq!(err.frame_index(), Some(0));
+            assert!(err.input_offset().unwrap() <= len as u64);
+            assert!(err.output_offset().unwrap() <= src.len() as u64);
+            assert_eq!(
+                std::io::Error::from(err).kind(),
+                std::io::ErrorKind::UnexpectedEof
+            );
+        });
+    }
+
+    #[test]
+    fn incomplete_header() {
+        let mut comp_buf = Vec::new();
+        lz4f::compress_to_vec(b"HEADER", &mut comp_buf, &Default::default()).unwrap();
+        let mut decomp_buf = Vec::new();
+        assert_eq!(
+            lz4f::decompress_to_vec(&comp_buf[..5], &mut decomp_buf).map_err(Error::kind),
+            Err(lzzzz::ErrorKind::CompressedDataIncomplete)
+        );
+        assert!(decomp_buf.is_empty());
+    }
+
+    #[test]
+    fn content_size() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let prefs = PreferencesBuilder::from(prefs).content_size(1).build();
+            let mut comp_buf = Vec::new();
+            let mut decomp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            // Set up the decompression state of this thread.
+            lz4f::decompress_to_vec(&comp_buf, &mut Vec::new()).unwrap();
+            // The buffer is reserved once from the header and never grows.
+            let (count, _) = allocations(|| {
+                lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+            });
+            assert_eq!(decomp_buf, src);
+            assert_eq!(count, 1);
+        });
+    }
+}
+
+mod decompress_to_vec_skip_checksums {
+    use super::*;
+    use common::corrupted_checksum_set;
+
+    #[test]
+    fn default() {
+        corrupted_checksum_set()
+            .par_bridge()
+            .for_each(|(src, comp_buf)| {
+                let mut decomp_buf = Vec::new();
+                assert!(matches!(
+                    lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).map_err(Error::kind),
+                    Err(lzzzz::ErrorKind::Lz4f(
+                        ErrorKind::ContentChecksumInvalid | ErrorKind::BlockChecksumInvalid
+                    ))
+                ));
+                assert!(decomp_buf.is_empty());
+                assert_eq!(
+                    lz4f::decompress_to_vec_skip_checksums(&comp_buf, &mut decomp_buf).unwrap(),
+                    src.len()
+                );
+                assert_eq!(decomp_buf, src);
+            });
+    }
+}
+
+mod decompress_to_vec_with_limits {
+    use super::*;
+
+    const LIMIT_EXCEEDED: lzzzz::ErrorKind = lzzzz::ErrorKind::DecompressionLimitExceeded;
+
+    #[test]
+    fn max_output_size() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            let mut decomp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            lz4f::decompress_to_vec(&comp_buf, &mut Vec::new()).unwrap();
+
+            let limits = DecompressionLimitsBuilder::new()
+                .max_output_size(src.len() - 1)
+                .build();
+            let (_, largest) = allocations(|| {
+                assert_eq!(
+                    lz4f::decompress_to_vec_with_limits(&comp_buf, &mut decomp_buf, &limits)
+                        .map_err(Error::kind),
+                    Err(LIMIT_EXCEEDED)
+                );
+            });
+            assert!(decomp_buf.is_empty());
+            assert!(largest < src.len());
+
+            let limits = DecompressionLimitsBuilder::new()
+                .max_output_size(src.len())
+                .build();
+            lz4f::decompress_to_vec_with_limits(&comp_buf, &mut decomp_buf, &limits).unwrap();
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
+    #[test]
+    fn max_ratio() {
+        let src = vec![0; 1 << 20];
+        let mut comp_buf = Vec::new();
+        let mut decomp_buf = Vec::new();
+        lz4f::compress_to_vec(&src, &mut comp_buf, &Default::default()).unwrap();
+
+        let limits = DecompressionLimitsBuilder::new().max_ratio(100).build();
+        assert_eq!(
+            lz4f::decompress_to_vec_with_limits(&comp_buf, &mut decomp_buf, &limits)
+                .map_err(Error::kind),
+            Err(LIMIT_EXCEEDED)
+        );
+
+        let limits = DecompressionLimitsBuilder::new().max_ratio(1000).build();
+        lz4f::decompress_to_vec_with_limits(&comp_buf, &mut decomp_buf, &limits).unwrap();
+        assert_eq!(decomp_buf, src);
+    }
+
+    #[test]
+    fn max_block_size() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            let mut decomp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            let (frame_info, _) = FrameDecoder::new().unwrap().frame_info(&comp_buf).unwrap();
+            let limits = DecompressionLimitsBuilder::new()
+                .max_block_size(BlockSize::Max64KB)
+                .build();
+            let result = lz4f::decompress_to_vec_with_limits(&comp_buf, &mut decomp_buf, &limits);
+            match frame_info.block_size() {
+                BlockSize::Default | BlockSize::Max64KB => assert_eq!(decomp_buf, src),
+                _ => assert_eq!(result.map_err(Error::kind), Err(LIMIT_EXCEEDED)),
+            }
+        });
+    }
+
+    #[test]
+    fn reject_content_size() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let prefs = PreferencesBuilder::from(prefs).content_size(1).build();
+            let mut comp_buf = Vec::new();
+            let mut decomp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            lz4f::decompress_to_vec(&comp_buf, &mut Vec::new()).unwrap();
+
+            let limits = DecompressionLimitsBuilder::new()
+                .max_output_size(src.len() - 1)
+                .reject_content_size(true)
+                .build();
+            let (count, _) = allocations(|| {
+                assert_eq!(
+                    lz4f::decompress_to_vec_with_limits(&comp_buf, &mut decomp_buf, &limits)
+                        .map_err(Error::kind),
+                    Err(LIMIT_EXCEEDED)
+                );
+            });
+            assert_eq!(count, 0);
+        });
+    }
+}
+
+mod frame_encoder {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut enc = FrameEncoder::new(prefs).unwrap();
+            let mut comp_buf = vec![0; 32];
+            let mut len = enc.begin(&mut comp_buf).unwrap();
+            for (i, chunk) in src.chunks(4000).enumerate() {
+                comp_buf.resize(len + enc.compress_bound(chunk.len()), 0);
+                len += if i % 2 == 0 {
+                    enc.update(chunk, &mut comp_buf[len..]).unwrap()
+                } else {
+                    enc.update_stable(chunk, &mut comp_buf[len..]).unwrap()
+                };
+            }
+            comp_buf.resize(len + enc.compress_bound(0), 0);
+            len += enc.end(&mut comp_buf[len..]).unwrap();
+
+            let mut decomp_buf = Vec::new();
+            lz4f::decompress_to_vec(&comp_buf[..len], &mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
+    #[test]
+    fn too_small_dst() {
+        let mut enc = FrameEncoder::new(Default::default()).unwrap();
+        assert_eq!(
+            enc.begin(&mut [0; 4]).map_err(Error::kind),
+            Err(lzzzz::ErrorKind::Lz4f(ErrorKind::DstMaxSizeTooSmall))
+        );
+    }
+}
+
+mod frame_decoder {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            let mut dec = FrameDecoder::new().unwrap();
+            let (frame_info, mut offset) = dec.frame_info(&comp_buf).unwrap();
+            assert_eq!(
+                frame_info.content_checksum(),
+                prefs.frame_info().content_checksum()
+            );
+
+            let mut decomp_buf = Vec::new();
+            let mut chunk = [0; 1000];
+            loop {
+                let end = std::cmp::min(comp_buf.len(), offset + 7);
+                let progress = dec.decode(&comp_buf[offset..end], &mut chunk).unwrap();
+                offset += progress.consumed();
+                decomp_buf.extend_from_slice(&chunk[..progress.produced()]);
+                if progress.is_frame_end() {
+                    break;
+                }
+            }
+            assert_eq!(offset, comp_buf.len());
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
+    #[test]
+    fn stable() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            let mut decomp_buf = vec![0; src.len()];
+            let mut decoded = 0;
+            {
+                let mut dec = FrameDecoder::new().unwrap();
+                let mut dst = &mut decomp_buf[..];
+                for chunk in comp_buf.chunks(1000) {
+                    let (progress, _, rest) = dec.decode_stable(chunk, dst).unwrap();
+                    assert_eq!(progress.consumed(), chunk.len());
+                    decoded += progress.produced();
+                    dst = rest;
+                }
+            }
+            assert_eq!(decoded, src.len());
+            assert_eq!(decomp_buf, src);
+        });
+    }
+}
+
+mod context_pool {
+    use super::*;
+    use static_assertions::assert_impl_all;
+
+    assert_impl_all!(ContextPool: Send, Sync);
+
+    #[test]
+    fn default() {
+        let pool = ContextPool::new(4);
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let header = &b"HEADER"[..];
+            let mut comp_buf = Vec::from(header);
+            let mut decomp_buf = Vec::from(header);
+            let len = pool.compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            assert_eq!(len, comp_buf.len() - header.len());
+            pool.decompress_to_vec(&comp_buf[header.len()..], &mut decomp_buf)
+                .unwrap();
+            assert_eq!(&decomp_buf[header.len()..], &src[..]);
+        });
+    }
+
+    #[test]
+    fn dictionary() {
+        let dict = b"The quick brown fox jumps over the lazy dog.".repeat(100);
+        let pool = ContextPool::with_dict(4, Dictionary::new(&dict).unwrap());
+        let src = &dict[1000..2000];
+
+        let mut comp_buf = Vec::new();
+        pool.compress_to_vec(src, &mut comp_buf, &Preferences::default())
+            .unwrap();
+        let mut plain_buf = Vec::new();
+        lz4f::compress_to_vec(src, &mut plain_buf, &Preferences::default()).unwrap();
+        assert!(comp_buf.len() < plain_buf.len());
+
+        let mut decomp_buf = Vec::new();
+        pool.decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+        assert_eq!(decomp_buf, src);
+    }
+}
+
+mod decompression_state {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4f_test_set().par_bridge().for_each_init(
+            || DecompressionState::new().unwrap(),
+            |state, (src, prefs)| {
+                let mut comp_buf = Vec::new();
+                lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+                let header = &b"HEADER"[..];
+                let mut decomp_buf = Vec::from(header);
+                assert_eq!(
+                    state
+                        .decompress_to_vec(&comp_buf[..comp_buf.len() - 1], &mut decomp_buf)
+                        .map_err(Error::kind),
+                    Err(lzzzz::ErrorKind::CompressedDataIncomplete)
+                );
+                assert_eq!(decomp_buf, header);
+
+                state.decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+                assert_eq!(&decomp_buf[header.len()..], &src[..]);
+            },
+        );
+    }
+
+    #[test]
+    fn abandoned_content_size() {
+        let src = b"The quick brown fox jumps over the lazy dog. ".repeat(10000);
+        let prefs = PreferencesBuilder::new().content_size(src.len()).build();
+        let mut sized_buf = Vec::new();
+        lz4f::compress_to_vec(&src, &mut sized_buf, &prefs).unwrap();
+        let mut comp_buf = Vec::new();
+        lz4f::compress_to_vec(&src, &mut comp_buf, &Preferences::default()).unwrap();
+
+        // The remaining content size of the abandoned frame must not apply to the next one.
+        let mut state = DecompressionState::new().unwrap();
+        let mut decomp_buf = Vec::new();
+        assert!(state
+            .decompress_to_vec(&sized_buf[..sized_buf.len() / 2], &mut decomp_buf)
+            .is_err());
+        state.decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+        assert_eq!(decomp_buf, src);
+    }
+}
+
+mod dictionary_set {
+    use super::*;
+    use rand::{distributions::Standard, rngs::SmallRng, Rng, SeedableRng};
+
+    fn dictionary_data(seed: u64) -> Vec<u8> {
+        SmallRng::seed_from_u64(seed)
+            .sample_iter(Standard)
+            .take(64_000)
+            .collect()
+    }
+
+    #[test]
+    fn select() {
+        let dict_a = dictionary_data(1);
+        let dict_b = dictionary_data(2);
+        let mut set = DictionarySet::new();
+        set.insert(1, Dictionary::new(&dict_a).unwrap());
+        set.insert(2, Dictionary::new(&dict_b).unwrap());
+
+        let prefs = Preferences::default();
+        assert_eq!(set.select(&dict_a[1000..9000], &prefs).unwrap(), 1);
+        assert_eq!(set.select(&dict_b[1000..9000], &prefs).unwrap(), 2);
+        assert_eq!(set.select(&dictionary_data(3)[..8000], &prefs).unwrap(), 0);
+
+        set.set_sample_size(1000);
+        assert_eq!(set.select(&dict_b[1000..9000], &prefs).unwrap(), 2);
+    }
+
+    #[test]
+    fn default() {
+        let dict_a = dictionary_data(1);
+        let dict_b = dictionary_data(2);
+        let mut set = DictionarySet::new();
+        set.insert(1, Dictionary::new(&dict_a).unwrap());
+        set.insert(2, Dictionary::new(&dict_b).unwrap());
+
+        lz4f_test_set()
+            .filter(|(src, _)| src.len() <= 64 * 1024)
+            .par_bridge()
+            .for_each(|(src, prefs)| {
+                let mut src = src.to_vec();
+                src.splice(..0, dict_b[32_000..36_096].iter().copied());
+
+                let header = &b"HEADER"[..];
+                let mut comp_buf = Vec::from(header);
+                let mut decomp_buf = Vec::from(header);
+                assert_eq!(
+                    set.compress_to_vec(&src, &mut comp_buf, &prefs).unwrap(),
+                    comp_buf.len() - header.len()
+                );
+
+                let mut frame = &comp_buf[header.len()..];
+                let mut r = ReadDecompressor::new(&mut frame).unwrap();
+                assert_eq!(r.read_frame_info().unwrap().dict_id(), 2);
+
+                assert_eq!(
+                    set.decompress_to_vec(&comp_buf[header.len()..], &mut decomp_buf)
+                        .unwrap(),
+                    decomp_buf.len() - header.len()
+                );
+                assert_eq!(&decomp_buf[header.len()..], &src[..]);
+            });
+    }
+
+    #[test]
+    fn dictionary_not_found() {
+        let dict = dictionary_data(1);
+        let mut set = DictionarySet::new();
+        set.insert(1, Dictionary::new(&dict).unwrap());
+
+        let mut comp_buf = Vec::new();
+        set.compress_to_vec(
+            &dict[32_000..40_000],
+            &mut comp_buf,
+            &Preferences::default(),
+        )
+        .unwrap();
+
+        let mut decomp_buf = Vec::new();
+        assert_eq!(
+            DictionarySet::new()
+                .decompress_to_vec(&comp_buf, &mut decomp_buf)
+                .map_err(Error::kind),
+            Err(lzzzz::ErrorKind::DictionaryNotFound)
+        );
+        assert!(decomp_buf.is_empty());
+    }
 }
@@ -4,11 +4,92 @@ use rayon::{iter::ParallelBridge, prelude::*};
 use static_assertions::assert_impl_all;
 use std::{
     fs::File,
-    io::{prelude::*, BufReader},
+    io::{prelude::*, BufReader, IoSlice},
+    sync::{Arc, Mutex},
 };
 
 mod common;
-use common::lz4f_test_set;
+use common::{
+    checksum_error_set, corrupted_checksum_set, for_each_schedule, lz4f_test_set, retry,
+    FlakyReader, FlakyWriter, TRANSIENT_ERRORS,
+};
+
+fn flaky_test_set() -> impl Iterator<Item = (bytes::Bytes, Preferences)> {
+    lz4f_test_set().filter(|(src, _)| src.len() <= 0x10000)
+}
+
+/// Returns a small input and its frame, for the tests which try every fault schedule.
+fn flaky_sample() -> (Vec<u8>, Vec<u8>) {
+    let src = b"The quick brown fox jumps over the lazy dog. ".repeat(100);
+    let mut comp_buf = Vec::new();
+    lz4f::compress_to_vec(&src, &mut comp_buf, &Default::default()).unwrap();
+    (src, comp_buf)
+}
+
+/// Writes `src` in small chunks, retrying on transient errors.
+fn write_retry<W: Write>(w: &mut W, src: &[u8]) {
+    for mut chunk in src.chunks(1000) {
+        while !chunk.is_empty() {
+            let len = retry(|| w.write(chunk)).unwrap();
+            chunk = &chunk[len..];
+        }
+    }
+    retry(|| w.flush()).unwrap();
+}
+
+/// Reads until EOF in small chunks, retrying on transient errors.
+fn read_retry<R: Read>(r: &mut R) -> Vec<u8> {
+    let mut dst = Vec::new();
+    let mut buf = [0; 13];
+    loop {
+        match retry(|| r.read(&mut buf)).unwrap() {
+            0 => return dst,
+            len => dst.extend_from_slice(&buf[..len]),
+        }
+    }
+}
+
+/// Returns limits for a stream of two frames of `len` bytes each,
+/// with whether the stream fits within them.
+fn limits_set(len: usize) -> Vec<(DecompressionLimits, bool)> {
+    vec![
+        (DecompressionLimits::default(), true),
+        (
+            DecompressionLimitsBuilder::new().max_frames(2).build(),
+            true,
+        ),
+        (
+            DecompressionLimitsBuilder::new().max_frames(1).build(),
+            false,
+        ),
+        (
+            DecompressionLimitsBuilder::new()
+                .max_output_size(len * 2)
+                .build(),
+            true,
+        ),
+        (
+            DecompressionLimitsBuilder::new()
+                .max_output_size(len * 2 - 1)
+                .build(),
+            false,
+        ),
+    ]
+}
+
+fn assert_limits(result: std::io::Result<Vec<u8>>, src: &[u8], ok: bool) {
+    if ok {
+        assert_eq!(result.unwrap(), [src, src].concat());
+    } else {
+        let err = result.unwrap_err();
+        assert_eq!(
+            err.get_ref()
+                .and_then(|err| err.downcast_ref::<lz4f::Error>())
+                .map(|err| err.kind()),
+            Some(lzzzz::ErrorKind::DecompressionLimitExceeded)
+        );
+    }
+}
 
 assert_impl_all!(lz4f::BufReadCompressor<BufReader<File>>: Send);
 assert_impl_all!(lz4f::ReadCompressor<File>: Send);
@@ -17,9 +98,134 @@ assert_impl_all!(lz4f::BufReadDecompressor<BufReader<File>>: Send);
 assert_impl_all!(lz4f::ReadDecompressor<File>: Send);
 assert_impl_all!(lz4f::WriteDecompressor<File>: Send);
 
+/// Returns the lengths to truncate a frame of `len` bytes to.
+fn truncated_lens(len: usize) -> [usize; 3] {
+    [1, len / 2, len - 1]
+}
+
+fn assert_incomplete<T: std::fmt::Debug>(result: std::io::Result<T>) {
+    let err = result.unwrap_err();
+    assert_eq!(
+        err.get_ref()
+            .and_then(|err| err.downcast_ref::<lz4f::Error>())
+            .map(|err| err.kind()),
+        Some(lzzzz::ErrorKind::CompressedDataIncomplete)
+    );
+}
+
+/// Returns the `lz4f::Error` carried by `err`.
+fn lz4f_error(err: &std::io::Error) -> &lz4f::Error {
+    err.get_ref().unwrap().downcast_ref().unwrap()
+}
+
 mod write_compressor {
     use super::*;
-    use lzzzz::lz4f::WriteCompressor;
+    use lzzzz::lz4f::{Dictionary, WriteCompressor, WriteDecompressor};
+
+    #[test]
+    fn reset() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut w = WriteCompressor::new(Vec::new(), Default::default()).unwrap();
+            w.write_all(b"Hello world!").unwrap();
+            let first = w.reset_with_prefs(Vec::new(), prefs).unwrap();
+            assert_eq!(w.stats().frames(), 0);
+            w.write_all(&src).unwrap();
+            let second = w.reset(Vec::new()).unwrap();
+            assert_eq!(w.stats().bytes_in(), 0);
+            w.write_all(&src).unwrap();
+            let third = w.finish().unwrap();
+
+            let mut decomp_buf = Vec::new();
+            lz4f::decompress_to_vec(&first, &mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, b"Hello world!");
+            for comp_buf in &[second, third] {
+                decomp_buf.clear();
+                lz4f::decompress_to_vec(comp_buf, &mut decomp_buf).unwrap();
+                assert_eq!(decomp_buf, src);
+            }
+        });
+    }
+
+    #[test]
+    fn reset_dictionary() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let dict = SmallRng::seed_from_u64(0)
+                .sample_iter(Standard)
+                .take(64_000)
+                .collect::<Vec<_>>();
+            let mut w =
+                WriteCompressor::with_dict(Vec::new(), prefs, Dictionary::new(&dict).unwrap())
+                    .unwrap();
+            w.write_all(&src).unwrap();
+            let first = w.reset(Vec::new()).unwrap();
+            w.write_all(&src).unwrap();
+            let second = w.finish().unwrap();
+
+            for comp_buf in &[first, second] {
+                let mut decomp_buf = Vec::new();
+                let mut w = WriteDecompressor::new(&mut decomp_buf).unwrap();
+                w.set_dict(&dict);
+                w.write_all(comp_buf).unwrap();
+                w.finish().unwrap();
+                assert_eq!(decomp_buf, src);
+            }
+        });
+    }
+
+    #[test]
+    fn content_checksum() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let prefs = PreferencesBuilder::from(prefs)
+                .content_checksum(ContentChecksum::Enabled)
+                .build();
+            let mut w = WriteCompressor::new(Vec::new(), prefs).unwrap();
+            w.write_all(&src).unwrap();
+            assert_eq!(w.content_checksum(), None);
+            w.try_finish().unwrap();
+            assert_eq!(
+                w.content_checksum(),
+                Some(lzzzz::xxhash::lz4f_content_checksum(&src))
+            );
+        });
+    }
+
+    #[test]
+    fn write_after_finish() {
+        let mut w = WriteCompressor::new(Vec::new(), Default::default()).unwrap();
+        w.write_all(b"Hello world!").unwrap();
+        w.try_finish().unwrap();
+        for err in [
+            w.write(b"!").unwrap_err(),
+            w.write_vectored(&[IoSlice::new(b"!")]).unwrap_err(),
+        ] {
+            assert_eq!(
+                err.get_ref()
+                    .and_then(|err| err.downcast_ref::<lzzzz::Error>())
+                    .map(|err| err.kind()),
+                Some(lzzzz::ErrorKind::CompressionFailed)
+            );
+        }
+        w.flush().unwrap();
+
+        let comp_buf = w.finish().unwrap();
+        let mut decomp_buf = Vec::new();
+        lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+        assert_eq!(decomp_buf, b"Hello world!");
+    }
+
+    #[test]
+    fn finish_with_checksum() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut w = WriteCompressor::new(Vec::new(), prefs).unwrap();
+            w.write_all(&src).unwrap();
+            let (comp_buf, checksum) = w.finish_with_checksum().unwrap();
+            assert_eq!(checksum, lz4f::content_checksum(&comp_buf));
+            assert_eq!(
+                checksum.is_some(),
+                prefs.frame_info().content_checksum() == ContentChecksum::Enabled
+            );
+        });
+    }
 
     #[test]
     fn default() {
@@ -38,6 +244,35 @@ mod write_compressor {
         });
     }
 
+    #[test]
+    fn vectored() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            let mut decomp_buf = Vec::new();
+            {
+                let mut w = WriteCompressor::new(&mut comp_buf, prefs).unwrap();
+                let (header, rest) = src.split_at(src.len() / 3);
+                let (body, trailer) = rest.split_at(rest.len() / 2);
+                let mut parts = [header, body, trailer];
+                while parts.iter().any(|part| !part.is_empty()) {
+                    let bufs = parts.map(IoSlice::new);
+                    let mut len = w.write_vectored(&bufs).unwrap();
+                    assert!(len > 0);
+                    for part in &mut parts {
+                        let n = std::cmp::min(len, part.len());
+                        *part = &part[n..];
+                        len -= n;
+                    }
+                }
+            }
+            assert_eq!(
+                lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap(),
+                decomp_buf.len()
+            );
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
     #[test]
     fn random_chunk() {
         lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
@@ -63,12 +298,160 @@ mod write_compressor {
             assert_eq!(decomp_buf, src);
         });
     }
+
+    #[test]
+    fn finish() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut w = WriteCompressor::new(Vec::new(), prefs).unwrap();
+            w.write_all(&src).unwrap();
+            let comp_buf = w.finish().unwrap();
+
+            let mut decomp_buf = Vec::new();
+            lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
+    #[test]
+    fn try_finish() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let writer = FlakyWriter::new(Vec::new(), std::io::ErrorKind::Other, 7);
+            let mut w = WriteCompressor::new(writer, prefs).unwrap();
+            w.get_mut().err = None;
+            w.write_all(&src).unwrap();
+
+            // Every other call to the writer fails, so ending the frame fails at least once.
+            w.get_mut().err = Some(std::io::ErrorKind::Other);
+            let mut errors = 0;
+            while let Err(err) = w.try_finish() {
+                assert_eq!(err.kind(), std::io::ErrorKind::Other);
+                errors += 1;
+            }
+            assert!(errors > 0);
+            assert!(w.write(b"more").is_err());
+
+            w.get_mut().err = None;
+            let comp_buf = w.finish().unwrap().inner;
+            let mut decomp_buf = Vec::new();
+            lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
+    #[test]
+    fn stats() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let progress = Arc::new(Mutex::new(Vec::new()));
+            let mut w = WriteCompressor::new(Vec::new(), prefs).unwrap();
+            let sink = progress.clone();
+            w.set_progress(move |stats| sink.lock().unwrap().push(*stats));
+            w.write_all(&src).unwrap();
+            w.try_finish().unwrap();
+
+            let stats = w.stats();
+            assert_eq!(stats.bytes_in(), src.len() as u64);
+            assert_eq!(stats.bytes_out(), w.get_ref().len() as u64);
+            assert_eq!(stats.frames(), 1);
+            assert_eq!(stats.blocks() > 0, !src.is_empty());
+
+            let progress = progress.lock().unwrap();
+            assert_eq!(progress.last(), Some(&stats));
+            assert!(progress
+                .windows(2)
+                .all(|pair| pair[0].bytes_out() <= pair[1].bytes_out()));
+        });
+    }
+
+    #[test]
+    fn flaky() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            for err in TRANSIENT_ERRORS {
+                let mut w =
+                    WriteCompressor::new(FlakyWriter::new(Vec::new(), err, 7), prefs).unwrap();
+                write_retry(&mut w, &src);
+                w.get_mut().err = None;
+                let comp_buf = w.into_inner().inner;
+
+                let mut decomp_buf = Vec::new();
+                lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+                assert_eq!(decomp_buf, src);
+            }
+        });
+    }
+
+    #[test]
+    fn flaky_schedules() {
+        let (src, _) = flaky_sample();
+        for_each_schedule(|faults, err| {
+            let writer = FlakyWriter::with_faults(Vec::new(), err, 7, faults);
+            let mut w = WriteCompressor::new(writer, Default::default()).unwrap();
+            write_retry(&mut w, &src);
+            retry(|| w.try_finish()).unwrap();
+            let writer = w.into_inner();
+
+            let mut decomp_buf = Vec::new();
+            lz4f::decompress_to_vec(&writer.inner, &mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+            writer.calls()
+        });
+    }
 }
 
 mod read_compressor {
     use super::*;
     use lzzzz::lz4f::ReadCompressor;
 
+    #[test]
+    fn reset() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut r = ReadCompressor::new(&b"Hello world!"[..], Default::default()).unwrap();
+            let mut buf = [0; 4];
+            r.read_exact(&mut buf).unwrap();
+            assert_eq!(r.reset_with_prefs(src.as_ref(), prefs), b"");
+
+            let mut comp_buf = Vec::new();
+            r.read_to_end(&mut comp_buf).unwrap();
+            assert_eq!(r.stats().bytes_out(), comp_buf.len() as u64);
+            assert_eq!(r.reset(src.as_ref()), b"");
+
+            let mut comp_buf2 = Vec::new();
+            r.read_to_end(&mut comp_buf2).unwrap();
+            assert_eq!(comp_buf, comp_buf2);
+
+            let mut decomp_buf = Vec::new();
+            lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
+    #[test]
+    fn content_checksum() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            let mut r = ReadCompressor::new(src.as_ref(), prefs).unwrap();
+            r.read_to_end(&mut comp_buf).unwrap();
+            assert_eq!(
+                r.content_checksum().is_some(),
+                prefs.frame_info().content_checksum() == ContentChecksum::Enabled
+            );
+            assert_eq!(r.content_checksum(), lz4f::content_checksum(&comp_buf));
+        });
+    }
+
+    #[test]
+    fn stats() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            let mut r = ReadCompressor::new(src.as_ref(), prefs).unwrap();
+            r.read_to_end(&mut comp_buf).unwrap();
+
+            let stats = r.stats();
+            assert_eq!(stats.bytes_in(), src.len() as u64);
+            assert_eq!(stats.bytes_out(), comp_buf.len() as u64);
+            assert_eq!(stats.frames(), 1);
+        });
+    }
+
     #[test]
     fn default() {
         lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
@@ -119,12 +502,65 @@ mod read_compressor {
             assert_eq!(decomp_buf, src);
         });
     }
+
+    #[test]
+    fn flaky() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            for err in TRANSIENT_ERRORS {
+                let mut r =
+                    ReadCompressor::new(FlakyReader::new(src.as_ref(), err, 7), prefs).unwrap();
+                let comp_buf = read_retry(&mut r);
+
+                let mut decomp_buf = Vec::new();
+                lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+                assert_eq!(decomp_buf, src);
+            }
+        });
+    }
+
+    #[test]
+    fn flaky_schedules() {
+        let (src, _) = flaky_sample();
+        for_each_schedule(|faults, err| {
+            let reader = FlakyReader::with_faults(src.as_slice(), err, 7, faults);
+            let mut r = ReadCompressor::new(reader, Default::default()).unwrap();
+            let comp_buf = read_retry(&mut r);
+
+            let mut decomp_buf = Vec::new();
+            lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+            r.get_ref().calls()
+        });
+    }
 }
 
 mod bufread_compressor {
     use super::*;
     use lzzzz::lz4f::BufReadCompressor;
 
+    #[test]
+    fn reset() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut r = BufReadCompressor::new(&b"Hello world!"[..], Default::default()).unwrap();
+            let mut buf = [0; 4];
+            r.read_exact(&mut buf).unwrap();
+            assert_eq!(r.reset_with_prefs(src.as_ref(), prefs), b"");
+
+            let mut comp_buf = Vec::new();
+            r.read_to_end(&mut comp_buf).unwrap();
+            assert_eq!(r.stats().bytes_out(), comp_buf.len() as u64);
+            assert_eq!(r.reset(src.as_ref()), b"");
+
+            let mut comp_buf2 = Vec::new();
+            r.read_to_end(&mut comp_buf2).unwrap();
+            assert_eq!(comp_buf, comp_buf2);
+
+            let mut decomp_buf = Vec::new();
+            lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
     #[test]
     fn default() {
         lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
@@ -175,12 +611,106 @@ mod bufread_compressor {
             assert_eq!(decomp_buf, src);
         });
     }
+
+    #[test]
+    fn flaky() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            for err in TRANSIENT_ERRORS {
+                let reader = BufReader::with_capacity(64, FlakyReader::new(src.as_ref(), err, 7));
+                let mut r = BufReadCompressor::new(reader, prefs).unwrap();
+                let comp_buf = read_retry(&mut r);
+
+                let mut decomp_buf = Vec::new();
+                lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+                assert_eq!(decomp_buf, src);
+            }
+        });
+    }
+
+    #[test]
+    fn flaky_schedules() {
+        let (src, _) = flaky_sample();
+        for_each_schedule(|faults, err| {
+            let reader = FlakyReader::with_faults(src.as_slice(), err, 7, faults);
+            let mut r =
+                BufReadCompressor::new(BufReader::with_capacity(64, reader), Default::default())
+                    .unwrap();
+            let comp_buf = read_retry(&mut r);
+
+            let mut decomp_buf = Vec::new();
+            lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+            r.get_ref().get_ref().calls()
+        });
+    }
 }
 
 mod write_decompressor {
     use super::*;
     use lzzzz::lz4f::{Dictionary, WriteCompressor, WriteDecompressor};
 
+    #[test]
+    fn reset() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            let mut w = WriteDecompressor::new(Vec::new()).unwrap();
+            w.write_all(&comp_buf[..comp_buf.len() / 2]).unwrap();
+            let partial = w.reset(Vec::new()).unwrap();
+            assert!(src.starts_with(&partial));
+            assert_eq!(w.frame_info(), None);
+            assert_eq!(w.stats().bytes_in(), 0);
+
+            w.write_all(&comp_buf).unwrap();
+            w.try_finish().unwrap();
+            assert_eq!(w.reset(Vec::new()).unwrap(), src);
+            w.write_all(&comp_buf).unwrap();
+            assert_eq!(w.finish().unwrap(), src);
+        });
+    }
+
+    #[test]
+    fn content_checksum() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let prefs = PreferencesBuilder::from(prefs)
+                .content_checksum(ContentChecksum::Enabled)
+                .build();
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            let mut w = WriteDecompressor::new(Vec::new()).unwrap();
+            w.write_all(&comp_buf[..comp_buf.len() - 1]).unwrap();
+            assert_eq!(w.content_checksum(), None);
+            w.write_all(&comp_buf[comp_buf.len() - 1..]).unwrap();
+            assert_eq!(
+                w.content_checksum(),
+                Some(lzzzz::xxhash::lz4f_content_checksum(&src))
+            );
+        });
+    }
+
+    #[test]
+    fn stats() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut w = WriteCompressor::new(Vec::new(), prefs).unwrap();
+            w.write_all(&src).unwrap();
+            w.try_finish().unwrap();
+            let comp_stats = w.stats();
+            let mut comp_buf = w.finish().unwrap();
+            comp_buf.extend_from_slice(&comp_buf.clone());
+
+            let mut w = WriteDecompressor::new(Vec::new()).unwrap();
+            write_retry(&mut w, &comp_buf);
+            let stats = w.stats();
+            assert_eq!(stats.bytes_in(), comp_buf.len() as u64);
+            assert_eq!(stats.bytes_out(), 2 * src.len() as u64);
+            assert_eq!(stats.frames(), 2);
+            assert_eq!(stats.blocks(), 2 * comp_stats.blocks());
+            assert_eq!(stats.stored_blocks(), 2 * comp_stats.stored_blocks());
+        });
+    }
+
     #[test]
     fn default() {
         lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
@@ -252,6 +782,75 @@ mod write_decompressor {
         });
     }
 
+    #[test]
+    fn skip_checksums() {
+        corrupted_checksum_set()
+            .par_bridge()
+            .for_each(|(src, comp_buf)| {
+                let mut decomp_buf = Vec::new();
+                {
+                    let mut w = WriteDecompressor::new(&mut decomp_buf).unwrap();
+                    assert!(w.write_all(&comp_buf).is_err());
+                }
+                decomp_buf.clear();
+                {
+                    let mut w = WriteDecompressor::new(&mut decomp_buf).unwrap();
+                    w.skip_checksums(true);
+                    w.write_all(&comp_buf).unwrap();
+                }
+                assert_eq!(decomp_buf, src);
+            });
+    }
+
+    #[test]
+    fn checksum_context() {
+        for case in checksum_error_set() {
+            let mut w = WriteDecompressor::new(Vec::new()).unwrap();
+            let err = case
+                .frame
+                .chunks(1000)
+                .try_for_each(|chunk| w.write_all(chunk))
+                .unwrap_err();
+            case.check(lz4f_error(&err));
+        }
+    }
+
+    #[test]
+    fn limits() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            for (limits, ok) in limits_set(src.len()) {
+                let mut decomp_buf = Vec::new();
+                let mut w = WriteDecompressor::new(&mut decomp_buf).unwrap();
+                w.set_limits(limits);
+                let result = w.write_all(&comp_buf).map(|_| w.into_inner().clone());
+                assert_limits(result, &src, ok);
+            }
+        });
+    }
+
+    #[test]
+    fn truncated() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            let mut w = WriteDecompressor::new(Vec::new()).unwrap();
+            w.write_all(&comp_buf).unwrap();
+            assert_eq!(w.finish().unwrap(), src);
+
+            for len in truncated_lens(comp_buf.len()) {
+                let mut w = WriteDecompressor::new(Vec::new()).unwrap();
+                w.write_all(&comp_buf[..len]).unwrap();
+                assert_incomplete(w.try_finish());
+                assert!(src.starts_with(w.get_ref()));
+            }
+        });
+    }
+
     #[test]
     fn random_chunk() {
         lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
@@ -296,19 +895,106 @@ mod write_decompressor {
                     .unwrap()
                     .downcast::<lz4f::Error>()
                     .unwrap();
-                assert_eq!(
-                    *err,
-                    lz4f::Error::Common(lzzzz::ErrorKind::FrameHeaderInvalid)
-                );
+                assert_eq!(err.kind(), lzzzz::ErrorKind::FrameHeaderInvalid);
             }
         });
     }
+
+    #[test]
+    fn flaky() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            for err in TRANSIENT_ERRORS {
+                let mut w = WriteDecompressor::new(FlakyWriter::new(Vec::new(), err, 7)).unwrap();
+                write_retry(&mut w, &comp_buf);
+                assert_eq!(w.into_inner().inner, src);
+            }
+        });
+    }
+
+    #[test]
+    fn flaky_schedules() {
+        let (src, comp_buf) = flaky_sample();
+        for_each_schedule(|faults, err| {
+            let writer = FlakyWriter::with_faults(Vec::new(), err, 7, faults);
+            let mut w = WriteDecompressor::new(writer).unwrap();
+            write_retry(&mut w, &comp_buf);
+            retry(|| w.try_finish()).unwrap();
+            let writer = w.into_inner();
+            assert_eq!(writer.inner, src);
+            writer.calls()
+        });
+    }
 }
 
 mod read_decompressor {
     use super::*;
     use lzzzz::lz4f::{ReadDecompressor, WriteCompressor};
 
+    #[test]
+    fn reset() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            let half = &comp_buf[..comp_buf.len() / 2];
+            let mut r = ReadDecompressor::new(half).unwrap();
+            let mut buf = [0; 1];
+            let _ = r.read(&mut buf).unwrap();
+            // The data buffered from the old reader is dropped.
+            assert!(half.ends_with(r.reset(comp_buf.as_slice())));
+            assert_eq!(r.stats().bytes_in(), 0);
+
+            for _ in 0..2 {
+                let mut decomp_buf = Vec::new();
+                r.read_to_end(&mut decomp_buf).unwrap();
+                assert_eq!(decomp_buf, src);
+                r.reset(comp_buf.as_slice());
+            }
+        });
+    }
+
+    #[test]
+    fn content_checksum() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(b"first frame", &mut comp_buf, &prefs).unwrap();
+            let prefs = PreferencesBuilder::from(prefs)
+                .content_checksum(ContentChecksum::Enabled)
+                .build();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            let mut r = ReadDecompressor::new(comp_buf.as_slice()).unwrap();
+            r.read_to_end(&mut Vec::new()).unwrap();
+            assert_eq!(
+                r.content_checksum(),
+                Some(lzzzz::xxhash::lz4f_content_checksum(&src))
+            );
+        });
+    }
+
+    #[test]
+    fn stats() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            let last = Arc::new(Mutex::new(None));
+            let mut r = ReadDecompressor::new(comp_buf.as_slice()).unwrap();
+            let sink = last.clone();
+            r.set_progress(move |stats| *sink.lock().unwrap() = Some(*stats));
+            let mut decomp_buf = Vec::new();
+            r.read_to_end(&mut decomp_buf).unwrap();
+
+            let stats = r.stats();
+            assert_eq!(stats.bytes_in(), comp_buf.len() as u64);
+            assert_eq!(stats.bytes_out(), src.len() as u64);
+            assert_eq!(stats.frames(), 1);
+            assert_eq!(*last.lock().unwrap(), Some(stats));
+        });
+    }
+
     #[test]
     fn default() {
         lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
@@ -363,6 +1049,89 @@ mod read_decompressor {
         });
     }
 
+    #[test]
+    fn skip_checksums() {
+        corrupted_checksum_set()
+            .par_bridge()
+            .for_each(|(src, comp_buf)| {
+                let mut decomp_buf = Vec::new();
+                let mut r = ReadDecompressor::new(comp_buf.as_slice()).unwrap();
+                assert!(r.read_to_end(&mut decomp_buf).is_err());
+
+                decomp_buf.clear();
+                let mut r = ReadDecompressor::new(comp_buf.as_slice()).unwrap();
+                r.skip_checksums(true);
+                r.read_to_end(&mut decomp_buf).unwrap();
+                assert_eq!(decomp_buf, src);
+            });
+    }
+
+    #[test]
+    fn error_context() {
+        corrupted_checksum_set()
+            .par_bridge()
+            .for_each(|(src, corrupted)| {
+                let mut comp_buf = Vec::new();
+                lz4f::compress_to_vec(&src, &mut comp_buf, &Default::default()).unwrap();
+                let first_len = comp_buf.len() as u64;
+                comp_buf.extend_from_slice(&corrupted);
+
+                let mut decomp_buf = Vec::new();
+                let mut r = ReadDecompressor::new(comp_buf.as_slice()).unwrap();
+                let err = r.read_to_end(&mut decomp_buf).unwrap_err();
+                assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+                let err = err
+                    .get_ref()
+                    .unwrap()
+                    .downcast_ref::<lz4f::Error>()
+                    .unwrap();
+                assert_eq!(err.frame_index(), Some(1));
+                assert!(err.input_offset().unwrap() >= first_len);
+                assert!(err.output_offset().unwrap() >= src.len() as u64);
+            });
+    }
+
+    #[test]
+    fn checksum_context() {
+        for case in checksum_error_set() {
+            let mut r = ReadDecompressor::new(case.frame.as_slice()).unwrap();
+            let err = r.read_to_end(&mut Vec::new()).unwrap_err();
+            case.check(lz4f_error(&err));
+        }
+    }
+
+    #[test]
+    fn limits() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            for (limits, ok) in limits_set(src.len()) {
+                let mut decomp_buf = Vec::new();
+                let mut r = ReadDecompressor::new(comp_buf.as_slice()).unwrap();
+                r.set_limits(limits);
+                let result = r.read_to_end(&mut decomp_buf).map(|_| decomp_buf);
+                assert_limits(result, &src, ok);
+            }
+        });
+    }
+
+    #[test]
+    fn truncated() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            for len in truncated_lens(comp_buf.len()) {
+                let mut decomp_buf = Vec::new();
+                let mut r = ReadDecompressor::new(&comp_buf[..len]).unwrap();
+                assert_incomplete(r.read_to_end(&mut decomp_buf));
+                assert!(src.starts_with(&decomp_buf));
+            }
+        });
+    }
+
     #[test]
     fn random_chunk() {
         lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
@@ -390,12 +1159,75 @@ mod read_decompressor {
             assert_eq!(decomp_buf.len(), src.len());
         });
     }
+
+    #[test]
+    fn flaky() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            for err in TRANSIENT_ERRORS {
+                let mut r =
+                    ReadDecompressor::new(FlakyReader::new(comp_buf.as_slice(), err, 101)).unwrap();
+                assert_eq!(read_retry(&mut r), src);
+            }
+        });
+    }
+
+    #[test]
+    fn flaky_schedules() {
+        let (src, comp_buf) = flaky_sample();
+        for_each_schedule(|faults, err| {
+            let reader = FlakyReader::with_faults(comp_buf.as_slice(), err, 7, faults);
+            let mut r = ReadDecompressor::new(reader).unwrap();
+            assert_eq!(read_retry(&mut r), src);
+            r.get_ref().calls()
+        });
+    }
+
+    #[test]
+    fn large_buffer() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            let mut r = ReadDecompressor::new(comp_buf.as_slice()).unwrap();
+            let mut buf = vec![0; 4 << 20];
+            let mut decomp_buf = Vec::new();
+            loop {
+                match r.read(&mut buf).unwrap() {
+                    0 => break,
+                    len => decomp_buf.extend_from_slice(&buf[..len]),
+                }
+            }
+            assert_eq!(decomp_buf, src);
+        });
+    }
 }
 
 mod bufread_decompressor {
     use super::*;
     use lzzzz::lz4f::{BufReadDecompressor, WriteCompressor};
 
+    #[test]
+    fn reset() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            let mut r = BufReadDecompressor::new(&comp_buf[..comp_buf.len() / 2]).unwrap();
+            let _ = r.fill_buf().unwrap();
+            assert_eq!(r.reset(comp_buf.as_slice()), b"");
+            assert_eq!(r.stats().bytes_in(), 0);
+
+            for _ in 0..2 {
+                let mut decomp_buf = Vec::new();
+                r.read_to_end(&mut decomp_buf).unwrap();
+                assert_eq!(decomp_buf, src);
+                r.reset(comp_buf.as_slice());
+            }
+        });
+    }
+
     #[test]
     fn default() {
         lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
@@ -450,6 +1282,73 @@ mod bufread_decompressor {
         });
     }
 
+    #[test]
+    fn skip_checksums() {
+        corrupted_checksum_set()
+            .par_bridge()
+            .for_each(|(src, comp_buf)| {
+                let mut decomp_buf = Vec::new();
+                let mut r = BufReadDecompressor::new(comp_buf.as_slice()).unwrap();
+                assert!(r.read_to_end(&mut decomp_buf).is_err());
+
+                decomp_buf.clear();
+                let mut r = BufReadDecompressor::new(comp_buf.as_slice()).unwrap();
+                r.skip_checksums(true);
+                r.read_to_end(&mut decomp_buf).unwrap();
+                assert_eq!(decomp_buf, src);
+            });
+    }
+
+    #[test]
+    fn checksum_context() {
+        for case in checksum_error_set() {
+            // Small reads go through the internal buffer, large ones straight into `buf`.
+            for len in [100, 1 << 20] {
+                let reader = BufReader::with_capacity(1000, case.frame.as_slice());
+                let mut r = BufReadDecompressor::new(reader).unwrap();
+                let mut buf = vec![0; len];
+                let err = loop {
+                    match r.read(&mut buf) {
+                        Ok(len) => assert!(len > 0),
+                        Err(err) => break err,
+                    }
+                };
+                case.check(lz4f_error(&err));
+            }
+        }
+    }
+
+    #[test]
+    fn limits() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            for (limits, ok) in limits_set(src.len()) {
+                let mut decomp_buf = Vec::new();
+                let mut r = BufReadDecompressor::new(comp_buf.as_slice()).unwrap();
+                r.set_limits(limits);
+                let result = r.read_to_end(&mut decomp_buf).map(|_| decomp_buf);
+                assert_limits(result, &src, ok);
+            }
+        });
+    }
+
+    #[test]
+    fn truncated() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            for len in truncated_lens(comp_buf.len()) {
+                let mut decomp_buf = Vec::new();
+                let mut r = BufReadDecompressor::new(&comp_buf[..len]).unwrap();
+                assert_incomplete(r.read_to_end(&mut decomp_buf));
+                assert!(src.starts_with(&decomp_buf));
+            }
+        });
+    }
+
     #[test]
     fn random_chunk() {
         lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
@@ -477,4 +1376,351 @@ mod bufread_decompressor {
             assert_eq!(decomp_buf.len(), src.len());
         });
     }
+
+    #[test]
+    fn flaky() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            for err in TRANSIENT_ERRORS {
+                let reader =
+                    BufReader::with_capacity(64, FlakyReader::new(comp_buf.as_slice(), err, 101));
+                let mut r = BufReadDecompressor::new(reader).unwrap();
+                assert_eq!(read_retry(&mut r), src);
+            }
+        });
+    }
+
+    #[test]
+    fn flaky_schedules() {
+        let (src, comp_buf) = flaky_sample();
+        for_each_schedule(|faults, err| {
+            let reader = FlakyReader::with_faults(comp_buf.as_slice(), err, 7, faults);
+            let mut r = BufReadDecompressor::new(BufReader::with_capacity(64, reader)).unwrap();
+            assert_eq!(read_retry(&mut r), src);
+            r.get_ref().get_ref().calls()
+        });
+    }
+}
+
+mod salvage_decompressor {
+    use super::*;
+    use lzzzz::lz4f::{SalvageDecompressor, WriteCompressor};
+    use std::ops::Range;
+
+    const CHUNK_SIZE: usize = 64 << 10;
+
+    /// Returns four chunks of a block each, and a frame of them with block checksums.
+    ///
+    /// The frame header has the content size if `sized` is `true`.
+    fn blocks(block_mode: BlockMode, sized: bool) -> (Vec<Vec<u8>>, Vec<u8>) {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let chunks = (0..4)
+            .map(|_| (0..CHUNK_SIZE).map(|_| rng.gen_range(0..4)).collect())
+            .collect::<Vec<Vec<u8>>>();
+        let prefs = PreferencesBuilder::new()
+            .block_size(BlockSize::Max64KB)
+            .block_mode(block_mode)
+            .block_checksum(BlockChecksum::Enabled)
+            .content_checksum(ContentChecksum::Enabled)
+            .content_size(sized as usize)
+            .build();
+        let mut comp_buf = Vec::new();
+        lz4f::compress_to_vec(&chunks.concat(), &mut comp_buf, &prefs).unwrap();
+        (chunks, comp_buf)
+    }
+
+    /// Returns the ranges of the blocks in a frame made by `blocks`.
+    fn block_ranges(frame: &[u8]) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let mut pos = if frame[4] & 0x08 != 0 { 15 } else { 7 };
+        loop {
+            let size = u32::from_le_bytes(frame[pos..][..4].try_into().unwrap()) & 0x7fff_ffff;
+            if size == 0 {
+                return ranges;
+            }
+            let end = pos + 4 + size as usize + 4;
+            ranges.push(pos..end);
+            pos = end;
+        }
+    }
+
+    fn salvage(comp_buf: &[u8]) -> (Vec<u8>, Vec<Damage>) {
+        let mut r = SalvageDecompressor::new(comp_buf).unwrap();
+        let mut decomp_buf = Vec::new();
+        r.read_to_end(&mut decomp_buf).unwrap();
+        (decomp_buf, r.damage().to_vec())
+    }
+
+    fn to_u64(range: Range<usize>) -> Range<u64> {
+        range.start as u64..range.end as u64
+    }
+
+    #[test]
+    fn default() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            let (decomp_buf, damage) = salvage(&comp_buf);
+            assert_eq!(decomp_buf, [src.clone(), src].concat());
+            assert!(damage.is_empty());
+        });
+    }
+
+    #[test]
+    fn dictionary() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            let mut decomp_buf = Vec::new();
+            let dict = SmallRng::seed_from_u64(0)
+                .sample_iter(Standard)
+                .take(64_000)
+                .collect::<Vec<_>>();
+            {
+                let mut w = WriteCompressor::with_dict(
+                    &mut comp_buf,
+                    prefs,
+                    Dictionary::new(&dict).unwrap(),
+                )
+                .unwrap();
+                w.write_all(&src).unwrap();
+            }
+            let mut r = SalvageDecompressor::new(comp_buf.as_slice()).unwrap();
+            r.set_dict(&dict);
+            r.read_to_end(&mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+            assert!(r.damage().is_empty());
+        });
+    }
+
+    #[test]
+    fn limits() {
+        flaky_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            for (limits, ok) in limits_set(src.len()) {
+                let mut decomp_buf = Vec::new();
+                let mut r = SalvageDecompressor::new(comp_buf.as_slice()).unwrap();
+                r.set_limits(limits);
+                let result = r.read_to_end(&mut decomp_buf).map(|_| decomp_buf);
+                assert_limits(result, &src, ok);
+                assert!(r.damage().is_empty());
+            }
+        });
+    }
+
+    #[test]
+    fn independent_blocks() {
+        let (chunks, mut comp_buf) = blocks(BlockMode::Independent, true);
+        let ranges = block_ranges(&comp_buf);
+        comp_buf[ranges[1].start + 100] ^= 0xff;
+
+        let (decomp_buf, damage) = salvage(&comp_buf);
+        assert_eq!(
+            decomp_buf,
+            [&chunks[0], &chunks[2], &chunks[3]]
+                .map(Vec::as_slice)
+                .concat()
+        );
+        assert_eq!(damage.len(), 1);
+        assert_eq!(
+            damage[0].error().kind(),
+            lzzzz::ErrorKind::Lz4f(lz4f::ErrorKind::BlockChecksumInvalid)
+        );
+        assert_eq!(damage[0].input_range(), to_u64(ranges[1].clone()));
+        assert_eq!(damage[0].output_offset(), CHUNK_SIZE as u64);
+        assert_eq!(
+            damage[0].output_range(),
+            Some(CHUNK_SIZE as u64..2 * CHUNK_SIZE as u64)
+        );
+
+        let err = damage[0].error();
+        assert_eq!(err.frame_index(), Some(0));
+        assert_eq!(err.block_index(), Some(1));
+        assert_eq!(err.input_offset(), Some(ranges[1].start as u64));
+        assert_ne!(err.expected_checksum(), err.actual_checksum());
+        assert!(err.expected_checksum().is_some());
+    }
+
+    #[test]
+    fn broken_block_header() {
+        let (chunks, mut comp_buf) = blocks(BlockMode::Independent, true);
+        let ranges = block_ranges(&comp_buf);
+        comp_buf[ranges[2].start + 3] = 0x7f;
+
+        let (decomp_buf, damage) = salvage(&comp_buf);
+        assert_eq!(
+            decomp_buf,
+            [&chunks[0], &chunks[1], &chunks[3]]
+                .map(Vec::as_slice)
+                .concat()
+        );
+        assert_eq!(damage.len(), 1);
+        assert_eq!(damage[0].input_range(), to_u64(ranges[2].clone()));
+        assert_eq!(damage[0].output_offset(), 2 * CHUNK_SIZE as u64);
+    }
+
+    #[test]
+    fn linked_blocks() {
+        let (chunks, mut comp_buf) = blocks(BlockMode::Linked, true);
+        let ranges = block_ranges(&comp_buf);
+        let frame_len = comp_buf.len();
+        comp_buf[ranges[1].start + 100] ^= 0xff;
+        let (_, next_frame) = blocks(BlockMode::Linked, true);
+        comp_buf.extend_from_slice(&next_frame);
+
+        let (decomp_buf, damage) = salvage(&comp_buf);
+        assert_eq!(decomp_buf, [chunks[0].clone(), chunks.concat()].concat());
+        assert_eq!(damage.len(), 1);
+        assert_eq!(damage[0].input_range(), to_u64(ranges[1].start..frame_len));
+        assert_eq!(damage[0].output_offset(), CHUNK_SIZE as u64);
+        assert_eq!(
+            damage[0].output_range(),
+            Some(CHUNK_SIZE as u64..4 * CHUNK_SIZE as u64)
+        );
+    }
+
+    #[test]
+    fn output_range() {
+        for sized in [true, false] {
+            let (_, frame) = blocks(BlockMode::Independent, sized);
+            let ranges = block_ranges(&frame);
+            let mut comp_buf = frame.clone();
+            comp_buf[ranges[0].start + 100] ^= 0xff;
+            comp_buf[ranges[2].start + 100] ^= 0xff;
+            comp_buf.extend_from_slice(&[0xaa; 1000]);
+            comp_buf.extend_from_slice(&frame);
+            let len = comp_buf.len();
+            comp_buf[len - frame.len() + ranges[1].start + 100] ^= 0xff;
+
+            let (decomp_buf, damage) = salvage(&comp_buf);
+            assert_eq!(decomp_buf.len(), 5 * CHUNK_SIZE);
+            let chunk = CHUNK_SIZE as u64;
+            let expected = if sized {
+                vec![
+                    Some(0..chunk),
+                    Some(2 * chunk..3 * chunk),
+                    Some(4 * chunk..4 * chunk),
+                    Some(5 * chunk..6 * chunk),
+                ]
+            } else {
+                vec![None; 4]
+            };
+            assert_eq!(
+                damage.iter().map(Damage::output_range).collect::<Vec<_>>(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn fake_block_headers() {
+        let (chunks, frame) = blocks(BlockMode::Independent, true);
+        let ranges = block_ranges(&frame);
+        // Each fake header points to the next one, so every fake block gets decoded
+        // unless the decompressor bounds its work.
+        let fake = (CHUNK_SIZE as u32 - 8).to_le_bytes().repeat(64 << 10);
+        let comp_buf = [&frame[..ranges[1].start], &fake, &frame[ranges[2].start..]].concat();
+
+        let (decomp_buf, damage) = salvage(&comp_buf);
+        assert!(decomp_buf.starts_with(&chunks[0]));
+        assert!(decomp_buf.ends_with(&chunks[3]));
+        assert!(!damage.is_empty());
+    }
+
+    #[test]
+    fn garbage() {
+        let (chunks, frame) = blocks(BlockMode::Linked, true);
+        let garbage = vec![0xaa; 1000];
+        let comp_buf = [&garbage, &frame, &garbage, &frame]
+            .map(Vec::as_slice)
+            .concat();
+
+        let (decomp_buf, damage) = salvage(&comp_buf);
+        assert_eq!(decomp_buf, [chunks.concat(), chunks.concat()].concat());
+        assert_eq!(
+            damage
+                .iter()
+                .map(|damage| (damage.error().kind(), damage.input_range()))
+                .collect::<Vec<_>>(),
+            [0, garbage.len() + frame.len()].map(|start| (
+                lzzzz::ErrorKind::Lz4f(lz4f::ErrorKind::FrameTypeUnknown),
+                to_u64(start..start + garbage.len())
+            ))
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let (chunks, comp_buf) = blocks(BlockMode::Independent, true);
+        let ranges = block_ranges(&comp_buf);
+        let comp_buf = &comp_buf[..ranges[2].start + 100];
+
+        let (decomp_buf, damage) = salvage(comp_buf);
+        assert_eq!(decomp_buf, chunks[..2].concat());
+        assert_eq!(damage.len(), 1);
+        assert_eq!(
+            damage[0].error().kind(),
+            lzzzz::ErrorKind::CompressedDataIncomplete
+        );
+        assert_eq!(
+            damage[0].input_range(),
+            to_u64(ranges[2].start..comp_buf.len())
+        );
+    }
+}
+
+mod copy {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            let comp_stats = lz4f::copy_compress(&mut src.as_ref(), &mut comp_buf, &prefs).unwrap();
+            assert_eq!(comp_stats.bytes_in(), src.len() as u64);
+            assert_eq!(comp_stats.bytes_out(), comp_buf.len() as u64);
+
+            let mut decomp_buf = Vec::new();
+            let stats = lz4f::copy_decompress(&mut comp_buf.as_slice(), &mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+            assert_eq!(stats.bytes_in(), comp_stats.bytes_out());
+            assert_eq!(stats.bytes_out(), comp_stats.bytes_in());
+            assert_eq!(stats.blocks(), comp_stats.blocks());
+            assert_eq!(stats.stored_blocks(), comp_stats.stored_blocks());
+            assert_eq!(stats.frames(), 1);
+        });
+    }
+
+    #[test]
+    fn stored_blocks() {
+        let src = SmallRng::seed_from_u64(0)
+            .sample_iter(Standard)
+            .take(300_000)
+            .collect::<Vec<u8>>();
+        let mut comp_buf = Vec::new();
+        let stats =
+            lz4f::copy_compress(&mut src.as_slice(), &mut comp_buf, &Default::default()).unwrap();
+        assert_eq!(stats.blocks(), 5);
+        assert_eq!(stats.stored_blocks(), 5);
+
+        let mut decomp_buf = Vec::new();
+        let stats = lz4f::copy_decompress(&mut comp_buf.as_slice(), &mut decomp_buf).unwrap();
+        assert_eq!(stats.stored_blocks(), 5);
+        assert_eq!(decomp_buf, src);
+    }
+
+    #[test]
+    fn truncated() {
+        let mut comp_buf = Vec::new();
+        lz4f::compress_to_vec(b"Hello world!", &mut comp_buf, &Default::default()).unwrap();
+        let len = comp_buf.len() - 1;
+        assert_incomplete(lz4f::copy_decompress(
+            &mut &comp_buf[..len],
+            &mut Vec::new(),
+        ));
+    }
 }
@@ -0,0 +1,295 @@
+use lzzzz::{formats::mozlz4, lz4, lz4f::DecompressionLimitsBuilder, ErrorKind};
+use rayon::{iter::ParallelBridge, prelude::*};
+use static_assertions::assert_impl_all;
+use std::{
+    fs::File,
+    io::{self, prelude::*},
+};
+
+mod common;
+use common::{lz4_test_set, retry, FlakyReader, FlakyWriter};
+
+/// A hand-encoded file holding `{"a":"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"}`.
+const SAMPLE_FILE: &[u8] = b"mozLz40\0\x28\0\0\0\x7f{\"a\":\"a\x01\0\x09\x50aaa\"}";
+
+fn error_kind(err: &io::Error) -> Option<ErrorKind> {
+    err.get_ref()
+        .and_then(|err| err.downcast_ref::<lzzzz::Error>())
+        .map(|err| err.kind())
+}
+
+assert_impl_all!(mozlz4::WriteCompressor<File>: Send);
+assert_impl_all!(mozlz4::ReadDecompressor<File>: Send);
+
+mod compress {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4_test_set().par_bridge().for_each(|(src, mode)| {
+            let mut comp_buf = vec![0; mozlz4::max_compressed_size(src.len())];
+            let mut decomp_buf = vec![0; src.len()];
+            let len = mozlz4::compress(&src, &mut comp_buf, mode).unwrap();
+            assert!(comp_buf.starts_with(mozlz4::MAGIC));
+            assert_eq!(mozlz4::decompressed_size(&comp_buf).unwrap(), src.len());
+            mozlz4::decompress(&comp_buf[..len], &mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
+    #[test]
+    fn empty() {
+        let mut comp_buf = [0; 16];
+        let len = mozlz4::compress(b"", &mut comp_buf, lz4::ACC_LEVEL_DEFAULT).unwrap();
+        assert_eq!(&comp_buf[..len], b"mozLz40\0\0\0\0\0\0");
+        assert_eq!(mozlz4::decompress(&comp_buf[..len], &mut []).unwrap(), 0);
+    }
+
+    #[test]
+    fn dst_too_small() {
+        let src = b"Hello world!";
+        for len in &[0, mozlz4::HEADER_SIZE, mozlz4::HEADER_SIZE + 1] {
+            let mut comp_buf = vec![0; *len];
+            assert_eq!(
+                mozlz4::compress(src, &mut comp_buf, lz4::ACC_LEVEL_DEFAULT)
+                    .unwrap_err()
+                    .kind(),
+                ErrorKind::CompressionFailed
+            );
+        }
+    }
+}
+
+mod compress_to_vec {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4_test_set().par_bridge().for_each(|(src, mode)| {
+            let header = &b"HEADER"[..];
+            let mut comp_buf = Vec::from(header);
+            let mut decomp_buf = Vec::from(header);
+            let len = mozlz4::compress_to_vec(&src, &mut comp_buf, mode).unwrap();
+            assert_eq!(comp_buf.len(), header.len() + len);
+            assert_eq!(
+                mozlz4::decompress_to_vec(&comp_buf[header.len()..], &mut decomp_buf).unwrap(),
+                src.len()
+            );
+            assert_eq!(&decomp_buf[..header.len()], header);
+            assert_eq!(&decomp_buf[header.len()..], &src[..]);
+        });
+    }
+}
+
+mod decompress_to_vec {
+    use super::*;
+
+    #[test]
+    fn sample() {
+        let mut buf = Vec::new();
+        mozlz4::decompress_to_vec(SAMPLE_FILE, &mut buf).unwrap();
+        assert_eq!(buf, br#"{"a":"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"}"#);
+    }
+
+    #[test]
+    fn invalid_magic() {
+        let mut file = SAMPLE_FILE.to_vec();
+        file[6] = b'1';
+        for len in &[7, 8, file.len()] {
+            let mut buf = Vec::new();
+            assert_eq!(
+                mozlz4::decompress_to_vec(&file[..*len], &mut buf)
+                    .unwrap_err()
+                    .kind(),
+                ErrorKind::FrameHeaderInvalid
+            );
+            assert!(buf.is_empty());
+        }
+    }
+
+    #[test]
+    fn truncated() {
+        for len in &[0, 8, mozlz4::HEADER_SIZE - 1] {
+            assert_eq!(
+                mozlz4::decompress_to_vec(&SAMPLE_FILE[..*len], &mut Vec::new())
+                    .unwrap_err()
+                    .kind(),
+                ErrorKind::CompressedDataIncomplete
+            );
+        }
+        for len in mozlz4::HEADER_SIZE..SAMPLE_FILE.len() {
+            let mut buf = b"HEADER".to_vec();
+            assert_eq!(
+                mozlz4::decompress_to_vec(&SAMPLE_FILE[..len], &mut buf)
+                    .unwrap_err()
+                    .kind(),
+                ErrorKind::DecompressionFailed
+            );
+            assert_eq!(buf, b"HEADER");
+        }
+    }
+
+    #[test]
+    fn wrong_size() {
+        for size in &[0x27u32, 0x29, u32::MAX] {
+            let mut file = SAMPLE_FILE.to_vec();
+            file[8..12].copy_from_slice(&size.to_le_bytes());
+            assert_eq!(
+                mozlz4::decompress_to_vec(&file, &mut Vec::new())
+                    .unwrap_err()
+                    .kind(),
+                ErrorKind::DecompressionFailed
+            );
+        }
+    }
+
+    #[test]
+    fn limits() {
+        let limits = |size| {
+            DecompressionLimitsBuilder::new()
+                .max_output_size(size)
+                .build()
+        };
+        let mut buf = Vec::new();
+        mozlz4::decompress_to_vec_with_limits(SAMPLE_FILE, &mut buf, &limits(0x28)).unwrap();
+        assert_eq!(buf.len(), 0x28);
+        assert_eq!(
+            mozlz4::decompress_to_vec_with_limits(SAMPLE_FILE, &mut buf, &limits(0x27))
+                .unwrap_err()
+                .kind(),
+            ErrorKind::DecompressionLimitExceeded
+        );
+    }
+}
+
+mod write_compressor {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4_test_set().par_bridge().for_each(|(src, mode)| {
+            let mut w = mozlz4::WriteCompressor::new(Vec::new());
+            w.set_acceleration(mode);
+            for chunk in src.chunks(1000) {
+                w.write_all(chunk).unwrap();
+            }
+            let comp_buf = w.finish().unwrap();
+
+            let mut decomp_buf = Vec::new();
+            mozlz4::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
+    #[test]
+    fn drop() {
+        let mut comp_buf = Vec::new();
+        {
+            let mut w = mozlz4::WriteCompressor::new(&mut comp_buf);
+            w.write_all(b"Hello world!").unwrap();
+        }
+        let mut decomp_buf = Vec::new();
+        mozlz4::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+        assert_eq!(decomp_buf, b"Hello world!");
+    }
+
+    #[test]
+    fn flaky() {
+        let src = b"The quick brown fox jumps over the lazy dog. ".repeat(5000);
+        let mut w = mozlz4::WriteCompressor::new(FlakyWriter::new(
+            Vec::new(),
+            io::ErrorKind::WouldBlock,
+            7,
+        ));
+        w.write_all(&src).unwrap();
+        retry(|| w.try_finish()).unwrap();
+        assert_eq!(
+            error_kind(&w.write(b"!").unwrap_err()),
+            Some(ErrorKind::CompressionFailed)
+        );
+        w.get_mut().err = None;
+        let comp_buf = w.finish().unwrap().inner;
+
+        let mut decomp_buf = Vec::new();
+        mozlz4::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+        assert_eq!(decomp_buf, src);
+    }
+}
+
+mod read_decompressor {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4_test_set().par_bridge().for_each(|(src, mode)| {
+            let mut comp_buf = Vec::new();
+            mozlz4::compress_to_vec(&src, &mut comp_buf, mode).unwrap();
+
+            let mut r = mozlz4::ReadDecompressor::new(comp_buf.as_slice());
+            let mut decomp_buf = Vec::new();
+            r.read_to_end(&mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
+    #[test]
+    fn sample() {
+        let mut r = mozlz4::ReadDecompressor::new(SAMPLE_FILE);
+        let mut buf = String::new();
+        r.read_to_string(&mut buf).unwrap();
+        assert_eq!(buf, r#"{"a":"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"}"#);
+    }
+
+    #[test]
+    fn flaky() {
+        let src = b"The quick brown fox jumps over the lazy dog. ".repeat(5000);
+        let mut comp_buf = Vec::new();
+        mozlz4::compress_to_vec(&src, &mut comp_buf, lz4::ACC_LEVEL_DEFAULT).unwrap();
+
+        let mut r = mozlz4::ReadDecompressor::new(FlakyReader::new(
+            comp_buf.as_slice(),
+            io::ErrorKind::WouldBlock,
+            5,
+        ));
+        let mut decomp_buf = Vec::new();
+        let mut buf = [0; 1000];
+        loop {
+            match retry(|| r.read(&mut buf)).unwrap() {
+                0 => break,
+                len => decomp_buf.extend_from_slice(&buf[..len]),
+            }
+        }
+        assert_eq!(decomp_buf, src);
+    }
+
+    #[test]
+    fn limits() {
+        let mut r = mozlz4::ReadDecompressor::new(SAMPLE_FILE);
+        r.set_limits(
+            DecompressionLimitsBuilder::new()
+                .max_output_size(0x27)
+                .build(),
+        );
+        assert_eq!(
+            error_kind(&r.read(&mut [0; 64]).unwrap_err()),
+            Some(ErrorKind::DecompressionLimitExceeded)
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let mut trailing = SAMPLE_FILE.to_vec();
+        trailing.resize(mozlz4::HEADER_SIZE + lz4::max_compressed_size(0x28) + 1, 0);
+        let cases = vec![
+            (&b"mozLz41\0"[..], ErrorKind::FrameHeaderInvalid),
+            (&SAMPLE_FILE[..10], ErrorKind::CompressedDataIncomplete),
+            (&SAMPLE_FILE[..20], ErrorKind::DecompressionFailed),
+            (&trailing[..], ErrorKind::DecompressionFailed),
+        ];
+        for (file, kind) in cases {
+            let mut r = mozlz4::ReadDecompressor::new(file);
+            let err = r.read_to_end(&mut Vec::new()).unwrap_err();
+            assert_eq!(error_kind(&err), Some(kind));
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use lzzzz::{lz4, lz4_hc, lz4f, tune, tune::Goal};
+
+fn sample() -> Vec<u8> {
+    b"The quick brown fox jumps over the lazy dog. ".repeat(300)
+}
+
+mod measure {
+    use super::*;
+
+    #[test]
+    fn lz4() {
+        let data = sample();
+        let measurements = tune::measure_lz4(&[&data]).unwrap();
+        assert_eq!(measurements[0].setting(), lz4::ACC_LEVEL_DEFAULT);
+        assert!(measurements
+            .iter()
+            .all(|m| m.ratio() > 1.0 && m.throughput() > 0.0));
+    }
+
+    #[test]
+    fn lz4_hc() {
+        let data = sample();
+        let levels = tune::measure_lz4_hc(&[&data])
+            .unwrap()
+            .iter()
+            .map(|m| m.setting())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            levels,
+            (lz4_hc::CLEVEL_MIN..=lz4_hc::CLEVEL_MAX).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn lz4f() {
+        let data = sample();
+        let measurements = tune::measure_lz4f(&[&data, b"", &data[..100]]).unwrap();
+        // The samples fit in the smallest block size, so the larger ones are skipped.
+        assert!(measurements
+            .iter()
+            .all(|m| m.setting().frame_info().block_size() == lz4f::BlockSize::Max64KB));
+        assert!(measurements
+            .iter()
+            .any(|m| m.setting().compression_level() == lz4f::CLEVEL_MAX));
+        assert!(measurements.iter().all(|m| m.ratio() > 1.0));
+    }
+
+    #[test]
+    fn empty() {
+        assert!(tune::measure_lz4(&[])
+            .unwrap()
+            .iter()
+            .all(|m| m.ratio() == 0.0 && m.throughput() == 0.0));
+        assert_eq!(
+            tune::lz4_acceleration(&[b""], Goal::Ratio(0.5)).unwrap(),
+            None
+        );
+    }
+}
+
+mod recommend {
+    use super::*;
+
+    #[test]
+    fn throughput() {
+        let data = sample();
+        assert!(tune::lz4_acceleration(&[&data], Goal::Throughput(0.0))
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            tune::lz4_hc_level(&[&data], Goal::Throughput(f64::INFINITY)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn ratio() {
+        let data = sample();
+        let prefs = tune::lz4f_preferences(&[&data], Goal::Ratio(1.0))
+            .unwrap()
+            .unwrap();
+        let mut comp_buf = Vec::new();
+        lz4f::compress_to_vec(&data, &mut comp_buf, &prefs).unwrap();
+        assert!(comp_buf.len() < data.len());
+
+        assert_eq!(
+            tune::lz4f_preferences(&[&data], Goal::Ratio(f64::INFINITY)).unwrap(),
+            None
+        );
+    }
+}
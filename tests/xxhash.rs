@@ -0,0 +1,119 @@
+use lzzzz::{lz4f, xxhash, xxhash::*};
+use rand::{distributions::Standard, rngs::SmallRng, Rng, SeedableRng};
+use rayon::{iter::ParallelBridge, prelude::*};
+use std::hash::Hasher;
+
+mod common;
+use common::lz4f_test_set;
+
+fn random_data(len: usize) -> Vec<u8> {
+    SmallRng::seed_from_u64(0)
+        .sample_iter(Standard)
+        .take(len)
+        .collect()
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+    u32::from_le_bytes(data[..4].try_into().unwrap())
+}
+
+mod xxh32 {
+    use super::*;
+
+    #[test]
+    fn known_values() {
+        assert_eq!(xxhash::xxh32(b"", 0), 0x02cc_5d05);
+        assert_eq!(xxhash::xxh32(b"abc", 0), 0x32d1_53ff);
+        assert_ne!(xxhash::xxh32(b"abc", 1), xxhash::xxh32(b"abc", 0));
+    }
+
+    #[test]
+    fn streaming() {
+        let data = random_data(100_000);
+        for seed in [0, 1, u32::MAX] {
+            for chunk in [1, 15, 16, 1000, data.len()] {
+                let mut hasher = Xxh32::new(seed);
+                data.chunks(chunk).for_each(|chunk| hasher.update(chunk));
+                assert_eq!(hasher.digest(), xxhash::xxh32(&data, seed));
+                assert_eq!(hasher.finish(), u64::from(xxhash::xxh32(&data, seed)));
+
+                hasher.reset();
+                assert_eq!(hasher.digest(), xxhash::xxh32(b"", seed));
+            }
+        }
+    }
+}
+
+mod xxh64 {
+    use super::*;
+
+    #[test]
+    fn known_values() {
+        assert_eq!(xxhash::xxh64(b"", 0), 0xef46_db37_51d8_e999);
+        assert_eq!(xxhash::xxh64(b"abc", 0), 0x44bc_2cf5_ad77_0999);
+        assert_ne!(xxhash::xxh64(b"abc", 1), xxhash::xxh64(b"abc", 0));
+    }
+
+    #[test]
+    fn streaming() {
+        let data = random_data(100_000);
+        for seed in [0, 1, u64::MAX] {
+            for chunk in [1, 31, 32, 1000, data.len()] {
+                let mut hasher = Xxh64::new(seed);
+                data.chunks(chunk).for_each(|chunk| hasher.update(chunk));
+                assert_eq!(hasher.digest(), xxhash::xxh64(&data, seed));
+                assert_eq!(hasher.finish(), xxhash::xxh64(&data, seed));
+
+                let clone = hasher.clone();
+                hasher.reset();
+                assert_eq!(hasher.digest(), xxhash::xxh64(b"", seed));
+                assert_eq!(clone.digest(), xxhash::xxh64(&data, seed));
+            }
+        }
+    }
+}
+
+mod lz4f_checksums {
+    use super::*;
+    use lzzzz::lz4f::{BlockChecksum, ContentChecksum, PreferencesBuilder};
+
+    #[test]
+    fn default() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let prefs = PreferencesBuilder::from(prefs)
+                .block_checksum(BlockChecksum::Enabled)
+                .content_checksum(ContentChecksum::Enabled)
+                .content_size(src.len())
+                .build();
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            // Magic number, FLG, BD, the optional content size and dictionary ID,
+            // and the header checksum.
+            let flags = comp_buf[4];
+            let header_len = 4
+                + 2
+                + if flags & 0x08 != 0 { 8 } else { 0 }
+                + if flags & 0x01 != 0 { 4 } else { 0 }
+                + 1;
+            assert_eq!(
+                lz4f_header_checksum(&comp_buf[4..header_len - 1]),
+                comp_buf[header_len - 1]
+            );
+
+            let block_len = (read_u32(&comp_buf[header_len..]) & 0x7fff_ffff) as usize;
+            if block_len > 0 {
+                let block = &comp_buf[header_len + 4..][..block_len];
+                assert_eq!(
+                    lz4f_block_checksum(block),
+                    read_u32(&comp_buf[header_len + 4 + block_len..])
+                );
+            }
+
+            assert_eq!(
+                lz4f_content_checksum(&src),
+                read_u32(&comp_buf[comp_buf.len() - 4..])
+            );
+        });
+    }
+}